@@ -5,6 +5,7 @@ use async_graphql::Schema;
 use async_graphql_warp::{graphql_subscription, Response};
 use rpc::edge_registry::EdgeRegistryConnectionManager;
 use rpc::materializer_ondemand::OnDemandMaterializerConnectionManager;
+use rpc::object_builder::ObjectBuilderConnectionManager;
 use rpc::schema_registry::SchemaRegistryConnectionManager;
 use warp::{http::Response as HttpResponse, hyper::header::CONTENT_TYPE, hyper::Method, Filter};
 
@@ -50,11 +51,19 @@ async fn main() -> anyhow::Result<()> {
         .await
         .unwrap();
 
+    let ob_pool = bb8::Pool::builder()
+        .build(ObjectBuilderConnectionManager {
+            address: settings.services.object_builder_url.clone(),
+        })
+        .await
+        .unwrap();
+
     let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
         .data(settings)
         .data(sr_pool)
         .data(er_pool)
         .data(odm_pool)
+        .data(ob_pool)
         .data(MQEvents {
             events: Default::default(),
         })
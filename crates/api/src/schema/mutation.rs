@@ -5,10 +5,11 @@ use crate::types::view::{NewView, View, ViewUpdate};
 use crate::{error::Error, settings::Settings};
 use crate::{types::view::FullView, types::IntoQueried};
 use async_graphql::{Context, FieldResult, Object};
-use cdl_dto::ingestion::OwnedInsertMessage;
+use cdl_dto::ingestion::{Operation, OwnedInsertMessage};
 use cdl_dto::TryIntoRpc;
 use misc_utils::current_timestamp;
 use rpc::edge_registry::EdgeRegistryPool;
+use rpc::object_builder::ObjectBuilderPool;
 use rpc::schema_registry::SchemaRegistryPool;
 use serde_json::value::to_raw_value;
 use uuid::Uuid;
@@ -121,6 +122,20 @@ impl MutationRoot {
         get_view(&mut conn, id).await
     }
 
+    #[tracing::instrument(skip(self, context))]
+    /// Rebuild every object of the view and send it to its materializer
+    async fn refresh_view(&self, context: &Context<'_>, view_id: Uuid) -> FieldResult<bool> {
+        let mut conn = context.data_unchecked::<ObjectBuilderPool>().get().await?;
+
+        conn.refresh_view(rpc::object_builder::ViewId {
+            view_id: view_id.to_string(),
+        })
+        .await
+        .map_err(|source| rpc::error::ClientError::QueryError { source })?;
+
+        Ok(true)
+    }
+
     #[tracing::instrument(skip(self, context))]
     async fn update_schema(
         &self,
@@ -150,6 +165,9 @@ impl MutationRoot {
             schema_id: message.schema_id,
             data: to_raw_value(&message.payload.0).unwrap(), // serde_json::Value -> RawValue should never fail
             timestamp: current_timestamp(),
+            operation: Operation::Insert,
+            idempotency_key: message.idempotency_key,
+            repositories: None,
         })?;
 
         publisher
@@ -179,6 +197,10 @@ impl MutationRoot {
                 schema_id: message.schema_id,
                 data: to_raw_value(&message.payload.0).unwrap(), // serde_json::Value -> RawValue should never fail
                 timestamp: current_timestamp(),
+                operation: Operation::Insert,
+                idempotency_key: message.idempotency_key,
+                repositories: None,
+            repositories: None,
             })?;
 
             publisher
@@ -193,6 +215,69 @@ impl MutationRoot {
         Ok(true)
     }
 
+    #[tracing::instrument(skip(self, context))]
+    /// Apply payload to the latest version of the object as JSON merge patch (RFC 7396)
+    async fn patch_message(
+        &self,
+        context: &Context<'_>,
+        message: InputMessage,
+    ) -> FieldResult<bool> {
+        let publisher = context.data_unchecked::<Settings>().publisher().await?;
+
+        let payload = serde_json::to_vec(&OwnedInsertMessage {
+            version: message.version,
+            object_id: message.object_id,
+            schema_id: message.schema_id,
+            data: to_raw_value(&message.payload.0).unwrap(), // serde_json::Value -> RawValue should never fail
+            timestamp: current_timestamp(),
+            operation: Operation::Patch,
+            idempotency_key: message.idempotency_key,
+            repositories: None,
+        })?;
+
+        publisher
+            .publish_message(
+                &context.data_unchecked::<Settings>().insert_destination,
+                &message.object_id.to_string(),
+                payload,
+            )
+            .await
+            .map_err(Error::PublisherError)?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(skip(self, context))]
+    /// Delete object from its repository and retract it from materialized views
+    async fn delete_object(
+        &self,
+        context: &Context<'_>,
+        object_id: Uuid,
+        schema_id: Uuid,
+    ) -> FieldResult<bool> {
+        let publisher = context.data_unchecked::<Settings>().publisher().await?;
+
+        let payload = serde_json::to_vec(&OwnedInsertMessage {
+            version: "1.0".to_owned(),
+            object_id,
+            schema_id,
+            data: to_raw_value(&serde_json::Value::Null).unwrap(), // serde_json::Value -> RawValue should never fail
+            timestamp: current_timestamp(),
+            operation: Operation::Delete,
+            idempotency_key: None,
+            repositories: None,
+        })?;
+
+        publisher
+            .publish_message(
+                &context.data_unchecked::<Settings>().insert_destination,
+                &object_id.to_string(),
+                payload,
+            )
+            .await
+            .map_err(Error::PublisherError)?;
+        Ok(true)
+    }
+
     #[tracing::instrument(skip(self, context))]
     /// Add new relation, return generated `relation_id`
     async fn add_relation(
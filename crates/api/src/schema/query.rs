@@ -6,14 +6,14 @@ use semver::VersionReq;
 use uuid::Uuid;
 
 use crate::schema::utils::{get_schema, get_view};
-use crate::types::data::{CdlObject, EdgeRelations, SchemaRelation};
+use crate::types::data::{CdlObject, EdgeRelations, ObjectPage, SchemaPageQuery, SchemaRelation};
 use crate::types::schema::{Definition, FullSchema};
 use crate::types::view::View;
 use crate::types::view::{MaterializedView, RowDefinition};
 use crate::{error::Result, types::view::FullView};
 use crate::{settings::Settings, types::view::OnDemandViewRequest};
 use rpc::edge_registry::EdgeRegistryPool;
-use rpc::materializer_ondemand::{OnDemandMaterializerPool, OnDemandRequest};
+use rpc::materializer_ondemand::OnDemandMaterializerPool;
 use rpc::schema_registry::types::SchemaType;
 use rpc::schema_registry::SchemaRegistryPool;
 use tracing_utils::http::RequestBuilderTracingExt;
@@ -47,6 +47,11 @@ impl FullSchema {
         self.schema_type
     }
 
+    /// JSON pointers to fields, from which data-router derives ids of objects inserted without them.
+    async fn natural_key(&self) -> &[String] {
+        &self.natural_key
+    }
+
     /// Returns schema definition for given version.
     /// Schema is following semantic versioning, querying for "2.1.0" will return "2.1.1" if exist,
     /// querying for "=2.1.0" will return "2.1.0" if exist
@@ -208,6 +213,37 @@ impl QueryRoot {
             .collect::<Vec<CdlObject>>())
     }
 
+    /// Return single page of objects in a schema, filtered and ordered by their payloads.
+    /// Supported only by document storage backed by Postgres
+    #[tracing::instrument(skip(self, context))]
+    async fn schema_objects_page(
+        &self,
+        context: &Context<'_>,
+        schema_id: Uuid,
+        query: SchemaPageQuery,
+    ) -> FieldResult<ObjectPage> {
+        let client = reqwest::Client::new();
+
+        let page: cdl_dto::query::ObjectPage = client
+            .post(&format!(
+                "{}/schema/page",
+                &context
+                    .data_unchecked::<Settings>()
+                    .services
+                    .query_router_url,
+            ))
+            .header("SCHEMA_ID", schema_id.to_string())
+            .json(&query.into_dto())
+            .inject_span()
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(page.into())
+    }
+
     /// Return schema `parent` is in `relation_id` relation with
     #[tracing::instrument(skip(self, context))]
     async fn relation(
@@ -337,10 +373,7 @@ impl QueryRoot {
             .get()
             .await?;
         let view_id = request.view_id;
-        let materialized = conn
-            .materialize(OnDemandRequest::from(request))
-            .await?
-            .into_inner();
+        let materialized = conn.materialize(request.into_rpc()?).await?.into_inner();
 
         let rows = materialized
             .map_err(async_graphql::Error::from)
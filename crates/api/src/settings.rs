@@ -23,6 +23,7 @@ pub struct ServicesSettings {
     pub schema_registry_url: String,
     pub edge_registry_url: String,
     pub on_demand_materializer_url: String,
+    pub object_builder_url: String,
     pub query_router_url: String,
 }
 
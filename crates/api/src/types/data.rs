@@ -1,4 +1,5 @@
 use async_graphql::{InputObject, Json, SimpleObject};
+use cdl_dto::query::{self, Predicate};
 use serde_json::Value;
 use uuid::Uuid;
 
@@ -11,6 +12,8 @@ pub struct InputMessage {
     pub schema_id: Uuid,
     /// JSON-encoded payload
     pub payload: Json<Value>,
+    /// Optional key, message is stored only once even when sent multiple times
+    pub idempotency_key: Option<String>,
 }
 
 #[derive(serde::Deserialize, SimpleObject)]
@@ -56,3 +59,81 @@ impl ObjectRelations {
         }
     }
 }
+
+#[derive(Debug, InputObject)]
+pub struct SchemaPageQuery {
+    /// Maximal number of objects in the page, all objects are returned when missing
+    pub limit: Option<u64>,
+    /// Return objects following given object
+    pub after: Option<PageCursor>,
+    /// Field used to order objects. Objects with equal values are ordered by their IDs
+    pub order_by: Option<PageOrdering>,
+    /// Condition on payloads, eg. `{"and": [{"equals": {"path": "/a", "value": 1}}, {"exists": {"path": "/b"}}]}`
+    pub filter: Option<Json<Predicate>>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct PageOrdering {
+    /// JSON pointer to the field
+    pub path: String,
+    #[graphql(default)]
+    pub descending: bool,
+}
+
+/// Last object of the previous page
+#[derive(Debug, InputObject)]
+pub struct PageCursor {
+    pub object_id: Uuid,
+    /// Value of the ordering field. Required when `orderBy` is present
+    pub value: Option<Json<Value>>,
+}
+
+#[derive(SimpleObject)]
+pub struct ObjectPage {
+    pub objects: Vec<CdlObject>,
+    /// Last object of the page, missing when there are no more objects
+    pub next: Option<LastObject>,
+}
+
+#[derive(SimpleObject)]
+pub struct LastObject {
+    pub object_id: Uuid,
+    /// Value of the ordering field
+    pub value: Option<Json<Value>>,
+}
+
+impl SchemaPageQuery {
+    pub fn into_dto(self) -> query::SchemaQuery {
+        query::SchemaQuery {
+            limit: self.limit,
+            after: self.after.map(|after| query::Cursor {
+                object_id: after.object_id,
+                value: after.value.map(|value| value.0),
+            }),
+            order_by: self.order_by.map(|order_by| query::Ordering {
+                path: order_by.path,
+                descending: order_by.descending,
+            }),
+            filter: self.filter.map(|filter| filter.0),
+        }
+    }
+}
+
+impl From<query::ObjectPage> for ObjectPage {
+    fn from(page: query::ObjectPage) -> Self {
+        Self {
+            objects: page
+                .objects
+                .into_iter()
+                .map(|object| CdlObject {
+                    object_id: object.object_id,
+                    data: Json(object.payload),
+                })
+                .collect(),
+            next: page.next.map(|next| LastObject {
+                object_id: next.object_id,
+                value: next.value.map(Json),
+            }),
+        }
+    }
+}
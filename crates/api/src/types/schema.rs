@@ -14,6 +14,7 @@ pub struct FullSchema {
     pub insert_destination: String,
     pub query_address: String,
     pub schema_type: SchemaType,
+    pub natural_key: Vec<String>,
 
     pub definitions: Vec<Definition>,
     pub views: Vec<View>,
@@ -41,6 +42,7 @@ impl FullSchema {
             insert_destination: schema.metadata.insert_destination,
             query_address: schema.metadata.query_address,
             schema_type,
+            natural_key: schema.metadata.natural_key,
             definitions: schema
                 .definitions
                 .into_iter()
@@ -86,6 +88,10 @@ pub struct NewSchema {
     /// Whether the schema stores documents or timeseries data.
     #[graphql(name = "type")]
     pub schema_type: SchemaType,
+    /// JSON pointers to fields, from which ids of objects inserted without them are derived.
+    /// When empty, data-router generates random ids.
+    #[graphql(default)]
+    pub natural_key: Vec<String>,
 }
 
 impl NewSchema {
@@ -96,6 +102,7 @@ impl NewSchema {
                 schema_type: self.schema_type.into(),
                 insert_destination: self.insert_destination,
                 query_address: self.query_address,
+                natural_key: self.natural_key,
             },
             definition: serde_json::to_vec(&self.definition)?,
         })
@@ -126,6 +133,8 @@ pub struct UpdateSchema {
     /// Whether the schema stores documents or timeseries data.
     #[graphql(name = "type")]
     pub schema_type: Option<SchemaType>,
+    /// JSON pointers to fields, from which ids of objects inserted without them are derived.
+    pub natural_key: Option<Vec<String>>,
 }
 
 impl UpdateSchema {
@@ -137,6 +146,9 @@ impl UpdateSchema {
                 insert_destination: self.insert_destination,
                 query_address: self.query_address,
                 schema_type: self.schema_type.map(Into::into),
+                natural_key: self
+                    .natural_key
+                    .map(|fields| rpc::schema_registry::NaturalKey { fields }),
             },
         }
     }
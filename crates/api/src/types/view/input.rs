@@ -142,6 +142,18 @@ pub struct OnDemandViewRequest {
     pub view_id: Uuid,
     /// Schemas with objects. This collection is treated like a hash-map with `schemaId` as a key, therefore `schemaId` should be unique per request.
     pub schemas: Vec<Schema>,
+    /// Additional filter, applied together with view's filters
+    pub filter: Option<Json<Filter>>,
+    /// Fields to return. All fields are returned if not present
+    pub fields: Option<Vec<String>>,
+    /// Field used to order rows. Rows with equal values are ordered by object IDs
+    pub order_by: Option<OnDemandOrdering>,
+    /// Maximal number of rows to return
+    pub limit: Option<u64>,
+    /// Number of rows to skip. Cannot be used together with `after`
+    pub offset: Option<u64>,
+    /// Return rows following given row. Cannot be used together with `offset`
+    pub after: Option<OnDemandCursor>,
 }
 
 #[derive(Debug, Clone, InputObject)]
@@ -152,9 +164,28 @@ pub struct Schema {
     pub object_ids: Vec<Uuid>,
 }
 
-impl From<OnDemandViewRequest> for rpc::materializer_ondemand::OnDemandRequest {
-    fn from(val: OnDemandViewRequest) -> Self {
-        let schemas = val
+#[derive(Debug, Clone, InputObject)]
+pub struct OnDemandOrdering {
+    /// Name of the view's field
+    pub field: String,
+    #[graphql(default)]
+    pub descending: bool,
+}
+
+/// Last row of the previous page
+#[derive(Debug, Clone, InputObject)]
+pub struct OnDemandCursor {
+    /// Object IDs of the row
+    pub object_ids: Vec<Uuid>,
+    /// Value of the ordering field. Required when `orderBy` is present
+    pub value: Option<Json<Value>>,
+}
+
+impl OnDemandViewRequest {
+    pub fn into_rpc(self) -> FieldResult<rpc::materializer_ondemand::OnDemandRequest> {
+        use rpc::materializer_ondemand::on_demand_request::Page;
+
+        let schemas = self
             .schemas
             .into_iter()
             .map(|schema| {
@@ -170,9 +201,33 @@ impl From<OnDemandViewRequest> for rpc::materializer_ondemand::OnDemandRequest {
                 )
             })
             .collect();
-        Self {
-            view_id: val.view_id.to_string(),
+
+        let page = match (self.offset, self.after) {
+            (Some(_), Some(_)) => return Err("`offset` and `after` are mutually exclusive".into()),
+            (Some(offset), None) => Some(Page::Offset(offset)),
+            (None, Some(after)) => Some(Page::After(rpc::materializer_ondemand::Cursor {
+                object_ids: after.object_ids.iter().map(|id| id.to_string()).collect(),
+                value: after
+                    .value
+                    .map(|value| serde_json::to_string(&value.0))
+                    .transpose()?,
+            })),
+            (None, None) => None,
+        };
+
+        Ok(rpc::materializer_ondemand::OnDemandRequest {
+            view_id: self.view_id.to_string(),
             schemas,
-        }
+            filter: self.filter.map(|f| f.0.try_into_rpc()).transpose()?,
+            fields: self.fields.unwrap_or_default(),
+            order_by: self
+                .order_by
+                .map(|order_by| rpc::materializer_ondemand::Ordering {
+                    field: order_by.field,
+                    descending: Some(order_by.descending),
+                }),
+            limit: self.limit,
+            page,
+        })
     }
 }
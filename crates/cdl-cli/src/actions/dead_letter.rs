@@ -0,0 +1,106 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use cdl_dto::ingestion::DeadLetterMessage;
+use communication_utils::consumer::{CommonConsumer, CommonConsumerConfig, ConsumerHandler};
+use communication_utils::message::CommunicationMessage;
+use communication_utils::publisher::CommonPublisher;
+
+struct ReplayHandler {
+    publisher: CommonPublisher,
+    destination: String,
+    last_received: Arc<Mutex<Instant>>,
+}
+
+#[async_trait]
+impl ConsumerHandler for ReplayHandler {
+    async fn handle<'a>(&'a mut self, msg: &'a dyn CommunicationMessage) -> anyhow::Result<()> {
+        *self.last_received.lock().unwrap() = Instant::now();
+
+        let dead_letter: DeadLetterMessage = serde_json::from_str(msg.payload()?)?;
+        let key = dead_letter.message.object_id.to_string();
+
+        self.publisher
+            .publish_message(
+                &self.destination,
+                &key,
+                serde_json::to_vec(&dead_letter.message)?,
+            )
+            .await?;
+
+        eprintln!(
+            "Replayed object {} (failed {} times: {})",
+            key, dead_letter.attempts, dead_letter.error
+        );
+
+        Ok(())
+    }
+}
+
+pub async fn replay_dead_letters(
+    kafka_brokers: Option<String>,
+    kafka_group_id: String,
+    amqp_url: Option<String>,
+    source: String,
+    destination: String,
+    idle_timeout_secs: u64,
+) -> anyhow::Result<()> {
+    let (consumer, publisher) = match (&kafka_brokers, &amqp_url) {
+        (Some(brokers), _) => (
+            CommonConsumer::new(CommonConsumerConfig::Kafka {
+                brokers,
+                group_id: &kafka_group_id,
+                topic: &source,
+            })
+            .await?,
+            CommonPublisher::new_kafka(brokers).await?,
+        ),
+        (None, Some(amqp_url)) => (
+            CommonConsumer::new(CommonConsumerConfig::Amqp {
+                connection_string: amqp_url,
+                consumer_tag: "cdl-cli-replay",
+                queue_name: &source,
+                options: None,
+            })
+            .await?,
+            CommonPublisher::new_amqp(amqp_url).await?,
+        ),
+        (None, None) => anyhow::bail!("Either Kafka brokers or AMQP url has to be provided"),
+    };
+
+    let last_received = Arc::new(Mutex::new(Instant::now()));
+    let handler = ReplayHandler {
+        publisher,
+        destination,
+        last_received: last_received.clone(),
+    };
+
+    let assigned = consumer.assigned();
+    let idle_timeout = Duration::from_secs(idle_timeout_secs);
+    let idle = async {
+        // Timeout starts once consumer joined its group, so slow rebalance doesn't end the replay
+        while !assigned.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        *last_received.lock().unwrap() = Instant::now();
+
+        loop {
+            let deadline = *last_received.lock().unwrap() + idle_timeout;
+            if Instant::now() >= deadline {
+                break;
+            }
+            tokio::time::sleep_until(deadline.into()).await;
+        }
+    };
+
+    tokio::select! {
+        result = consumer.run(handler) => result?,
+        _ = idle => {},
+    }
+
+    eprintln!("No dead-lettered messages left to replay.");
+
+    Ok(())
+}
@@ -1,2 +1,3 @@
+pub mod dead_letter;
 pub mod schema;
 pub mod view;
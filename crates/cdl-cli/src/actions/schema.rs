@@ -7,8 +7,8 @@ use uuid::Uuid;
 
 use crate::utils::*;
 use rpc::schema_registry::{
-    types::SchemaType, Empty, Id, NewSchema, NewSchemaVersion, SchemaDefinition, SchemaMetadata,
-    SchemaMetadataPatch, SchemaMetadataUpdate, ValueToValidate, VersionedId,
+    types::SchemaType, Empty, Id, NaturalKey, NewSchema, NewSchemaVersion, SchemaDefinition,
+    SchemaMetadata, SchemaMetadataPatch, SchemaMetadataUpdate, ValueToValidate, VersionedId,
 };
 
 pub async fn get_schema_definition(
@@ -38,6 +38,7 @@ pub async fn add_schema(
     query_address: String,
     file: Option<PathBuf>,
     schema_type: SchemaType,
+    natural_key: Vec<String>,
     registry_addr: String,
 ) -> anyhow::Result<()> {
     let definition = read_json(file)?;
@@ -50,6 +51,7 @@ pub async fn add_schema(
                 query_address,
                 insert_destination,
                 schema_type: schema_type.into(),
+                natural_key,
             },
             definition: serde_json::to_vec(&definition)?,
         })
@@ -94,6 +96,7 @@ pub async fn update_schema(
     insert_destination: Option<String>,
     query_address: Option<String>,
     schema_type: Option<SchemaType>,
+    natural_key: Option<Vec<String>>,
     registry_addr: String,
 ) -> anyhow::Result<()> {
     let mut client = rpc::schema_registry::connect(registry_addr).await?;
@@ -105,6 +108,7 @@ pub async fn update_schema(
                 insert_destination,
                 query_address,
                 schema_type: schema_type.map(|t| t.into()),
+                natural_key: natural_key.map(|fields| NaturalKey { fields }),
             },
         })
         .await?;
@@ -162,6 +166,7 @@ pub async fn get_schema_metadata(id: Uuid, registry_addr: String) -> anyhow::Res
     println!("Topic or Queue: {}", metadata.insert_destination);
     println!("Query Address: {}", metadata.query_address);
     println!("Type: {}", schema_type);
+    println!("Natural Key: {}", metadata.natural_key.join(", "));
 
     Ok(())
 }
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use cdl_dto::materialization::{Filter, Relation};
 use cdl_dto::TryIntoRpc;
+use rpc::object_builder::ViewId;
 use rpc::schema_registry::{Id, NewView, ViewUpdate};
 use uuid::Uuid;
 
@@ -141,3 +142,16 @@ pub async fn get_schema_views(schema_id: Uuid, registry_addr: String) -> anyhow:
 
     Ok(())
 }
+
+pub async fn refresh_view(view_id: Uuid, object_builder_addr: String) -> anyhow::Result<()> {
+    let mut client = rpc::object_builder::connect(object_builder_addr).await?;
+    client
+        .refresh_view(ViewId {
+            view_id: view_id.to_string(),
+        })
+        .await?;
+
+    eprintln!("Successfully refreshed view.");
+
+    Ok(())
+}
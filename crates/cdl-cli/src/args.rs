@@ -31,6 +31,12 @@ pub enum Action {
         #[clap(subcommand)]
         action: ViewAction,
     },
+
+    /// Work with messages which command services failed to store.
+    DeadLetter {
+        #[clap(subcommand)]
+        action: DeadLetterAction,
+    },
 }
 
 #[derive(Clap)]
@@ -84,6 +90,10 @@ pub enum SchemaAction {
         /// The type of schema. Possible values: DocumentStorage, Timeseries.
         #[clap(short, long = "type", default_value = "DocumentStorage")]
         schema_type: SchemaType,
+        /// Comma separated JSON pointers to fields, from which ids of objects
+        /// inserted without them are derived.
+        #[clap(long, use_delimiter = true)]
+        natural_key: Vec<String>,
     },
 
     /// Add a new version of an existing schema in the registry.
@@ -117,6 +127,10 @@ pub enum SchemaAction {
         /// The new type of the schema. Possible values: DocumentStorage, Timeseries.
         #[clap(short, long = "type")]
         schema_type: Option<SchemaType>,
+        /// The new comma separated JSON pointers to fields, from which ids of objects
+        /// inserted without them are derived.
+        #[clap(long, use_delimiter = true)]
+        natural_key: Option<Vec<String>>,
     },
 
     /// Validate that a JSON value is valid under the format of the
@@ -220,4 +234,39 @@ pub enum ViewAction {
         #[clap(short, long)]
         update_relations: bool,
     },
+
+    /// Rebuild every object of the view and send it to its materializer.
+    Refresh {
+        /// The id of the view.
+        #[clap(short, long)]
+        id: Uuid,
+        /// The address where the object builder is hosted.
+        #[clap(short, long)]
+        object_builder_addr: String,
+    },
+}
+
+#[derive(Clap)]
+pub enum DeadLetterAction {
+    /// Consume dead-lettered messages and publish them again, until no new one arrives.
+    Replay {
+        /// Kafka brokers. If not provided, AMQP is used.
+        #[clap(long)]
+        kafka_brokers: Option<String>,
+        /// Kafka consumer group of the replay.
+        #[clap(long, default_value = "cdl-cli-replay")]
+        kafka_group_id: String,
+        /// AMQP connection string.
+        #[clap(long)]
+        amqp_url: Option<String>,
+        /// The Kafka topic or AMQP queue containing dead-lettered messages.
+        #[clap(short, long)]
+        source: String,
+        /// The Kafka topic or AMQP exchange messages are published to, eg. command service input.
+        #[clap(short, long)]
+        destination: String,
+        /// Replay stops after no message was received for this many seconds.
+        #[clap(short, long, default_value = "10")]
+        idle_timeout_secs: u64,
+    },
 }
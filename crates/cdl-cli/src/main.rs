@@ -2,6 +2,7 @@ pub mod actions;
 pub mod args;
 pub mod utils;
 
+use actions::dead_letter::*;
 use actions::schema::*;
 use actions::view::*;
 use args::*;
@@ -26,6 +27,7 @@ pub async fn main() -> anyhow::Result<()> {
                 query_address,
                 file,
                 schema_type,
+                natural_key,
             } => {
                 add_schema(
                     name,
@@ -33,6 +35,7 @@ pub async fn main() -> anyhow::Result<()> {
                     query_address,
                     file,
                     schema_type,
+                    natural_key,
                     args.registry_addr,
                 )
                 .await
@@ -46,6 +49,7 @@ pub async fn main() -> anyhow::Result<()> {
                 insert_destination,
                 query_address,
                 schema_type,
+                natural_key,
             } => {
                 update_schema(
                     id,
@@ -53,6 +57,7 @@ pub async fn main() -> anyhow::Result<()> {
                     insert_destination,
                     query_address,
                     schema_type,
+                    natural_key,
                     args.registry_addr,
                 )
                 .await
@@ -116,6 +121,30 @@ pub async fn main() -> anyhow::Result<()> {
                 )
                 .await
             }
+            ViewAction::Refresh {
+                id,
+                object_builder_addr,
+            } => refresh_view(id, object_builder_addr).await,
+        },
+        Action::DeadLetter { action } => match action {
+            DeadLetterAction::Replay {
+                kafka_brokers,
+                kafka_group_id,
+                amqp_url,
+                source,
+                destination,
+                idle_timeout_secs,
+            } => {
+                replay_dead_letters(
+                    kafka_brokers,
+                    kafka_group_id,
+                    amqp_url,
+                    source,
+                    destination,
+                    idle_timeout_secs,
+                )
+                .await
+            }
         },
     }
 }
@@ -0,0 +1,29 @@
+-- RFC 7396 JSON merge patch, used by command service to apply `patch` operation
+-- and by db-shrinker-postgres to merge patched versions.
+-- Recursive call is schema-qualified, so the function does not depend on `search_path` of the caller.
+CREATE OR REPLACE FUNCTION {schema}.json_merge_patch(target JSONB, patch JSONB) RETURNS JSONB AS $$
+BEGIN
+    IF jsonb_typeof(patch) IS DISTINCT FROM 'object' THEN
+        RETURN patch;
+    END IF;
+    IF jsonb_typeof(target) IS DISTINCT FROM 'object' THEN
+        target := '{}'::JSONB;
+    END IF;
+    RETURN COALESCE(
+        (SELECT jsonb_object_agg(key, value) FROM (
+            SELECT t.key, t.value
+            FROM jsonb_each(target) t
+            WHERE NOT patch ? t.key
+            UNION ALL
+            SELECT p.key, {schema}.json_merge_patch(target -> p.key, p.value)
+            FROM jsonb_each(patch) p
+            WHERE jsonb_typeof(p.value) <> 'null'
+        ) merged),
+        '{}'::JSONB
+    );
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+-- Patch applied by the version, `payload` holds the merged document.
+-- Shrinker applies it instead of `payload`, so fields removed by the patch are not brought back.
+ALTER TABLE {schema}.data ADD COLUMN IF NOT EXISTS patch JSONB;
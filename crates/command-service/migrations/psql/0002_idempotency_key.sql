@@ -0,0 +1,4 @@
+-- Client supplied key, only the first version with given key is stored for the object.
+-- Messages without idempotency key are never considered duplicates, as NULLs are distinct.
+ALTER TABLE {schema}.data ADD COLUMN IF NOT EXISTS idempotency_key TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS data_idempotency_key ON {schema}.data (object_id, idempotency_key);
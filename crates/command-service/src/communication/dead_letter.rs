@@ -0,0 +1,48 @@
+use crate::communication::resolution::Resolution;
+use cdl_dto::ingestion::{BorrowedInsertMessage, DeadLetterMessage, OwnMessage};
+use communication_utils::publisher::CommonPublisher;
+use metrics_utils::{self as metrics, counter};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Publishes messages which could not be stored, so they can be replayed later
+#[derive(Clone)]
+pub struct DeadLetterPublisher {
+    publisher: CommonPublisher,
+    destination: Arc<String>,
+}
+
+impl DeadLetterPublisher {
+    pub fn new(publisher: CommonPublisher, destination: String) -> Self {
+        Self {
+            publisher,
+            destination: Arc::new(destination),
+        }
+    }
+
+    pub async fn publish(
+        &self,
+        msg: &BorrowedInsertMessage<'_>,
+        status: &Resolution,
+        attempts: u32,
+    ) -> anyhow::Result<()> {
+        warn!(
+            "Sending message to dead-letter destination after {} attempts: {}",
+            attempts, status
+        );
+
+        let payload = serde_json::to_vec(&DeadLetterMessage {
+            message: msg.to_owned_message(),
+            error: status.to_string(),
+            attempts,
+        })?;
+
+        self.publisher
+            .publish_message(&self.destination, &msg.object_id.to_string(), payload)
+            .await?;
+
+        counter!("cdl.command-service.dead-letter", 1);
+
+        Ok(())
+    }
+}
@@ -1,17 +1,22 @@
 use crate::communication::resolution::Resolution;
 use crate::output::OutputPlugin;
+use crate::settings::RetrySettings;
 use cdl_dto::ingestion::{BorrowedInsertMessage, OwnedInsertMessage};
+use dead_letter::DeadLetterPublisher;
 use metrics_utils::*;
 use std::sync::Arc;
-use tracing::trace;
+use tracing::{trace, warn};
 use utils::notification::NotificationPublisher;
 
 pub mod config;
+pub mod dead_letter;
 pub mod resolution;
 
 pub struct MessageRouter<P: OutputPlugin> {
     notification_sender: NotificationPublisher<OwnedInsertMessage>,
     output_plugin: Arc<P>,
+    retry: Option<Arc<RetrySettings>>,
+    dead_letter: Option<DeadLetterPublisher>,
 }
 
 impl<P: OutputPlugin> Clone for MessageRouter<P> {
@@ -19,38 +24,66 @@ impl<P: OutputPlugin> Clone for MessageRouter<P> {
         MessageRouter {
             notification_sender: self.notification_sender.clone(),
             output_plugin: self.output_plugin.clone(),
+            retry: self.retry.clone(),
+            dead_letter: self.dead_letter.clone(),
         }
     }
 }
 
 impl<P: OutputPlugin> MessageRouter<P> {
-    pub fn new(report_sender: NotificationPublisher<OwnedInsertMessage>, output_plugin: P) -> Self {
+    pub fn new(
+        report_sender: NotificationPublisher<OwnedInsertMessage>,
+        output_plugin: P,
+        retry: Option<RetrySettings>,
+        dead_letter: Option<DeadLetterPublisher>,
+    ) -> Self {
         Self {
             notification_sender: report_sender,
             output_plugin: Arc::new(output_plugin),
+            retry: retry.map(Arc::new),
+            dead_letter,
         }
     }
 
     #[tracing::instrument(skip(self, msg))]
-    pub async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> anyhow::Result<()> {
+    pub async fn handle_message(&self, mut msg: BorrowedInsertMessage<'_>) -> anyhow::Result<()> {
         let instance = self.notification_sender.clone().with_message_body(&msg);
 
-        let status = self.output_plugin.handle_message(msg).await;
+        let mut attempts = 1;
+        let mut status = self.output_plugin.handle_message(msg.clone()).await;
+        while let Some(retry) = &self.retry {
+            if !status.is_retriable() || attempts > retry.max_retries {
+                break;
+            }
 
-        trace!("Finished processing a message with resolution `{}`", status);
+            let backoff = retry.backoff(attempts);
+            warn!(
+                "Storing message failed with `{}`, retrying in {:?}",
+                status, backoff
+            );
+            counter!("cdl.command-service.retry", 1);
 
-        match status {
-            Resolution::StorageLayerFailure { .. } => {
-                counter!("cdl.command-service.post-process.storage-failure", 1);
-            }
-            Resolution::CommandServiceFailure => {
-                counter!("cdl.command-service.post-process.command-failure", 1);
-            }
-            Resolution::UserFailure { .. } => {
-                counter!("cdl.command-service.post-process.user-failure", 1);
+            tokio::time::sleep(backoff).await;
+            attempts += 1;
+
+            // Repositories which already stored the message are not written again
+            if let Some(failed) = status.failed_repositories() {
+                msg.repositories = Some(failed);
             }
-            Resolution::Success => {
-                counter!("cdl.command-service.post-process.success", 1);
+            let retried = self.output_plugin.handle_message(msg.clone()).await;
+            status = status.merge_retry(retried);
+        }
+
+        trace!("Finished processing a message with resolution `{}`", status);
+
+        count_resolution(&status);
+
+        if !status.is_success() {
+            if let Some(dead_letter) = &self.dead_letter {
+                if let Some(failed) = status.failed_repositories() {
+                    msg.repositories = Some(failed);
+                }
+                dead_letter.publish(&msg, &status, attempts).await?;
             }
         }
 
@@ -59,3 +92,161 @@ impl<P: OutputPlugin> MessageRouter<P> {
         Ok(())
     }
 }
+
+fn count_resolution(status: &Resolution) {
+    match status {
+        Resolution::StorageLayerFailure { .. } => {
+            counter!("cdl.command-service.post-process.storage-failure", 1);
+        }
+        Resolution::CommandServiceFailure => {
+            counter!("cdl.command-service.post-process.command-failure", 1);
+        }
+        Resolution::UserFailure { .. } => {
+            counter!("cdl.command-service.post-process.user-failure", 1);
+        }
+        Resolution::Success => {
+            counter!("cdl.command-service.post-process.success", 1);
+        }
+        Resolution::Combined { resolutions, .. } => {
+            for (_, resolution) in resolutions {
+                count_resolution(resolution);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::CompositeOutputPlugin;
+    use crate::settings::CompositePolicy;
+    use serde_json::value::RawValue;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use uuid::Uuid;
+
+    /// Fails with storage failure `failures` times, then succeeds
+    struct FlakyPlugin {
+        name: &'static str,
+        failures: u32,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait::async_trait]
+    impl OutputPlugin for FlakyPlugin {
+        async fn handle_message(&self, _msg: BorrowedInsertMessage<'_>) -> Resolution {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Resolution::StorageLayerFailure {
+                    description: "timeout".to_string(),
+                }
+            } else {
+                Resolution::Success
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_only_failed_repositories_of_combined_resolution() {
+        let first_calls = Arc::new(AtomicU32::new(0));
+        let second_calls = Arc::new(AtomicU32::new(0));
+        let router = MessageRouter::new(
+            NotificationPublisher::Disabled,
+            CompositeOutputPlugin::new(
+                vec![
+                    Box::new(FlakyPlugin {
+                        name: "first",
+                        failures: 0,
+                        calls: first_calls.clone(),
+                    }),
+                    Box::new(FlakyPlugin {
+                        name: "second",
+                        failures: 2,
+                        calls: second_calls.clone(),
+                    }),
+                ],
+                CompositePolicy::All,
+            ),
+            Some(RetrySettings {
+                max_retries: 3,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+            }),
+            None,
+        );
+        let data = RawValue::from_string("{}".to_string()).unwrap();
+
+        router
+            .handle_message(BorrowedInsertMessage {
+                object_id: Uuid::nil(),
+                schema_id: Uuid::nil(),
+                timestamp: 0,
+                data: &data,
+                operation: Default::default(),
+                idempotency_key: None,
+                repositories: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(first_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(second_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn combined_resolution_keeps_successful_repositories_after_retry() {
+        let previous = Resolution::Combined {
+            success: false,
+            resolutions: vec![
+                ("first", Resolution::Success),
+                (
+                    "second",
+                    Resolution::StorageLayerFailure {
+                        description: "timeout".to_string(),
+                    },
+                ),
+            ],
+        };
+        assert!(previous.is_retriable());
+        assert_eq!(
+            previous.failed_repositories(),
+            Some(vec!["second".to_string()])
+        );
+
+        let merged = previous.merge_retry(Resolution::Combined {
+            success: true,
+            resolutions: vec![("second", Resolution::Success)],
+        });
+
+        assert_eq!(
+            merged.to_string(),
+            "success [first: success] [second: success]"
+        );
+    }
+
+    #[test]
+    fn combined_resolution_with_user_failure_is_not_retried() {
+        let resolution = Resolution::Combined {
+            success: false,
+            resolutions: vec![
+                (
+                    "first",
+                    Resolution::UserFailure {
+                        description: "invalid".to_string(),
+                        context: "{}".to_string(),
+                    },
+                ),
+                (
+                    "second",
+                    Resolution::StorageLayerFailure {
+                        description: "timeout".to_string(),
+                    },
+                ),
+            ],
+        };
+
+        assert!(!resolution.is_retriable());
+    }
+}
@@ -12,6 +12,82 @@ pub enum Resolution {
         context: String,
     },
     Success,
+    /// Resolutions of every backend written by composite plugin,
+    /// `success` is decided by its policy
+    Combined {
+        success: bool,
+        resolutions: Vec<(&'static str, Resolution)>,
+    },
+}
+
+impl Resolution {
+    pub fn is_success(&self) -> bool {
+        match self {
+            Resolution::Success => true,
+            Resolution::Combined { success, .. } => *success,
+            _ => false,
+        }
+    }
+
+    /// Storage failures can be retried. Combined resolution is retried only when all its failures can be,
+    /// otherwise message fails regardless of the retry.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            Resolution::StorageLayerFailure { .. } => true,
+            Resolution::Combined {
+                success: false,
+                resolutions,
+            } => resolutions
+                .iter()
+                .filter(|(_, resolution)| !resolution.is_success())
+                .all(|(_, resolution)| resolution.is_retriable()),
+            _ => false,
+        }
+    }
+
+    /// Names of repositories which failed to store the message, `None` when it's not combined resolution
+    pub fn failed_repositories(&self) -> Option<Vec<String>> {
+        match self {
+            Resolution::Combined { resolutions, .. } => Some(
+                resolutions
+                    .iter()
+                    .filter(|(_, resolution)| !resolution.is_success())
+                    .map(|(name, _)| name.to_string())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Replaces resolutions of repositories written again by `retried` attempt.
+    /// Only failed repositories are retried, so the attempt decides about overall success.
+    pub fn merge_retry(self, retried: Resolution) -> Resolution {
+        match (self, retried) {
+            (
+                Resolution::Combined {
+                    resolutions: previous,
+                    ..
+                },
+                Resolution::Combined {
+                    success,
+                    resolutions: retried,
+                },
+            ) => Resolution::Combined {
+                success,
+                resolutions: previous
+                    .into_iter()
+                    .map(|(name, resolution)| {
+                        retried
+                            .iter()
+                            .find(|(retried_name, _)| *retried_name == name)
+                            .cloned()
+                            .unwrap_or((name, resolution))
+                    })
+                    .collect(),
+            },
+            (_, retried) => retried,
+        }
+    }
 }
 
 impl fmt::Display for Resolution {
@@ -26,6 +102,16 @@ impl fmt::Display for Resolution {
                 context,
             } => write!(f, "{}; caused by {}", description, context),
             Resolution::Success => write!(f, "success"),
+            Resolution::Combined {
+                success,
+                resolutions,
+            } => {
+                write!(f, "{}", if *success { "success" } else { "failure" })?;
+                for (name, resolution) in resolutions {
+                    write!(f, " [{}: {}]", name, resolution)?;
+                }
+                Ok(())
+            }
         }
     }
 }
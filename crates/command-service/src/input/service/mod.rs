@@ -40,8 +40,11 @@ where
     #[tracing::instrument(skip(self, msg))]
     async fn handle<'a>(&'a self, msg: &'a dyn CommunicationMessage) -> anyhow::Result<()> {
         let order_group_id = get_order_group_id(msg);
-        let _guard = order_group_id
-            .map(move |x| async move { self.task_queue.acquire_permit(x.to_string()).await });
+        // Messages of the same order group are processed one after another
+        let _guard = match order_group_id {
+            Some(order_group_id) => Some(self.task_queue.acquire_permit(order_group_id).await),
+            None => None,
+        };
 
         counter!("cdl.command-service.input-request", 1);
 
@@ -1,13 +1,15 @@
 #![feature(async_closure)]
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use cdl_dto::ingestion::OwnedInsertMessage;
+use command_service::communication::dead_letter::DeadLetterPublisher;
 use command_service::communication::MessageRouter;
 use command_service::input::{Error, Service};
 use command_service::output::{
-    DruidOutputPlugin, OutputPlugin, PostgresOutputPlugin, VictoriaMetricsOutputPlugin,
+    CompositeOutputPlugin, DruidOutputPlugin, OutputPlugin, PostgresOutputPlugin,
+    RocksDbOutputPlugin, VictoriaMetricsOutputPlugin,
 };
-use command_service::settings::{RepositoryKind, Settings};
+use command_service::settings::{RepositoryKind, RetrySettings, Settings};
 use communication_utils::parallel_consumer::ParallelCommonConsumer;
 use metrics_utils as metrics;
 use settings_utils::load_settings;
@@ -38,41 +40,45 @@ async fn main() -> anyhow::Result<()> {
         )
         .await?;
 
-    match (
-        settings.postgres,
-        settings.victoria_metrics,
-        settings.druid,
-        settings.repository_kind,
-    ) {
-        (Some(postgres), _, _, RepositoryKind::Postgres) => {
+    let dead_letter = match &settings.dead_letter {
+        Some(dead_letter) => Some(DeadLetterPublisher::new(
+            settings.publisher().await?,
+            dead_letter.destination.clone(),
+        )),
+        None => None,
+    };
+
+    match settings.repository_kind {
+        RepositoryKind::Composite => {
+            let composite = settings
+                .composite
+                .as_ref()
+                .context("Composite setup requires [composite] section")?;
+
+            let mut plugins = vec![];
+            for kind in composite.repositories()? {
+                plugins.push(output_plugin(&settings, kind).await?);
+            }
+
             start_services(
                 consumers,
                 notification_publisher,
-                PostgresOutputPlugin::new(postgres).await?,
+                settings.retry.clone(),
+                dead_letter,
+                CompositeOutputPlugin::new(plugins, composite.policy),
             )
             .await
         }
-        (_, Some(victoria_metrics), _, RepositoryKind::VictoriaMetrics) => {
+        kind => {
             start_services(
                 consumers,
                 notification_publisher,
-                VictoriaMetricsOutputPlugin::new(victoria_metrics)?,
+                settings.retry.clone(),
+                dead_letter,
+                output_plugin(&settings, kind).await?,
             )
             .await
         }
-        (_, _, Some(druid), RepositoryKind::Druid) => {
-            if let Some(kafka) = settings.kafka {
-                start_services(
-                    consumers,
-                    notification_publisher,
-                    DruidOutputPlugin::new(druid, &kafka.brokers).await?,
-                )
-                .await
-            } else {
-                bail!("Druid setup requires [kafka] section")
-            }
-        }
-        _ => bail!("Unsupported consumer specification"),
     }?;
 
     Ok(())
@@ -81,9 +87,11 @@ async fn main() -> anyhow::Result<()> {
 async fn start_services(
     communication_config: Vec<ParallelCommonConsumer>,
     notification_publisher: NotificationPublisher<OwnedInsertMessage>,
+    retry: Option<RetrySettings>,
+    dead_letter: Option<DeadLetterPublisher>,
     output: impl OutputPlugin,
 ) -> Result<(), Error> {
-    let message_router = MessageRouter::new(notification_publisher, output);
+    let message_router = MessageRouter::new(notification_publisher, output, retry, dead_letter);
 
     debug!("Starting command service on a message-queue");
     Service::new(communication_config, message_router)
@@ -93,3 +101,36 @@ async fn start_services(
 
     Ok(())
 }
+
+async fn output_plugin(
+    settings: &Settings,
+    kind: RepositoryKind,
+) -> anyhow::Result<Box<dyn OutputPlugin>> {
+    Ok(
+        match (
+            &settings.postgres,
+            &settings.victoria_metrics,
+            &settings.druid,
+            &settings.rocksdb,
+            kind,
+        ) {
+            (Some(postgres), _, _, _, RepositoryKind::Postgres) => Box::new(
+                PostgresOutputPlugin::new(postgres.clone(), settings.batching.clone()).await?,
+            ),
+            (_, Some(victoria_metrics), _, _, RepositoryKind::VictoriaMetrics) => {
+                Box::new(VictoriaMetricsOutputPlugin::new(victoria_metrics.clone())?)
+            }
+            (_, _, Some(druid), _, RepositoryKind::Druid) => {
+                if let Some(kafka) = &settings.kafka {
+                    Box::new(DruidOutputPlugin::new(druid.clone(), &kafka.brokers).await?)
+                } else {
+                    bail!("Druid setup requires [kafka] section")
+                }
+            }
+            (_, _, _, Some(rocksdb), RepositoryKind::RocksDb) => {
+                Box::new(RocksDbOutputPlugin::new(rocksdb.clone())?)
+            }
+            _ => bail!("Unsupported consumer specification"),
+        },
+    )
+}
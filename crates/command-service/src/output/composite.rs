@@ -0,0 +1,174 @@
+use crate::communication::resolution::Resolution;
+use crate::output::OutputPlugin;
+use crate::settings::CompositePolicy;
+use cdl_dto::ingestion::BorrowedInsertMessage;
+use futures::future::join_all;
+
+/// Writes every message to all configured backends, or only to ones listed in message `repositories`
+pub struct CompositeOutputPlugin {
+    plugins: Vec<Box<dyn OutputPlugin>>,
+    policy: CompositePolicy,
+}
+
+impl CompositeOutputPlugin {
+    pub fn new(plugins: Vec<Box<dyn OutputPlugin>>, policy: CompositePolicy) -> Self {
+        Self { plugins, policy }
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputPlugin for CompositeOutputPlugin {
+    async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> Resolution {
+        // Message naming no configured repository would otherwise be written nowhere and reported as stored
+        if let Some(names) = &msg.repositories {
+            let unknown: Vec<_> = names
+                .iter()
+                .filter(|name| {
+                    !self
+                        .plugins
+                        .iter()
+                        .any(|plugin| plugin.name() == name.as_str())
+                })
+                .map(String::as_str)
+                .collect();
+            if !unknown.is_empty() {
+                return Resolution::UserFailure {
+                    description: format!("Unknown repositories: {}", unknown.join(", ")),
+                    context: msg.object_id.to_string(),
+                };
+            }
+        }
+
+        let plugins: Vec<_> = self
+            .plugins
+            .iter()
+            .filter(|plugin| {
+                msg.repositories
+                    .as_ref()
+                    .map_or(true, |names| names.iter().any(|name| name == plugin.name()))
+            })
+            .collect();
+
+        let resolutions: Vec<_> = join_all(
+            plugins
+                .iter()
+                .map(|plugin| plugin.handle_message(msg.clone())),
+        )
+        .await;
+
+        let success = match self.policy {
+            CompositePolicy::All => resolutions.iter().all(Resolution::is_success),
+            CompositePolicy::BestEffort => resolutions.iter().any(Resolution::is_success),
+        };
+
+        Resolution::Combined {
+            success,
+            resolutions: plugins
+                .iter()
+                .map(|plugin| plugin.name())
+                .zip(resolutions)
+                .collect(),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "Composite"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::value::RawValue;
+    use uuid::Uuid;
+
+    struct StaticPlugin(&'static str, Resolution);
+
+    #[async_trait::async_trait]
+    impl OutputPlugin for StaticPlugin {
+        async fn handle_message(&self, _msg: BorrowedInsertMessage<'_>) -> Resolution {
+            self.1.clone()
+        }
+
+        fn name(&self) -> &'static str {
+            self.0
+        }
+    }
+
+    async fn handle(policy: CompositePolicy, repositories: Option<Vec<String>>) -> Resolution {
+        let plugin = CompositeOutputPlugin::new(
+            vec![
+                Box::new(StaticPlugin("first", Resolution::Success)),
+                Box::new(StaticPlugin(
+                    "second",
+                    Resolution::StorageLayerFailure {
+                        description: "timeout".to_string(),
+                    },
+                )),
+            ],
+            policy,
+        );
+        let data = RawValue::from_string("{}".to_string()).unwrap();
+
+        plugin
+            .handle_message(BorrowedInsertMessage {
+                object_id: Uuid::nil(),
+                schema_id: Uuid::nil(),
+                timestamp: 0,
+                data: &data,
+                operation: Default::default(),
+                idempotency_key: None,
+                repositories,
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn all_policy_fails_when_any_backend_fails() {
+        let resolution = handle(CompositePolicy::All, None).await;
+
+        assert!(!resolution.is_success());
+        assert_eq!(
+            resolution.to_string(),
+            "failure [first: success] [second: failed on database layer, `timeout`]"
+        );
+    }
+
+    #[tokio::test]
+    async fn best_effort_policy_succeeds_when_any_backend_succeeds() {
+        let resolution = handle(CompositePolicy::BestEffort, None).await;
+
+        assert!(resolution.is_success());
+        assert_eq!(
+            resolution.to_string(),
+            "success [first: success] [second: failed on database layer, `timeout`]"
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_only_listed_repositories() {
+        let resolution = handle(CompositePolicy::All, Some(vec!["second".to_string()])).await;
+
+        assert!(!resolution.is_success());
+        assert_eq!(
+            resolution.to_string(),
+            "failure [second: failed on database layer, `timeout`]"
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_repositories() {
+        let resolution = handle(
+            CompositePolicy::All,
+            Some(vec!["first".to_string(), "third".to_string()]),
+        )
+        .await;
+
+        assert!(!resolution.is_success());
+        assert!(!resolution.is_retriable());
+        assert!(matches!(
+            resolution,
+            Resolution::UserFailure { description, .. } if description == "Unknown repositories: third"
+        ));
+    }
+}
@@ -2,7 +2,7 @@ use crate::communication::resolution::Resolution;
 use crate::output::OutputPlugin;
 use crate::settings::DruidSettings;
 use anyhow::Context;
-use cdl_dto::ingestion::BorrowedInsertMessage;
+use cdl_dto::ingestion::{BorrowedInsertMessage, Operation};
 use futures::stream::{self, StreamExt};
 use metrics_utils::{self as metrics, counter};
 use rdkafka::producer::{FutureProducer, FutureRecord};
@@ -27,6 +27,8 @@ struct DruidOutputMessage {
     schema_id: Uuid,
 }
 
+/// Publishes data points to Kafka topic ingested by Druid.
+/// Druid ingestion is append-only, so patches and deletions are rejected.
 pub struct DruidOutputPlugin {
     producer: FutureProducer,
     topic: String,
@@ -87,6 +89,31 @@ impl OutputPlugin for DruidOutputPlugin {
 }
 
 fn deserialize_payloads(msg: &BorrowedInsertMessage<'_>) -> Result<Vec<Vec<u8>>, Resolution> {
+    match msg.operation {
+        Operation::Insert => {}
+        Operation::Patch => {
+            return Err(Resolution::UserFailure {
+                description: "Patch operation is not supported by timeseries repository"
+                    .to_string(),
+                context: msg.object_id.to_string(),
+            })
+        }
+        Operation::Delete => {
+            return Err(Resolution::UserFailure {
+                description: "Delete operation is not supported by Druid repository".to_string(),
+                context: msg.object_id.to_string(),
+            })
+        }
+    }
+
+    // Druid ingestion is append-only, so duplicates can't be skipped
+    if msg.idempotency_key.is_some() {
+        return Err(Resolution::UserFailure {
+            description: "Idempotency key is not supported by timeseries repository".to_string(),
+            context: msg.object_id.to_string(),
+        });
+    }
+
     let result: Result<Vec<TimeseriesInputMessage>, serde_json::Error> =
         serde_json::from_str(&msg.data.get());
     match result {
@@ -115,3 +142,55 @@ fn deserialize_payloads(msg: &BorrowedInsertMessage<'_>) -> Result<Vec<Vec<u8>>,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::value::RawValue;
+
+    fn message<'a>(data: &'a RawValue, idempotency_key: Option<&str>) -> BorrowedInsertMessage<'a> {
+        BorrowedInsertMessage {
+            object_id: Uuid::nil(),
+            schema_id: Uuid::nil(),
+            timestamp: 0,
+            data,
+            operation: Operation::Insert,
+            idempotency_key: idempotency_key.map(str::to_string),
+            repositories: None,
+        }
+    }
+
+    #[test]
+    fn rejects_idempotency_key() {
+        let data =
+            RawValue::from_string(r#"[{"fields": {"y01": 1}, "ts": 1}]"#.to_string()).unwrap();
+
+        assert_eq!(
+            deserialize_payloads(&message(&data, None)).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            deserialize_payloads(&message(&data, Some("order-1234"))),
+            Err(Resolution::UserFailure {
+                description: "Idempotency key is not supported by timeseries repository"
+                    .to_string(),
+                context: Uuid::nil().to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_delete() {
+        let data = RawValue::from_string("null".to_string()).unwrap();
+        let mut delete = message(&data, None);
+        delete.operation = Operation::Delete;
+
+        assert_eq!(
+            deserialize_payloads(&delete),
+            Err(Resolution::UserFailure {
+                description: "Delete operation is not supported by Druid repository".to_string(),
+                context: Uuid::nil().to_string(),
+            })
+        );
+    }
+}
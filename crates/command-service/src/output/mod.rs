@@ -1,11 +1,15 @@
+pub use self::rocksdb::RocksDbOutputPlugin;
 use crate::communication::resolution::Resolution;
 use cdl_dto::ingestion::BorrowedInsertMessage;
+pub use composite::CompositeOutputPlugin;
 pub use druid::DruidOutputPlugin;
 pub use psql::PostgresOutputPlugin;
 pub use victoria_metrics::VictoriaMetricsOutputPlugin;
 
+mod composite;
 mod druid;
 mod psql;
+mod rocksdb;
 mod victoria_metrics;
 
 #[async_trait::async_trait]
@@ -13,3 +17,14 @@ pub trait OutputPlugin: Send + Sync + 'static {
     async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> Resolution;
     fn name(&self) -> &'static str;
 }
+
+#[async_trait::async_trait]
+impl OutputPlugin for Box<dyn OutputPlugin> {
+    async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> Resolution {
+        self.as_ref().handle_message(msg).await
+    }
+
+    fn name(&self) -> &'static str {
+        self.as_ref().name()
+    }
+}
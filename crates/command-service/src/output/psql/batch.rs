@@ -0,0 +1,199 @@
+use super::insert;
+use crate::communication::resolution::Resolution;
+use crate::settings::BatchingSettings;
+use bb8::Pool;
+use bb8_postgres::tokio_postgres::binary_copy::BinaryCopyInWriter;
+use bb8_postgres::tokio_postgres::types::{Json, ToSql, Type};
+use bb8_postgres::tokio_postgres::NoTls;
+use bb8_postgres::PostgresConnectionManager;
+use futures::pin_mut;
+use metrics_utils::{self as metrics, counter, histogram};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{timeout_at, Instant};
+use tracing::{error, trace};
+use uuid::Uuid;
+
+/// Single row of `data` table
+#[derive(Debug)]
+pub struct Row {
+    pub object_id: Uuid,
+    pub version: i64,
+    pub schema_id: Uuid,
+    pub payload: Value,
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    row: Row,
+    resolution: oneshot::Sender<Resolution>,
+}
+
+/// Accumulates inserted rows into micro-batches, which are written with binary `COPY`.
+/// Rows are copied to temporary table first, so duplicates can be skipped when moving them to `data`.
+/// Every message still gets its own resolution, once batch containing it is stored.
+/// Output plugin doesn't know partitions of the source queue, so rows are split into `partitions`
+/// independent batches by their object id instead. Versions of a single object always go to the same batch.
+pub struct Batcher {
+    senders: Vec<mpsc::Sender<Entry>>,
+}
+
+impl Batcher {
+    pub fn new(
+        settings: BatchingSettings,
+        pool: Pool<PostgresConnectionManager<NoTls>>,
+        schema: String,
+    ) -> Self {
+        let senders = (0..settings.partitions.get())
+            .map(|_| {
+                let (sender, receiver) = mpsc::channel(settings.max_size.get());
+                tokio::spawn(run(
+                    settings.clone(),
+                    pool.clone(),
+                    schema.clone(),
+                    receiver,
+                ));
+                sender
+            })
+            .collect();
+
+        Self { senders }
+    }
+
+    pub async fn store(&self, row: Row) -> Resolution {
+        let partition = (row.object_id.as_u128() % self.senders.len() as u128) as usize;
+        let (tx, rx) = oneshot::channel();
+        if self.senders[partition]
+            .send(Entry {
+                row,
+                resolution: tx,
+            })
+            .await
+            .is_err()
+        {
+            error!("Batching task has stopped");
+            return Resolution::CommandServiceFailure;
+        }
+
+        rx.await.unwrap_or(Resolution::CommandServiceFailure)
+    }
+}
+
+async fn run(
+    settings: BatchingSettings,
+    pool: Pool<PostgresConnectionManager<NoTls>>,
+    schema: String,
+    mut receiver: mpsc::Receiver<Entry>,
+) {
+    while let Some(first) = receiver.recv().await {
+        // Batch is flushed when it's full, or when its first row waited for `max_age`
+        let deadline = Instant::now() + settings.max_age();
+        let mut batch = vec![first];
+        while batch.len() < settings.max_size.get() {
+            match timeout_at(deadline, receiver.recv()).await {
+                Ok(Some(entry)) => batch.push(entry),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        write(&pool, &schema, batch).await;
+    }
+}
+
+async fn write(pool: &Pool<PostgresConnectionManager<NoTls>>, schema: &str, batch: Vec<Entry>) {
+    trace!("Storing batch of {} rows", batch.len());
+    histogram!("cdl.command-service.batch-size.psql", batch.len() as f64);
+
+    let mut connection = match pool.get().await {
+        Ok(conn) => conn,
+        Err(err) => {
+            error!("Failed to get connection from pool {:?}", err);
+            for entry in batch {
+                entry
+                    .resolution
+                    .send(Resolution::CommandServiceFailure)
+                    .ok();
+            }
+            return;
+        }
+    };
+
+    match copy(&mut connection, schema, &batch).await {
+        Ok(stored) => {
+            counter!("cdl.command-service.store.psql", stored);
+            counter!(
+                "cdl.command-service.duplicate.psql",
+                batch.len() as u64 - stored
+            );
+            for entry in batch {
+                entry.resolution.send(Resolution::Success).ok();
+            }
+        }
+        Err(err) => {
+            // Whole batch is rolled back, rows are retried one by one to find failing messages
+            error!(
+                "PSQL `COPY` of batch failed, inserting rows separately: {}",
+                err
+            );
+            for entry in batch {
+                let resolution = insert(&connection, schema, &entry.row).await;
+                entry.resolution.send(resolution).ok();
+            }
+        }
+    }
+}
+
+async fn copy(
+    connection: &mut bb8::PooledConnection<'_, PostgresConnectionManager<NoTls>>,
+    schema: &str,
+    batch: &[Entry],
+) -> Result<u64, bb8_postgres::tokio_postgres::Error> {
+    let tx = connection.transaction().await?;
+    // Temporary table is unique per session
+    tx.batch_execute(&format!(
+        "CREATE TEMP TABLE IF NOT EXISTS inserts ON COMMIT DELETE ROWS \
+         AS TABLE {}.data WITH NO DATA",
+        schema
+    ))
+    .await?;
+    let sink = tx
+        .copy_in(
+            "COPY inserts (object_id, version, schema_id, payload, idempotency_key) \
+             FROM STDIN BINARY",
+        )
+        .await?;
+    let writer = BinaryCopyInWriter::new(
+        sink,
+        &[Type::UUID, Type::INT8, Type::UUID, Type::JSON, Type::TEXT],
+    );
+    pin_mut!(writer);
+
+    for entry in batch {
+        let payload = Json(&entry.row.payload);
+        let row: [&(dyn ToSql + Sync); 5] = [
+            &entry.row.object_id,
+            &entry.row.version,
+            &entry.row.schema_id,
+            &payload,
+            &entry.row.idempotency_key,
+        ];
+        writer.as_mut().write(&row).await?;
+    }
+    writer.finish().await?;
+
+    let stored = tx
+        .execute(
+            format!(
+                "INSERT INTO {}.data SELECT * FROM inserts ON CONFLICT DO NOTHING",
+                schema
+            )
+            .as_str(),
+            &[],
+        )
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(stored)
+}
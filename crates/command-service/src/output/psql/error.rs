@@ -8,4 +8,6 @@ pub enum Error {
         "Schema `{0}` has invalid name. It can contain only ascii letters, numbers and underscores"
     )]
     InvalidSchemaName(String),
+    #[error("Migration `{0}` failed: {1}")]
+    MigrationFailed(&'static str, String),
 }
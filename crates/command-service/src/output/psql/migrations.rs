@@ -0,0 +1,51 @@
+use super::Error;
+use bb8::Pool;
+use bb8_postgres::tokio_postgres::NoTls;
+use bb8_postgres::PostgresConnectionManager;
+use tracing::info;
+
+/// Scripts are idempotent and run in order on every start.
+/// `{schema}` is replaced with the configured schema.
+const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "json_merge_patch",
+        include_str!("../../../migrations/psql/0001_json_merge_patch.sql"),
+    ),
+    (
+        "idempotency_key",
+        include_str!("../../../migrations/psql/0002_idempotency_key.sql"),
+    ),
+];
+
+/// Brings `data` table and its helper functions up to date.
+/// Advisory lock keeps instances starting at the same time from running migrations concurrently.
+pub async fn run(pool: &Pool<PostgresConnectionManager<NoTls>>, schema: &str) -> Result<(), Error> {
+    let mut connection = pool
+        .get()
+        .await
+        .map_err(|err| Error::MigrationFailed("connection", err.to_string()))?;
+    let transaction = connection
+        .transaction()
+        .await
+        .map_err(|err| Error::MigrationFailed("transaction", err.to_string()))?;
+    transaction
+        .execute(
+            "SELECT pg_advisory_xact_lock(hashtext('cdl-command-service-migrations'))",
+            &[],
+        )
+        .await
+        .map_err(|err| Error::MigrationFailed("lock", err.to_string()))?;
+
+    for (name, script) in MIGRATIONS {
+        info!("Running migration `{}`", name);
+        transaction
+            .batch_execute(&script.replace("{schema}", schema))
+            .await
+            .map_err(|err| Error::MigrationFailed(name, err.to_string()))?;
+    }
+
+    transaction
+        .commit()
+        .await
+        .map_err(|err| Error::MigrationFailed("commit", err.to_string()))
+}
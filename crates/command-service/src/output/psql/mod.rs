@@ -1,10 +1,12 @@
 use crate::communication::resolution::Resolution;
 use crate::output::OutputPlugin;
-use bb8::Pool;
+use crate::settings::BatchingSettings;
+use batch::{Batcher, Row};
+use bb8::{Pool, PooledConnection};
 use bb8_postgres::tokio_postgres::types::Json;
 use bb8_postgres::tokio_postgres::{Config, NoTls};
 use bb8_postgres::PostgresConnectionManager;
-use cdl_dto::ingestion::BorrowedInsertMessage;
+use cdl_dto::ingestion::{BorrowedInsertMessage, Operation};
 pub use error::Error;
 use metrics_utils::{self as metrics, counter};
 use serde_json::Value;
@@ -13,15 +15,21 @@ use std::time;
 use tracing::{error, trace};
 use utils::psql::validate_schema;
 
+mod batch;
 pub mod error;
+mod migrations;
 
 pub struct PostgresOutputPlugin {
     pool: Pool<PostgresConnectionManager<NoTls>>,
     schema: String,
+    batcher: Option<Batcher>,
 }
 
 impl PostgresOutputPlugin {
-    pub async fn new(config: PostgresSettings) -> Result<Self, Error> {
+    pub async fn new(
+        config: PostgresSettings,
+        batching: Option<BatchingSettings>,
+    ) -> Result<Self, Error> {
         let mut pg_config = Config::new();
         pg_config
             .user(&config.username)
@@ -42,7 +50,15 @@ impl PostgresOutputPlugin {
             return Err(Error::InvalidSchemaName(schema));
         }
 
-        Ok(Self { pool, schema })
+        migrations::run(&pool, &schema).await?;
+
+        let batcher = batching.map(|settings| Batcher::new(settings, pool.clone(), schema.clone()));
+
+        Ok(Self {
+            pool,
+            schema,
+            batcher,
+        })
     }
 }
 
@@ -50,6 +66,17 @@ impl PostgresOutputPlugin {
 impl OutputPlugin for PostgresOutputPlugin {
     #[tracing::instrument(skip(self, msg))]
     async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> Resolution {
+        if msg.operation == Operation::Insert {
+            if let Some(batcher) = &self.batcher {
+                trace!("Batching message {:?}", msg);
+
+                return match row(&msg) {
+                    Ok(row) => batcher.store(row).await,
+                    Err(resolution) => resolution,
+                };
+            }
+        }
+
         let connection = match self.pool.get().await {
             Ok(conn) => conn,
             Err(err) => {
@@ -58,35 +85,105 @@ impl OutputPlugin for PostgresOutputPlugin {
             }
         };
 
+        match msg.operation {
+            Operation::Insert => {}
+            Operation::Delete => return self.delete(&connection, &msg).await,
+            Operation::Patch => return self.patch(&connection, &msg).await,
+        }
+
         trace!("Storing message {:?}", msg);
 
-        let payload: Value = match serde_json::from_str(&msg.data.get()) {
+        match row(&msg) {
+            Ok(row) => insert(&connection, &self.schema, &row).await,
+            Err(resolution) => resolution,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "PostgreSQL"
+    }
+}
+
+impl PostgresOutputPlugin {
+    async fn delete(
+        &self,
+        connection: &PooledConnection<'_, PostgresConnectionManager<NoTls>>,
+        msg: &BorrowedInsertMessage<'_>,
+    ) -> Resolution {
+        trace!("Deleting object {:?}", msg);
+
+        // Versions stored after the deletion was requested are kept
+        let delete_query = format!(
+            "DELETE FROM {}.data WHERE object_id = $1 AND version <= $2",
+            &self.schema
+        );
+
+        let delete_result = connection
+            .execute(delete_query.as_str(), &[&msg.object_id, &msg.timestamp])
+            .await;
+
+        trace!("PSQL `DELETE` {:?}", delete_result);
+
+        match delete_result {
+            Ok(_) => {
+                counter!("cdl.command-service.delete.psql", 1);
+
+                Resolution::Success
+            }
+            Err(err) => Resolution::StorageLayerFailure {
+                description: err.to_string(),
+            },
+        }
+    }
+
+    async fn patch(
+        &self,
+        connection: &PooledConnection<'_, PostgresConnectionManager<NoTls>>,
+        msg: &BorrowedInsertMessage<'_>,
+    ) -> Resolution {
+        trace!("Patching object {:?}", msg);
+
+        let patch: Value = match serde_json::from_str(&msg.data.get()) {
             Ok(json) => json,
             Err(_err) => return Resolution::CommandServiceFailure,
         };
 
-        let store_query = format!(
-            "INSERT INTO {}.data (object_id, version, schema_id, payload) VALUES ($1, $2, $3, $4)",
-            &self.schema
+        // Patch is applied to the latest version preceding it, result is stored as a new version
+        let patch_query = format!(
+            "INSERT INTO {schema}.data (object_id, version, schema_id, payload, idempotency_key, patch) \
+             SELECT $1::uuid, $2::bigint, $3::uuid, {schema}.json_merge_patch( \
+                 (SELECT payload::jsonb FROM {schema}.data \
+                  WHERE object_id = $1::uuid AND version < $2::bigint \
+                  ORDER BY version DESC LIMIT 1), \
+                 $4::jsonb \
+             )::json, $5::text, $4::jsonb \
+             ON CONFLICT DO NOTHING",
+            schema = &self.schema
         );
 
-        let store_result = connection
-            .query(
-                store_query.as_str(),
+        let patch_result = connection
+            .execute(
+                patch_query.as_str(),
                 &[
                     &msg.object_id,
                     &msg.timestamp,
                     &msg.schema_id,
-                    &Json(payload),
+                    &Json(patch),
+                    &msg.idempotency_key,
                 ],
             )
             .await;
 
-        trace!("PSQL `INSERT` {:?}", store_result);
+        trace!("PSQL `INSERT` patch {:?}", patch_result);
 
-        match store_result {
+        match patch_result {
+            Ok(0) => {
+                counter!("cdl.command-service.duplicate.psql", 1);
+
+                Resolution::Success
+            }
             Ok(_) => {
-                counter!("cdl.command-service.store.psql", 1);
+                counter!("cdl.command-service.patch.psql", 1);
 
                 Resolution::Success
             }
@@ -95,8 +192,64 @@ impl OutputPlugin for PostgresOutputPlugin {
             },
         }
     }
+}
 
-    fn name(&self) -> &'static str {
-        "PostgreSQL"
+fn row(msg: &BorrowedInsertMessage<'_>) -> Result<Row, Resolution> {
+    let payload: Value = match serde_json::from_str(&msg.data.get()) {
+        Ok(json) => json,
+        Err(_err) => return Err(Resolution::CommandServiceFailure),
+    };
+
+    Ok(Row {
+        object_id: msg.object_id,
+        version: msg.timestamp,
+        schema_id: msg.schema_id,
+        payload,
+        idempotency_key: msg.idempotency_key.clone(),
+    })
+}
+
+async fn insert(
+    connection: &PooledConnection<'_, PostgresConnectionManager<NoTls>>,
+    schema: &str,
+    row: &Row,
+) -> Resolution {
+    // Redelivered message has the same `idempotency_key`, or the same version when it was already timestamped
+    let store_query = format!(
+        "INSERT INTO {}.data (object_id, version, schema_id, payload, idempotency_key) \
+         VALUES ($1, $2, $3, $4, $5) \
+         ON CONFLICT DO NOTHING",
+        schema
+    );
+
+    let store_result = connection
+        .execute(
+            store_query.as_str(),
+            &[
+                &row.object_id,
+                &row.version,
+                &row.schema_id,
+                &Json(&row.payload),
+                &row.idempotency_key,
+            ],
+        )
+        .await;
+
+    trace!("PSQL `INSERT` {:?}", store_result);
+
+    match store_result {
+        Ok(0) => {
+            counter!("cdl.command-service.duplicate.psql", 1);
+
+            Resolution::Success
+        }
+        Ok(_) => {
+            counter!("cdl.command-service.store.psql", 1);
+
+            Resolution::Success
+        }
+        Err(err) => Resolution::StorageLayerFailure {
+            description: err.to_string(),
+        },
     }
 }
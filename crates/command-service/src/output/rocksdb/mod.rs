@@ -0,0 +1,90 @@
+use crate::communication::resolution::Resolution;
+use crate::output::OutputPlugin;
+use cdl_dto::ingestion::{BorrowedInsertMessage, Operation};
+use kv_utils::DocumentStore;
+use metrics_utils::{self as metrics, counter};
+use serde_json::Value;
+use settings_utils::RocksDbSettings;
+use std::sync::Arc;
+use tracing::trace;
+
+/// Embedded document storage, which needs no external database
+pub struct RocksDbOutputPlugin {
+    store: Arc<DocumentStore>,
+}
+
+impl RocksDbOutputPlugin {
+    pub fn new(settings: RocksDbSettings) -> anyhow::Result<Self> {
+        Ok(Self {
+            store: Arc::new(DocumentStore::open(settings.path)?),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl OutputPlugin for RocksDbOutputPlugin {
+    #[tracing::instrument(skip(self, msg))]
+    async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> Resolution {
+        trace!("Storing message {:?}", msg);
+
+        let payload: Value = match msg.operation {
+            Operation::Delete => Value::Null,
+            Operation::Insert | Operation::Patch => match serde_json::from_str(msg.data.get()) {
+                Ok(json) => json,
+                Err(_err) => return Resolution::CommandServiceFailure,
+            },
+        };
+        let BorrowedInsertMessage {
+            object_id,
+            schema_id,
+            timestamp,
+            operation,
+            idempotency_key,
+            ..
+        } = msg;
+        let store = self.store.clone();
+
+        // RocksDB calls and locks of the store block, so they don't run on async workers
+        let result = tokio::task::spawn_blocking(move || match operation {
+            Operation::Delete => store.delete(object_id, timestamp).map(|_| true),
+            Operation::Patch => store.patch(
+                object_id,
+                schema_id,
+                timestamp,
+                payload,
+                idempotency_key.as_deref(),
+            ),
+            Operation::Insert => store.insert(
+                object_id,
+                schema_id,
+                timestamp,
+                &payload,
+                idempotency_key.as_deref(),
+            ),
+        })
+        .await;
+
+        match result {
+            Ok(Ok(true)) => {
+                counter!("cdl.command-service.store.rocksdb", 1);
+
+                Resolution::Success
+            }
+            Ok(Ok(false)) => {
+                counter!("cdl.command-service.duplicate.rocksdb", 1);
+
+                Resolution::Success
+            }
+            Ok(Err(err)) => Resolution::StorageLayerFailure {
+                description: err.to_string(),
+            },
+            Err(err) => Resolution::StorageLayerFailure {
+                description: format!("Storage task failed: {}", err),
+            },
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "RocksDB"
+    }
+}
@@ -1,6 +1,6 @@
 use crate::communication::resolution::Resolution;
 use crate::output::OutputPlugin;
-use cdl_dto::ingestion::BorrowedInsertMessage;
+use cdl_dto::ingestion::{BorrowedInsertMessage, Operation};
 use fnv::FnvHashMap;
 use metrics_utils::{self as metrics, counter};
 use reqwest::Url;
@@ -8,7 +8,8 @@ use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
 use serde_json::Value;
-use settings_utils::VictoriaMetricsSettings;
+use settings_utils::{TimestampPrecision, VictoriaMetricsSettings};
+use std::collections::{BTreeMap, HashSet};
 use thiserror::Error as DeriveError;
 use tracing::error;
 use url::ParseError;
@@ -17,6 +18,17 @@ use uuid::Uuid;
 pub struct VictoriaMetricsOutputPlugin {
     client: Client,
     url: Url,
+    delete_url: Url,
+    precision: Option<TimestampPrecision>,
+    format: Format,
+}
+
+/// Describes how payload is converted to line protocol
+#[derive(Debug)]
+struct Format {
+    /// Flattened payload fields written as tags
+    tags: HashSet<String>,
+    field_separator: String,
 }
 
 #[derive(Debug, DeriveError)]
@@ -25,8 +37,6 @@ pub enum Error {
     InvalidUrl(ParseError),
     #[error("Data cannot be parsed `{0}`")]
     DataCannotBeParsed(serde_json::Error),
-    #[error("Value cannot be an object, array or null")]
-    InvalidFieldType,
     #[error("Cannot handle empty payload")]
     EmptyFields,
 }
@@ -38,6 +48,15 @@ impl VictoriaMetricsOutputPlugin {
         Ok(VictoriaMetricsOutputPlugin {
             client,
             url: config.url.join("write").map_err(Error::InvalidUrl)?,
+            delete_url: config
+                .url
+                .join("api/v1/admin/tsdb/delete_series")
+                .map_err(Error::InvalidUrl)?,
+            precision: config.precision,
+            format: Format {
+                tags: config.tags().map(str::to_string).collect(),
+                field_separator: config.field_separator,
+            },
         })
     }
 }
@@ -45,9 +64,41 @@ impl VictoriaMetricsOutputPlugin {
 #[async_trait::async_trait]
 impl OutputPlugin for VictoriaMetricsOutputPlugin {
     async fn handle_message(&self, msg: BorrowedInsertMessage<'_>) -> Resolution {
+        match msg.operation {
+            // Samples are appended, so duplicates can't be skipped
+            Operation::Insert if msg.idempotency_key.is_some() => {
+                return Resolution::UserFailure {
+                    description: "Idempotency key is not supported by timeseries repository"
+                        .to_string(),
+                    context: msg.object_id.to_string(),
+                }
+            }
+            Operation::Insert => {}
+            Operation::Delete => {
+                return delete_series(
+                    self.delete_url.clone(),
+                    &self.client,
+                    msg.schema_id,
+                    msg.object_id,
+                )
+                .await
+            }
+            Operation::Patch => {
+                return Resolution::UserFailure {
+                    description: "Patch operation is not supported by timeseries repository"
+                        .to_string(),
+                    context: msg.object_id.to_string(),
+                }
+            }
+        }
+
         let mut url = self.url.clone();
 
         url.set_query(Some(&format!("db={}", msg.schema_id)));
+        if let Some(precision) = self.precision {
+            url.query_pairs_mut()
+                .append_pair("precision", precision.as_query_param());
+        }
 
         let BorrowedInsertMessage {
             object_id,
@@ -56,7 +107,7 @@ impl OutputPlugin for VictoriaMetricsOutputPlugin {
             ..
         } = msg;
 
-        match build_line_protocol(schema_id, object_id, data) {
+        match build_line_protocol(schema_id, object_id, data, &self.format) {
             Ok(line_protocol) => send_data(url, &self.client, line_protocol).await,
             Err(err) => {
                 let context = data.to_string();
@@ -84,40 +135,163 @@ struct Payload {
     ts: u64,
 }
 
-fn build_line_protocol(measurement: Uuid, tag: Uuid, payload: &RawValue) -> Result<String, Error> {
+fn build_line_protocol(
+    measurement: Uuid,
+    object_id: Uuid,
+    payload: &RawValue,
+    format: &Format,
+) -> Result<String, Error> {
     let payloads: Vec<Payload> =
         serde_json::from_str(payload.get()).map_err(Error::DataCannotBeParsed)?;
     let line_protocol = payloads
         .into_iter()
-        .map(|obj| {
-            Ok(format!(
-                "{},objectId={} {} {}",
-                measurement,
-                tag,
-                get_object_fields(&obj)?,
-                obj.ts
-            ))
-        })
+        .map(|obj| build_line(measurement, object_id, obj, format))
         .collect::<Result<Vec<String>, Error>>()?
         .join("\n");
     Ok(line_protocol)
 }
 
-fn get_object_fields(request_object: &Payload) -> Result<String, Error> {
-    if request_object.fields.is_empty() {
+fn build_line(
+    measurement: Uuid,
+    object_id: Uuid,
+    request_object: Payload,
+    format: &Format,
+) -> Result<String, Error> {
+    let mut flattened = BTreeMap::new();
+    for (key, value) in request_object.fields {
+        flatten(key, value, &format.field_separator, &mut flattened);
+    }
+
+    let (tags, fields): (Vec<_>, Vec<_>) = flattened
+        .into_iter()
+        .partition(|(key, _)| format.tags.contains(key));
+    if fields.is_empty() {
         return Err(Error::EmptyFields);
     }
-    Ok(request_object
-        .fields
-        .iter()
-        .map(|(key, value)| {
-            if value.is_array() || value.is_null() || value.is_object() {
-                return Err(Error::InvalidFieldType);
+
+    let tags: String = tags
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+            // Line protocol doesn't allow empty tag values
+            if value.is_empty() {
+                None
+            } else {
+                Some(format!(",{}={}", escape_key(&key), escape_key(&value)))
             }
-            Ok(format!("{}={}", key, value))
         })
-        .collect::<Result<Vec<String>, Error>>()?
-        .join(","))
+        .collect();
+    let fields = fields
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", escape_key(&key), field_value(value)))
+        .collect::<Vec<String>>()
+        .join(",");
+
+    Ok(format!(
+        "{},objectId={}{} {} {}",
+        measurement, object_id, tags, fields, request_object.ts
+    ))
+}
+
+/// Flattens nested objects and arrays into separate fields, named after their path.
+/// Nulls are skipped.
+fn flatten(key: String, value: Value, separator: &str, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Null => {}
+        Value::Object(object) => {
+            for (nested_key, value) in object {
+                flatten(
+                    format!("{}{}{}", key, separator, nested_key),
+                    value,
+                    separator,
+                    fields,
+                );
+            }
+        }
+        Value::Array(array) => {
+            for (index, value) in array.into_iter().enumerate() {
+                flatten(
+                    format!("{}{}{}", key, separator, index),
+                    value,
+                    separator,
+                    fields,
+                );
+            }
+        }
+        value => {
+            fields.insert(key, value);
+        }
+    }
+}
+
+/// Escapes tag keys, tag values and field keys
+fn escape_key(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, ',' | '=' | ' ') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn field_value(value: Value) -> String {
+    match value {
+        Value::String(value) => {
+            let mut escaped = String::with_capacity(value.len() + 2);
+            escaped.push('"');
+            for c in value.chars() {
+                if matches!(c, '"' | '\\') {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            escaped.push('"');
+            escaped
+        }
+        value => value.to_string(),
+    }
+}
+
+/// Deletes every series of the object, which were written as `<schema_id>_<field>` measurements
+async fn delete_series(
+    mut url: Url,
+    client: &Client,
+    schema_id: Uuid,
+    object_id: Uuid,
+) -> Resolution {
+    url.query_pairs_mut().append_pair(
+        "match[]",
+        &format!(
+            "{{__name__=~\"{}_.+\",objectId=\"{}\"}}",
+            schema_id, object_id
+        ),
+    );
+
+    match client.post(url).send().await {
+        Ok(response) => {
+            if matches!(response.status(), StatusCode::OK | StatusCode::NO_CONTENT) {
+                counter!("cdl.command-service.delete.victoria_metrics", 1);
+
+                Resolution::Success
+            } else {
+                Resolution::StorageLayerFailure {
+                    description: response
+                        .text()
+                        .await
+                        .unwrap_or_else(|err| format!("No description. Error `{}`", err)),
+                }
+            }
+        }
+        Err(err) => {
+            error!("Failed to delete series from vm `{}`", err);
+            Resolution::CommandServiceFailure
+        }
+    }
 }
 
 async fn send_data(url: Url, client: &Client, line_protocol: String) -> Resolution {
@@ -147,6 +321,43 @@ async fn send_data(url: Url, client: &Client, line_protocol: String) -> Resoluti
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn rejects_idempotency_key() {
+        let plugin = VictoriaMetricsOutputPlugin {
+            client: Client::new(),
+            url: Url::parse("http://localhost/write").unwrap(),
+            delete_url: Url::parse("http://localhost/api/v1/admin/tsdb/delete_series").unwrap(),
+            precision: None,
+            format: Format {
+                tags: HashSet::new(),
+                field_separator: "_".to_string(),
+            },
+        };
+        let data =
+            RawValue::from_string(r#"[{"fields": {"y01": 1}, "ts": 1}]"#.to_string()).unwrap();
+
+        let resolution = plugin
+            .handle_message(BorrowedInsertMessage {
+                object_id: Uuid::nil(),
+                schema_id: Uuid::nil(),
+                timestamp: 0,
+                data: &data,
+                operation: Operation::Insert,
+                idempotency_key: Some("order-1234".to_string()),
+                repositories: None,
+            })
+            .await;
+
+        assert_eq!(
+            resolution,
+            Resolution::UserFailure {
+                description: "Idempotency key is not supported by timeseries repository"
+                    .to_string(),
+                context: Uuid::nil().to_string(),
+            }
+        );
+    }
+
     mod describe_build_line_protocol {
         use super::*;
         use test_case::test_case;
@@ -154,9 +365,10 @@ mod tests {
 
         #[test_case(r#"[{"fields":{}, "ts": 15},
                        {"fields":{"a01": 10}, "ts": 15}]"#                => matches Err(Error::EmptyFields))]
-        #[test_case(r#"[{"fields":{"y01": {}}, "ts": 10}]"#               => matches Err(Error::InvalidFieldType))]
-        #[test_case(r#"[{"fields":{"y01": []}, "ts": 5}]"#                => matches Err(Error::InvalidFieldType))]
-        #[test_case(r#"[{"fields":{"y01": null}, "ts": 1}]"#              => matches Err(Error::InvalidFieldType))]
+        #[test_case(r#"[{"fields":{"y01": {}}, "ts": 10}]"#               => matches Err(Error::EmptyFields))]
+        #[test_case(r#"[{"fields":{"y01": []}, "ts": 5}]"#                => matches Err(Error::EmptyFields))]
+        #[test_case(r#"[{"fields":{"y01": null}, "ts": 1}]"#              => matches Err(Error::EmptyFields))]
+        #[test_case(r#"[{"fields":{"type": "sensor"}, "ts": 1}]"#         => matches Err(Error::EmptyFields))]
         #[test_case(r#"[{"fields":{"y01": 123}, "ts": {}}]"#              => matches Err(Error::DataCannotBeParsed(_)))]
         #[test_case(r#"[{"fields":{"y01": 1234}, "ts": []}]"#             => matches Err(Error::DataCannotBeParsed(_)))]
         #[test_case(r#"[{"fields":{"y01": 12345}, "ts": null}]"#          => matches Err(Error::DataCannotBeParsed(_)))]
@@ -168,9 +380,19 @@ mod tests {
                 Uuid::default(),
                 Uuid::default(),
                 &RawValue::from_string(payload.to_string()).unwrap(),
+                &format(),
             )
         }
 
+        fn format() -> Format {
+            Format {
+                tags: vec!["type".to_string(), "location_city".to_string()]
+                    .into_iter()
+                    .collect(),
+                field_separator: "_".to_string(),
+            }
+        }
+
         struct TestCase {
             object_id: &'static str,
             schema_id: &'static str,
@@ -229,6 +451,24 @@ mod tests {
                         ]"#,
         };
 
+        const TEST_CASE_9: TestCase = TestCase {
+            object_id: "00000000-0000-0000-0000-000000000000",
+            schema_id: "00000000-0000-0000-0000-000000000000",
+            payload: r#"[{"fields": {"y01": {"a": 1, "b": [2, 3]}, "y02": null}, "ts": 123}]"#,
+        };
+
+        const TEST_CASE_10: TestCase = TestCase {
+            object_id: "00000000-0000-0000-0000-000000000000",
+            schema_id: "00000000-0000-0000-0000-000000000000",
+            payload: r#"[{"fields": {"type": "air sensor", "location": {"city": "Paris"}, "y01": 1}, "ts": 123}]"#,
+        };
+
+        const TEST_CASE_11: TestCase = TestCase {
+            object_id: "00000000-0000-0000-0000-000000000000",
+            schema_id: "00000000-0000-0000-0000-000000000000",
+            payload: r#"[{"fields": {"a b,c=d": "say \"hi\" \\ bye", "type": ""}, "ts": 123}]"#,
+        };
+
         #[test_case(TEST_CASE_1 => "00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000 y01=13.4 123")]
         #[test_case(TEST_CASE_2 => "00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000 y01=13.4 1603887165" ; "changes timestamp")]
         #[test_case(TEST_CASE_3 => "10b7a9cd-0daf-4cb6-a7ef-b9db6058a2d3,objectId=00000000-0000-0000-0000-000000000000 y01=13.4 123"          ; "changes schema_id")]
@@ -241,12 +481,16 @@ mod tests {
 00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000 y01=321,y02=12345 4321
 00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000 a02=\"string\",z01=true 0";
                                  "handles multiple objects")]
+        #[test_case(TEST_CASE_9 => "00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000 y01_a=1,y01_b_0=2,y01_b_1=3 123"    ; "flattens nested values")]
+        #[test_case(TEST_CASE_10 => "00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000,location_city=Paris,type=air\\ sensor y01=1 123" ; "extracts tags")]
+        #[test_case(TEST_CASE_11 => r#"00000000-0000-0000-0000-000000000000,objectId=00000000-0000-0000-0000-000000000000 a\ b\,c\=d="say \"hi\" \\ bye" 123"# ; "escapes keys and strings")]
 
         fn produces_desired_correct_output(case: TestCase) -> String {
             build_line_protocol(
                 case.schema_id.parse().unwrap(),
                 case.object_id.parse().unwrap(),
                 &RawValue::from_string(case.payload.to_string()).unwrap(),
+                &format(),
             )
             .unwrap()
         }
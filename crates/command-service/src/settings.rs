@@ -4,8 +4,11 @@ use communication_utils::parallel_consumer::{
     ParallelCommonConsumer, ParallelCommonConsumerConfig,
 };
 use communication_utils::publisher::CommonPublisher;
+use serde::de::IntoDeserializer;
 use serde::{Deserialize, Serialize};
 use settings_utils::*;
+use std::num::NonZeroUsize;
+use std::time::Duration;
 use task_utils::task_limiter::TaskLimiter;
 use utils::notification::NotificationSettings;
 
@@ -21,6 +24,15 @@ pub struct Settings {
     pub postgres: Option<PostgresSettings>,
     pub victoria_metrics: Option<VictoriaMetricsSettings>,
     pub druid: Option<DruidSettings>,
+    pub rocksdb: Option<RocksDbSettings>,
+
+    /// Inserts into postgres are written in batches when present
+    pub batching: Option<BatchingSettings>,
+    pub composite: Option<CompositeSettings>,
+
+    /// Storage failures are retried when present
+    pub retry: Option<RetrySettings>,
+    pub dead_letter: Option<DeadLetterSettings>,
 
     // Communication settings - based on communication_method
     pub kafka: Option<KafkaSettings>,
@@ -50,12 +62,51 @@ pub struct ListenerSettings {
     pub unordered_sources: String,
 }
 
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum RepositoryKind {
     Postgres,
     VictoriaMetrics,
     Druid,
+    /// Embedded document storage
+    #[serde(rename = "rocksdb")]
+    RocksDb,
+    /// Writes to every repository listed in `[composite]` section
+    Composite,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompositeSettings {
+    /// Comma separated list of repository kinds, eg. `postgres,druid`
+    pub repositories: String,
+    pub policy: CompositePolicy,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompositePolicy {
+    /// Message fails when any of repositories failed to store it
+    All,
+    /// Message fails only when none of repositories stored it
+    BestEffort,
+}
+
+impl CompositeSettings {
+    pub fn repositories(&self) -> anyhow::Result<Vec<RepositoryKind>> {
+        self.repositories
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|kind| {
+                let kind: RepositoryKind =
+                    RepositoryKind::deserialize(kind.trim().into_deserializer())
+                        .map_err(|err: serde::de::value::Error| anyhow::anyhow!(err))?;
+                if kind == RepositoryKind::Composite {
+                    bail!("Composite repository can't be nested")
+                }
+                Ok(kind)
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -63,6 +114,52 @@ pub struct DruidSettings {
     pub topic: String,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BatchingSettings {
+    /// Maximum number of messages in a single batch
+    pub max_size: NonZeroUsize,
+    /// Maximum time the first message of the batch waits for it to be written
+    pub max_age_ms: u64,
+    /// Number of independent batches, messages are assigned to them by object id
+    #[serde(default = "default_batching_partitions")]
+    pub partitions: NonZeroUsize,
+}
+
+fn default_batching_partitions() -> NonZeroUsize {
+    NonZeroUsize::new(1).unwrap()
+}
+
+impl BatchingSettings {
+    pub fn max_age(&self) -> Duration {
+        Duration::from_millis(self.max_age_ms)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RetrySettings {
+    /// Number of retries after the first failed attempt
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled with every next one
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl RetrySettings {
+    /// Backoff after `attempt` failed attempts
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(1u64.checked_shl(attempt - 1).unwrap_or(u64::MAX));
+        Duration::from_millis(backoff.min(self.max_backoff_ms))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DeadLetterSettings {
+    /// Kafka topic, AMQP exchange or GRPC url, where failed messages are sent together with the error
+    pub destination: String,
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct KafkaSettings {
     pub brokers: String,
@@ -194,3 +291,35 @@ impl AmqpSettings {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_limit() {
+        let retry = RetrySettings {
+            max_retries: 100,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+        };
+
+        assert_eq!(retry.backoff(1), Duration::from_millis(100));
+        assert_eq!(retry.backoff(2), Duration::from_millis(200));
+        assert_eq!(retry.backoff(4), Duration::from_millis(800));
+        assert_eq!(retry.backoff(5), Duration::from_millis(1000));
+        assert_eq!(retry.backoff(80), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn batching_rejects_zero_max_size() {
+        let settings: Result<BatchingSettings, _> =
+            serde_json::from_value(serde_json::json!({ "max_size": 0, "max_age_ms": 50 }));
+        assert!(settings.is_err());
+
+        let settings: BatchingSettings =
+            serde_json::from_value(serde_json::json!({ "max_size": 1, "max_age_ms": 50 })).unwrap();
+        assert_eq!(settings.max_size.get(), 1);
+        assert_eq!(settings.partitions.get(), 1);
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::rate_limit::RateLimitSettings;
+use crate::rules::RoutingRule;
 use communication_utils::{parallel_consumer::ParallelCommonConsumer, publisher::CommonPublisher};
 use settings_utils::{
     AmqpSettings, ConsumerKafkaSettings, GRpcSettings, LogSettings, MonitoringSettings,
@@ -7,6 +9,7 @@ use settings_utils::{
 };
 use std::collections::HashMap;
 use task_utils::task_limiter::TaskLimiter;
+use utils::notification::NotificationSettings;
 
 #[derive(Deserialize, Debug, Serialize)]
 pub struct Settings {
@@ -19,6 +22,19 @@ pub struct Settings {
     pub amqp: Option<AmqpSettings>,
     pub grpc: Option<GRpcSettings>,
 
+    /// Messages are also accepted via HTTP, when present
+    pub http_port: Option<u16>,
+    /// Maximum size of HTTP request body in bytes
+    #[serde(default = "default_http_body_limit")]
+    pub http_body_limit: u64,
+
+    /// Decides if batch with invalid entries is partially accepted
+    #[serde(default)]
+    pub batch_policy: BatchPolicy,
+    /// Outcomes of batches are reported when enabled
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+
     pub monitoring: MonitoringSettings,
 
     pub services: ServicesSettings,
@@ -27,6 +43,13 @@ pub struct Settings {
 
     #[serde(default)]
     pub repositories: HashMap<String, RepositoryStaticRouting>,
+
+    /// Evaluated in order, before static routing and schema registry
+    #[serde(default)]
+    pub routing_rules: Vec<RoutingRule>,
+
+    #[serde(default)]
+    pub rate_limit: RateLimitSettings,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
@@ -38,6 +61,21 @@ pub enum CommunicationMethod {
     GRpc,
 }
 
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchPolicy {
+    /// Nothing is published, when any entry of batch is invalid
+    AllOrNothing,
+    /// Valid entries are published, invalid ones are only reported
+    Partial,
+}
+
+impl Default for BatchPolicy {
+    fn default() -> Self {
+        BatchPolicy::Partial
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize)]
 pub struct ServicesSettings {
     pub schema_registry_url: String,
@@ -47,6 +85,10 @@ const fn default_async_task_limit() -> usize {
     32
 }
 
+const fn default_http_body_limit() -> u64 {
+    1024 * 1024
+}
+
 impl Settings {
     pub async fn consumer(&self) -> anyhow::Result<ParallelCommonConsumer> {
         match (
@@ -0,0 +1,145 @@
+use anyhow::{bail, Context};
+use communication_utils::message::CommunicationMessage;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Encoding of ingested payload
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    /// Encoding is taken from `content-type` of the message.
+    /// Messages without it (eg. sent via gRPC) are detected by their first byte,
+    /// as JSON documents start with `{`, `[` or whitespace, which never starts MessagePack map or array.
+    pub fn detect(payload: &[u8], content_type: Option<&str>) -> anyhow::Result<Self> {
+        match content_type {
+            Some(content_type) => Self::from_content_type(content_type),
+            None => Ok(Self::sniff(payload)),
+        }
+    }
+
+    fn from_content_type(content_type: &str) -> anyhow::Result<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        Ok(match mime.as_str() {
+            "application/json" => Encoding::Json,
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Encoding::MessagePack
+            }
+            _ => bail!("Unsupported content type `{}`", content_type),
+        })
+    }
+
+    fn sniff(payload: &[u8]) -> Self {
+        match payload.first() {
+            Some(b'{') | Some(b'[') | Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => {
+                Encoding::Json
+            }
+            _ => Encoding::MessagePack,
+        }
+    }
+}
+
+/// Payload of the message as JSON text.
+/// MessagePack is transcoded, as messages are routed further in JSON.
+pub fn decode_payload(message: &dyn CommunicationMessage) -> anyhow::Result<Cow<'_, str>> {
+    decode(message.payload_bytes()?, message.content_type())
+}
+
+pub fn decode<'a>(payload: &'a [u8], content_type: Option<&str>) -> anyhow::Result<Cow<'a, str>> {
+    match Encoding::detect(payload, content_type)? {
+        Encoding::Json => Ok(Cow::Borrowed(
+            std::str::from_utf8(payload).context("Payload was not valid UTF-8")?,
+        )),
+        Encoding::MessagePack => {
+            let value: Value = rmp_serde::from_slice(payload)
+                .context("MessagePack payload deserialization failed")?;
+
+            Ok(Cow::Owned(serde_json::to_string(&value)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use test_case::test_case;
+
+    struct Message {
+        payload: Vec<u8>,
+        content_type: Option<&'static str>,
+    }
+
+    impl CommunicationMessage for Message {
+        fn payload(&self) -> anyhow::Result<&str> {
+            Ok(std::str::from_utf8(&self.payload)?)
+        }
+
+        fn key(&self) -> anyhow::Result<&str> {
+            Ok("")
+        }
+
+        fn payload_bytes(&self) -> anyhow::Result<&[u8]> {
+            Ok(&self.payload)
+        }
+
+        fn content_type(&self) -> Option<&str> {
+            self.content_type
+        }
+    }
+
+    fn message() -> Value {
+        json!({
+            "version": "1.0",
+            "objectId": "9056c0b3-2ceb-42a6-a6b6-9718c3e273bc",
+            "schemaId": "f79d7ebd-4260-4919-9ba3-45ea6701f065",
+            "data": { "a": [1, 2.5, "b", null, true] }
+        })
+    }
+
+    #[test_case(Some("application/json") => Encoding::Json)]
+    #[test_case(Some("application/json; charset=utf-8") => Encoding::Json)]
+    #[test_case(Some("application/msgpack") => Encoding::MessagePack)]
+    #[test_case(Some("Application/X-MsgPack") => Encoding::MessagePack)]
+    #[test_case(None => Encoding::Json)]
+    fn detects_encoding_of_json(content_type: Option<&'static str>) -> Encoding {
+        Encoding::detect(b"{}", content_type).unwrap()
+    }
+
+    #[test]
+    fn detects_encoding_of_message_pack() {
+        let payload = rmp_serde::to_vec_named(&message()).unwrap();
+
+        let encoding = Encoding::detect(&payload, None);
+
+        assert_eq!(encoding.unwrap(), Encoding::MessagePack);
+    }
+
+    #[test]
+    fn rejects_unknown_content_type() {
+        assert!(Encoding::detect(b"{}", Some("text/csv")).is_err());
+    }
+
+    #[test_case(message() ; "single message")]
+    #[test_case(json!([message(), message()]) ; "batch")]
+    fn transcodes_message_pack_to_json(value: Value) {
+        let payload = rmp_serde::to_vec_named(&value).unwrap();
+
+        let decoded = decode_payload(&Message {
+            payload,
+            content_type: Some("application/msgpack"),
+        })
+        .unwrap();
+
+        assert_eq!(serde_json::from_str::<Value>(&decoded).unwrap(), value);
+    }
+}
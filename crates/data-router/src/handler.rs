@@ -1,3 +1,8 @@
+use crate::config::BatchPolicy;
+use crate::encoding::decode_payload;
+use crate::object_id;
+use crate::rate_limit::{QuotaExceeded, RateLimiter};
+use crate::rules::{self, RoutingRule};
 use crate::schema::SchemaCache;
 use anyhow::{bail, Context};
 use async_trait::async_trait;
@@ -9,21 +14,280 @@ use communication_utils::{
 use lenient_semver::Version;
 use metrics_utils::{self as metrics, counter};
 use misc_utils::current_timestamp;
+use serde::Serialize;
+use serde_json::value::RawValue;
 use serde_json::Value;
 use settings_utils::RepositoryStaticRouting;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{error, trace};
+use utils::notification::NotificationPublisher;
 use utils::parallel_task_queue::ParallelTaskQueue;
+use uuid::Uuid;
 
 static CDL_INPUT_PROTOCOL_VERSION_MAJOR: u64 = 1;
 static CDL_INPUT_PROTOCOL_VERSION_MINOR: u64 = 0;
 
+#[derive(Clone)]
 pub struct Handler {
-    pub cache: SchemaCache,
+    pub cache: Arc<SchemaCache>,
     pub producer: Arc<CommonPublisher>,
     pub task_queue: Arc<ParallelTaskQueue>,
     pub routing_table: Arc<HashMap<String, RepositoryStaticRouting>>,
+    pub routing_rules: Arc<Vec<RoutingRule>>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub batch_policy: BatchPolicy,
+    pub notification_publisher: NotificationPublisher<BatchReport>,
+}
+
+/// Insert destination and id of the object, resolved for validated message
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub insert_destination: String,
+    pub object_id: Uuid,
+}
+
+/// Outcome of batched message, or of single message which was assigned object id, published as notification
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub accepted: usize,
+    pub rejected: Vec<RejectedEntry>,
+    /// Ids generated for accepted entries sent without them
+    pub assigned: Vec<AssignedId>,
+}
+
+/// Result of [`process_batch`](Handler::process_batch)
+#[derive(Debug, Default)]
+pub struct BatchOutcome {
+    pub report: BatchReport,
+    /// Ids of objects in order of entries, `None` for entries which weren't published
+    pub object_ids: Vec<Option<Uuid>>,
+    /// Any entry was rejected, because it exceeded its rate limit
+    pub quota_exceeded: bool,
+    /// Publishing of any entry failed
+    pub publish_failed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssignedId {
+    /// Position of the entry in batch, `0` for single message
+    pub index: usize,
+    pub object_id: Uuid,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectedEntry {
+    /// Position of the entry in batch
+    pub index: usize,
+    /// Missing when entry couldn't be deserialized or was sent without id, which wasn't generated yet
+    pub object_id: Option<Uuid>,
+    pub error: String,
+}
+
+impl Handler {
+    /// Checks message and resolves its insert destination,
+    /// either from the first matching routing rule, static routing table or schema registry.
+    /// Object id is generated when message was sent without it.
+    /// Quota isn't taken, see [`acquire_quota`](Self::acquire_quota).
+    pub async fn validate(&self, event: &DataRouterInsertMessage<'_>) -> anyhow::Result<Route> {
+        check_inbound_version(&event.version)?;
+
+        let object_id = match event.object_id {
+            Some(object_id) => object_id,
+            None => {
+                let metadata = self.cache.get(event.schema_id).await?;
+                object_id::generate(event, &metadata.natural_key)?
+            }
+        };
+
+        let insert_destination = match rules::destination(&self.routing_rules, event)? {
+            Some(destination) => destination.to_owned(),
+            None => match &event.options.repository_id {
+                Some(repository_id) => self
+                    .routing_table
+                    .get(repository_id)
+                    .map(|routing| routing.insert_destination.clone())
+                    .ok_or_else(|| anyhow::Error::msg("No such entry in routing table"))?,
+                None => self.cache.get(event.schema_id).await?.insert_destination,
+            },
+        };
+
+        Ok(Route {
+            insert_destination,
+            object_id,
+        })
+    }
+
+    /// Takes quota of the message, should be called only once it's decided that message is going to be published
+    pub async fn acquire_quota(&self, event: &DataRouterInsertMessage<'_>) -> anyhow::Result<()> {
+        self.rate_limiter
+            .acquire(
+                event.schema_id,
+                event.options.repository_id.as_deref(),
+                event.data.get().len(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Validates and publishes single message, returns id of the object
+    pub async fn route(
+        &self,
+        event: &DataRouterInsertMessage<'_>,
+        key: &str,
+    ) -> anyhow::Result<Uuid> {
+        let route = self.validate(event).await?;
+        self.acquire_quota(event).await?;
+
+        self.publish(event, key, &route).await?;
+
+        Ok(route.object_id)
+    }
+
+    /// Every entry of the batch is deserialized and validated separately.
+    /// With `all_or_nothing` policy nothing is published when any entry is invalid,
+    /// with `partial` policy valid entries are published and invalid ones only reported.
+    async fn handle_batch(&self, payload: &str, key: &str) -> anyhow::Result<()> {
+        let raw_entries: Vec<&RawValue> = serde_json::from_str(payload)
+            .context("Payload deserialization failed, message is not a valid cdl message ")?;
+
+        let outcome = self.process_batch(raw_entries, key).await;
+
+        // With partial acceptance entries which failed to publish are reported like invalid ones and the message is acknowledged,
+        // because redelivering it would publish the other entries again.
+        // With `all_or_nothing` policy the message fails and is redelivered, even when some entries were already published.
+        let rejected = outcome.report.rejected.len();
+        if self.batch_policy == BatchPolicy::AllOrNothing && rejected > 0 {
+            bail!("{} entries of batch were rejected", rejected);
+        }
+
+        Ok(())
+    }
+
+    /// Validates entries of the batch and publishes them according to the batch policy, reports its outcome.
+    /// Quota is taken only for entries which are going to be published, after the policy accepted the batch.
+    /// With `all_or_nothing` policy entries before the first one over quota keep the quota they took.
+    /// Entries are published one by one, so when publishing of any of them fails, the others are still published.
+    pub async fn process_batch(&self, raw_entries: Vec<&RawValue>, key: &str) -> BatchOutcome {
+        let mut outcome = BatchOutcome {
+            object_ids: vec![None; raw_entries.len()],
+            ..Default::default()
+        };
+        let report = &mut outcome.report;
+        let mut entries = Vec::with_capacity(raw_entries.len());
+        for (index, raw) in raw_entries.into_iter().enumerate() {
+            let entry = match serde_json::from_str::<DataRouterInsertMessage>(raw.get()) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    report.reject(index, None, &anyhow::Error::new(err));
+                    continue;
+                }
+            };
+            match self.validate(&entry).await {
+                Ok(route) => entries.push((index, entry, route)),
+                Err(err) => report.reject(index, entry.object_id, &err),
+            }
+        }
+
+        let accepts = |report: &BatchReport| {
+            self.batch_policy == BatchPolicy::Partial || report.rejected.is_empty()
+        };
+        if accepts(report) {
+            let mut admitted = Vec::with_capacity(entries.len());
+            for (index, entry, route) in entries {
+                match self.acquire_quota(&entry).await {
+                    Ok(()) => admitted.push((index, entry, route)),
+                    Err(err) => {
+                        outcome.quota_exceeded |= err.is::<QuotaExceeded>();
+                        report.reject(index, entry.object_id, &err);
+                        if !accepts(report) {
+                            break;
+                        }
+                    }
+                }
+            }
+            entries = admitted;
+        }
+
+        if accepts(report) {
+            for (index, entry, route) in entries {
+                match self.publish(&entry, key, &route).await {
+                    Ok(()) => {
+                        report.accepted += 1;
+                        outcome.object_ids[index] = Some(route.object_id);
+                        if entry.object_id.is_none() {
+                            report.assigned.push(AssignedId {
+                                index,
+                                object_id: route.object_id,
+                            });
+                        }
+                    }
+                    Err(err) => {
+                        outcome.publish_failed = true;
+                        report.reject(index, Some(route.object_id), &err);
+                    }
+                }
+
+                counter!("cdl.data-router.input-multimsg", 1);
+                counter!("cdl.data-router.processed", 1);
+            }
+        }
+
+        report.rejected.sort_by_key(|entry| entry.index);
+        let rejected = report.rejected.len();
+        counter!("cdl.data-router.rejected-entry", rejected as u64);
+        let description = match (report.accepted, rejected) {
+            (_, 0) => "success",
+            (0, _) => "failure",
+            _ => "partial",
+        };
+        self.notify(&outcome.report, description).await;
+
+        outcome
+    }
+
+    /// Reports id assigned to single message sent without it
+    pub async fn report_assigned(&self, event: &DataRouterInsertMessage<'_>, object_id: Uuid) {
+        if event.object_id.is_none() {
+            trace!("Assigned object id {}", object_id);
+            let report = BatchReport {
+                accepted: 1,
+                rejected: vec![],
+                assigned: vec![AssignedId {
+                    index: 0,
+                    object_id,
+                }],
+            };
+            self.notify(&report, "success").await;
+        }
+    }
+
+    /// Publishes message to insert destination returned by [`validate`](Self::validate)
+    pub async fn publish(
+        &self,
+        event: &DataRouterInsertMessage<'_>,
+        key: &str,
+        route: &Route,
+    ) -> anyhow::Result<()> {
+        route_static(event, key, &self.producer, route)
+            .await
+            .context("Tried to send message and failed")
+    }
+
+    async fn notify(&self, report: &BatchReport, description: &str) {
+        if let Err(err) = self
+            .notification_publisher
+            .clone()
+            .with_message_body(report)
+            .notify(description)
+            .await
+        {
+            error!("Failed to send notification {:?}", err);
+        }
+    }
 }
 
 #[async_trait]
@@ -43,75 +307,29 @@ impl ParallelConsumerHandler for Handler {
         let message_key = get_order_group_id(message).unwrap_or_default();
         counter!("cdl.data-router.input-msg", 1);
         let result = async {
-            let json_something: Value = serde_json::from_str(message.payload()?)
-                .context("Payload deserialization failed")?;
+            let payload = decode_payload(message)?;
+            let json_something: Value =
+                serde_json::from_str(&payload).context("Payload deserialization failed")?;
             if json_something.is_array() {
                 trace!("Processing multimessage");
 
-                let maybe_array: Vec<DataRouterInsertMessage> = serde_json::from_str(
-                    message.payload()?,
-                )
-                .context("Payload deserialization failed, message is not a valid cdl message ")?;
-
-                let mut result = Ok(());
-
-                for entry in maybe_array.iter() {
-                    let r = if let Some(repository_id) = &entry.options.repository_id {
-                        if let Some(routing) = self.routing_table.get(repository_id) {
-                            route_static(
-                                entry,
-                                &message_key,
-                                &self.producer,
-                                &routing.insert_destination,
-                            )
-                            .await
-                        } else {
-                            Err(anyhow::Error::msg("No such entry in routing table"))
-                        }
-                    } else {
-                        route(&self.cache, entry, &message_key, &self.producer)
-                            .await
-                            .context("Tried to send message and failed")
-                    };
-
-                    counter!("cdl.data-router.input-multimsg", 1);
-                    counter!("cdl.data-router.processed", 1);
-
-                    if r.is_err() {
-                        result = r;
-                    }
-                }
-
-                result
+                self.handle_batch(&payload, &message_key).await
             } else {
                 trace!("Processing single message");
 
                 let owned: DataRouterInsertMessage =
-                    serde_json::from_str::<DataRouterInsertMessage>(message.payload()?).context(
+                    serde_json::from_str::<DataRouterInsertMessage>(&payload).context(
                         "Payload deserialization failed, message is not a valid cdl message",
                     )?;
 
-                let result = if let Some(repository_id) = &owned.options.repository_id {
-                    if let Some(routing) = self.routing_table.get(repository_id) {
-                        route_static(
-                            &owned,
-                            &message_key,
-                            &self.producer,
-                            &routing.insert_destination,
-                        )
-                        .await
-                    } else {
-                        Err(anyhow::Error::msg("No such entry in routing table"))
-                    }
-                } else {
-                    route(&self.cache, &owned, &message_key, &self.producer)
-                        .await
-                        .context("Tried to send message and failed")
-                };
+                let result = self.route(&owned, &message_key).await;
                 counter!("cdl.data-router.input-singlemsg", 1);
                 counter!("cdl.data-router.processed", 1);
 
-                result
+                let object_id = result?;
+                self.report_assigned(&owned, object_id).await;
+
+                Ok(())
             }
         }
         .await;
@@ -130,6 +348,16 @@ impl ParallelConsumerHandler for Handler {
     }
 }
 
+impl BatchReport {
+    fn reject(&mut self, index: usize, object_id: Option<Uuid>, error: &anyhow::Error) {
+        self.rejected.push(RejectedEntry {
+            index,
+            object_id,
+            error: format!("{:#}", error),
+        });
+    }
+}
+
 fn check_version_matrix(version: lenient_semver::Version) -> anyhow::Result<()> {
     if version.major != CDL_INPUT_PROTOCOL_VERSION_MAJOR {
         bail!("Unsupported protocol : major version")
@@ -167,37 +395,25 @@ async fn route_static(
     event: &DataRouterInsertMessage<'_>,
     key: &str,
     publisher: &CommonPublisher,
-    repository_path: &str,
+    route: &Route,
 ) -> anyhow::Result<()> {
-    check_inbound_version(&event.version)?;
     let payload = BorrowedInsertMessage {
-        object_id: event.object_id,
+        object_id: route.object_id,
         schema_id: event.schema_id,
         timestamp: current_timestamp(),
         data: event.data,
+        operation: event.operation,
+        idempotency_key: event.idempotency_key.clone(),
+        repositories: None,
     };
 
     send_message(
         publisher,
-        repository_path,
+        &route.insert_destination,
         key,
         serde_json::to_vec(&payload)?,
     )
-    .await;
-
-    Ok(())
-}
-
-#[tracing::instrument(skip(cache, publisher))]
-async fn route(
-    cache: &SchemaCache,
-    event: &DataRouterInsertMessage<'_>,
-    key: &str,
-    publisher: &CommonPublisher,
-) -> anyhow::Result<()> {
-    let insert_destination = cache.get(event.schema_id).await?;
-
-    route_static(event, &key, publisher, &insert_destination).await
+    .await
 }
 
 #[tracing::instrument(skip(producer))]
@@ -206,20 +422,22 @@ async fn send_message(
     insert_destination: &str,
     key: &str,
     payload: Vec<u8>,
-) {
+) -> anyhow::Result<()> {
     let payload_len = payload.len();
     let delivery_status = producer
         .publish_message(&insert_destination, key, payload)
         .await;
 
-    if delivery_status.is_err() {
+    if let Err(err) = delivery_status {
         error!(
             "Fatal error, delivery status for message not received.  Insert destination: `{}`, Key: `{}`, Payload len: `{}`, {:?}",
-            insert_destination, key, payload_len, delivery_status
+            insert_destination, key, payload_len, err
         );
-    } else {
-        counter!("cdl.data-router.output-singleok", 1);
+        bail!("Message was not delivered to `{}`", insert_destination);
     }
+
+    counter!("cdl.data-router.output-singleok", 1);
+    Ok(())
 }
 
 #[cfg(test)]
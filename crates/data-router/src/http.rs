@@ -0,0 +1,291 @@
+use crate::config::BatchPolicy;
+use crate::encoding::decode;
+use crate::handler::{BatchOutcome, Handler, RejectedEntry};
+use cdl_dto::ingestion::DataRouterInsertMessage;
+use metrics_utils::{self as metrics, counter};
+use serde::Serialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::convert::Infallible;
+use uuid::Uuid;
+use warp::hyper::body::Bytes;
+use warp::hyper::StatusCode;
+use warp::{reject::Reject, Filter, Rejection, Reply};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidPayload(anyhow::Error),
+    InvalidMessages(Response),
+    QuotaExceeded(Response),
+    PublishFailed(Response),
+}
+
+impl Reject for Error {}
+
+/// Outcome of the request, sent also with errors
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    accepted: usize,
+    /// Ids of objects in order of messages, `null` for messages which weren't published
+    object_ids: Vec<Option<Uuid>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<RejectedEntry>,
+}
+
+impl From<BatchOutcome> for Response {
+    fn from(outcome: BatchOutcome) -> Self {
+        Self {
+            accepted: outcome.report.accepted,
+            object_ids: outcome.object_ids,
+            errors: outcome.report.rejected,
+        }
+    }
+}
+
+pub fn routes(
+    handler: Handler,
+    body_limit: u64,
+) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone {
+    let handler_filter = warp::any().map(move || handler.clone());
+
+    warp::post()
+        .and(warp::path!("insert"))
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::header::optional::<String>("ORDER_GROUP_ID"))
+        .and(warp::body::content_length_limit(body_limit))
+        .and(warp::body::bytes())
+        .and(handler_filter)
+        .and_then(insert)
+        .recover(recover)
+}
+
+/// Accepts single message or batch of messages, responds with ids of objects in order of messages.
+/// Batch is handled according to the batch policy and reported, same as batches consumed from the message queue.
+/// Messages are published one by one, so when publishing fails, the others are still published
+/// and the response lists which of them were.
+#[tracing::instrument(skip(body, handler))]
+async fn insert(
+    content_type: Option<String>,
+    order_group_id: Option<String>,
+    body: Bytes,
+    handler: Handler,
+) -> Result<impl Reply, Rejection> {
+    counter!("cdl.data-router.http.input-request", 1);
+
+    let payload = decode(&body, content_type.as_deref()).map_err(Error::InvalidPayload)?;
+    let key = order_group_id.unwrap_or_default();
+
+    let response = if payload.trim_start().starts_with('[') {
+        let raw_entries: Vec<&RawValue> =
+            serde_json::from_str(&payload).map_err(|err| Error::InvalidPayload(err.into()))?;
+
+        let outcome = handler.process_batch(raw_entries, &key).await;
+        if outcome.publish_failed {
+            return Err(Error::PublishFailed(outcome.into()).into());
+        }
+        if handler.batch_policy == BatchPolicy::AllOrNothing && !outcome.report.rejected.is_empty()
+        {
+            return Err(if outcome.quota_exceeded {
+                Error::QuotaExceeded(outcome.into())
+            } else {
+                Error::InvalidMessages(outcome.into())
+            }
+            .into());
+        }
+
+        outcome.into()
+    } else {
+        let message: DataRouterInsertMessage =
+            serde_json::from_str(&payload).map_err(|err| Error::InvalidPayload(err.into()))?;
+
+        let rejected = |err: anyhow::Error| Response {
+            accepted: 0,
+            object_ids: vec![None],
+            errors: vec![RejectedEntry {
+                index: 0,
+                object_id: message.object_id,
+                error: format!("{:#}", err),
+            }],
+        };
+
+        let route = handler
+            .validate(&message)
+            .await
+            .map_err(|err| Error::InvalidMessages(rejected(err)))?;
+        handler
+            .acquire_quota(&message)
+            .await
+            .map_err(|err| Error::QuotaExceeded(rejected(err)))?;
+        handler
+            .publish(&message, &key, &route)
+            .await
+            .map_err(|err| Error::PublishFailed(rejected(err)))?;
+        counter!("cdl.data-router.processed", 1);
+        handler.report_assigned(&message, route.object_id).await;
+
+        Response {
+            accepted: 1,
+            object_ids: vec![Some(route.object_id)],
+            errors: vec![],
+        }
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&response),
+        StatusCode::ACCEPTED,
+    ))
+}
+
+async fn recover(rejection: Rejection) -> Result<impl Reply, Infallible> {
+    if rejection.find::<Error>().is_some() {
+        counter!("cdl.data-router.error", 1);
+    }
+
+    let (body, code) = match rejection.find::<Error>() {
+        Some(Error::InvalidPayload(err)) => (
+            serde_json::json!({ "message": format!("Invalid payload: {:#}", err) }),
+            StatusCode::BAD_REQUEST,
+        ),
+        Some(Error::InvalidMessages(response)) => (
+            with_message("Invalid messages", response),
+            StatusCode::BAD_REQUEST,
+        ),
+        Some(Error::QuotaExceeded(response)) => (
+            with_message("Rate limit exceeded", response),
+            StatusCode::TOO_MANY_REQUESTS,
+        ),
+        Some(Error::PublishFailed(response)) => (
+            with_message("Publishing failed", response),
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        None if rejection.is_not_found() => (
+            serde_json::json!({ "message": "Not found" }),
+            StatusCode::NOT_FOUND,
+        ),
+        None => match rejection.find::<warp::reject::PayloadTooLarge>() {
+            Some(_) => (
+                serde_json::json!({ "message": "Payload too large" }),
+                StatusCode::PAYLOAD_TOO_LARGE,
+            ),
+            None => (
+                serde_json::json!({ "message": format!("{:?}", rejection) }),
+                StatusCode::BAD_REQUEST,
+            ),
+        },
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&body), code))
+}
+
+fn with_message(message: &str, response: &Response) -> Value {
+    let mut body = serde_json::to_value(response).unwrap_or_default();
+    body["message"] = message.into();
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limit::RateLimiter;
+    use crate::schema::SchemaMetadataSupplier;
+    use cache::DynamicCache;
+    use communication_utils::publisher::CommonPublisher;
+    use rpc::schema_registry::types::SchemaType;
+    use settings_utils::RepositoryStaticRouting;
+    use std::sync::Arc;
+    use utils::notification::NotificationPublisher;
+    use utils::parallel_task_queue::ParallelTaskQueue;
+
+    fn repository(insert_destination: &str) -> RepositoryStaticRouting {
+        RepositoryStaticRouting {
+            insert_destination: insert_destination.to_owned(),
+            query_address: String::new(),
+            repository_type: SchemaType::DocumentStorage,
+        }
+    }
+
+    /// Messages of `up` repository are published to local server, ones of `down` repository fail
+    async fn handler() -> Handler {
+        let (addr, server) =
+            warp::serve(warp::any().map(warp::reply)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        Handler {
+            cache: Arc::new(DynamicCache::new(
+                1,
+                SchemaMetadataSupplier::new("http://127.0.0.1:1".to_owned()),
+            )),
+            producer: Arc::new(
+                CommonPublisher::new_rest(format!("http://{}/", addr).parse().unwrap())
+                    .await
+                    .unwrap(),
+            ),
+            task_queue: Arc::new(ParallelTaskQueue::default()),
+            routing_table: Arc::new(
+                vec![
+                    ("up".to_owned(), repository("insert")),
+                    ("down".to_owned(), repository("http://127.0.0.1:1/insert")),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            routing_rules: Arc::new(vec![]),
+            rate_limiter: Arc::new(RateLimiter::new(Default::default())),
+            batch_policy: BatchPolicy::Partial,
+            notification_publisher: NotificationPublisher::Disabled,
+        }
+    }
+
+    fn message(object_id: u128, repository_id: &str) -> Value {
+        serde_json::json!({
+            "version": "1.0",
+            "objectId": Uuid::from_u128(object_id),
+            "schemaId": Uuid::nil(),
+            "data": {},
+            "options": { "repositoryId": repository_id }
+        })
+    }
+
+    #[tokio::test]
+    async fn reports_messages_published_before_failure() {
+        let body = serde_json::json!([message(1, "up"), message(2, "down"), message(3, "up")]);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/insert")
+            .json(&body)
+            .reply(&routes(handler().await, 1 << 20))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["accepted"], 2);
+        assert_eq!(
+            body["objectIds"],
+            serde_json::json!([Uuid::from_u128(1), null, Uuid::from_u128(3)])
+        );
+        assert_eq!(body["errors"][0]["index"], 1);
+    }
+
+    #[tokio::test]
+    async fn publishes_valid_messages_with_partial_policy() {
+        let body = serde_json::json!([message(1, "up"), message(2, "missing")]);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/insert")
+            .json(&body)
+            .reply(&routes(handler().await, 1 << 20))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["accepted"], 1);
+        assert_eq!(
+            body["objectIds"],
+            serde_json::json!([Uuid::from_u128(1), null])
+        );
+        assert_eq!(body["errors"][0]["index"], 1);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::rate_limit::RateLimiter;
 use crate::schema::SchemaMetadataSupplier;
 use crate::{config::Settings, handler::Handler};
 use cache::DynamicCache;
@@ -7,7 +8,12 @@ use std::sync::Arc;
 use utils::parallel_task_queue::ParallelTaskQueue;
 
 mod config;
+mod encoding;
 mod handler;
+mod http;
+mod object_id;
+mod rate_limit;
+mod rules;
 mod schema;
 
 #[tokio::main]
@@ -24,25 +30,49 @@ async fn main() -> anyhow::Result<()> {
 
     metrics::serve(&settings.monitoring);
 
+    for rule in &settings.routing_rules {
+        rule.check()?;
+    }
+
     let consumer = settings.consumer().await?;
     let producer = Arc::new(settings.producer().await?);
 
-    let cache = DynamicCache::new(
+    let cache = Arc::new(DynamicCache::new(
         settings.cache_capacity,
         SchemaMetadataSupplier::new(settings.services.schema_registry_url),
-    );
+    ));
 
     let task_queue = Arc::new(ParallelTaskQueue::default());
 
-    consumer
-        .par_run(Handler {
-            cache,
-            producer,
-            task_queue,
-            routing_table: Arc::new(settings.repositories),
-        })
+    let notification_publisher = settings
+        .notifications
+        .publisher(
+            || settings.producer(),
+            format!("{:?}", settings.communication_method),
+            "DataRouter",
+        )
         .await?;
 
+    let handler = Handler {
+        cache,
+        producer,
+        task_queue,
+        routing_table: Arc::new(settings.repositories),
+        routing_rules: Arc::new(settings.routing_rules),
+        rate_limiter: Arc::new(RateLimiter::new(settings.rate_limit)),
+        batch_policy: settings.batch_policy,
+        notification_publisher,
+    };
+
+    if let Some(http_port) = settings.http_port {
+        tokio::spawn(tracing_utils::http::serve(
+            http::routes(handler.clone(), settings.http_body_limit),
+            ([0, 0, 0, 0], http_port),
+        ));
+    }
+
+    consumer.par_run(handler).await?;
+
     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
 
     Ok(())
@@ -0,0 +1,128 @@
+use anyhow::{bail, Context};
+use cdl_dto::ingestion::{DataRouterInsertMessage, Operation};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Generates id for object sent without it. Id is derived from natural key of the schema
+/// as UUIDv5 in namespace of the schema id, so every message with the same key updates the same object.
+/// Inserts to schemas without natural key get id derived the same way from their idempotency key,
+/// so redelivered message is recognized as duplicate. Random id is generated only when there is none.
+pub fn generate(
+    event: &DataRouterInsertMessage<'_>,
+    natural_key: &[String],
+) -> anyhow::Result<Uuid> {
+    if natural_key.is_empty() {
+        if event.operation != Operation::Insert {
+            bail!(
+                "Object id is required for {:?} operation, schema has no natural key",
+                event.operation
+            );
+        }
+        return Ok(match &event.idempotency_key {
+            Some(idempotency_key) => Uuid::new_v5(&event.schema_id, idempotency_key.as_bytes()),
+            None => Uuid::new_v4(),
+        });
+    }
+
+    let data: Value =
+        serde_json::from_str(event.data.get()).context("Payload is not a valid JSON")?;
+    let values = natural_key
+        .iter()
+        .map(|field| match data.pointer(field) {
+            Some(value) if !value.is_null() => Ok(value),
+            _ => bail!("Natural key field `{}` is missing", field),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(Uuid::new_v5(
+        &event.schema_id,
+        serde_json::to_string(&values)?.as_bytes(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, value::RawValue};
+    use test_case::test_case;
+
+    fn event(data: &RawValue, operation: Operation) -> DataRouterInsertMessage<'_> {
+        DataRouterInsertMessage {
+            version: "1.0",
+            object_id: None,
+            schema_id: Uuid::from_u128(1),
+            data,
+            operation,
+            idempotency_key: None,
+            options: Default::default(),
+        }
+    }
+
+    fn event_with_key<'a>(
+        data: &'a RawValue,
+        idempotency_key: &str,
+    ) -> DataRouterInsertMessage<'a> {
+        DataRouterInsertMessage {
+            idempotency_key: Some(idempotency_key.to_owned()),
+            ..event(data, Operation::Insert)
+        }
+    }
+
+    fn raw(data: Value) -> Box<RawValue> {
+        RawValue::from_string(data.to_string()).unwrap()
+    }
+
+    fn key(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn derives_same_id_from_same_key() {
+        let first = raw(json!({"a": 1, "b": {"c": "x"}, "d": 1}));
+        let second = raw(json!({"a": 1, "b": {"c": "x"}, "d": 2}));
+        let other = raw(json!({"a": 2, "b": {"c": "x"}, "d": 1}));
+        let natural_key = key(&["/a", "/b/c"]);
+
+        let first = generate(&event(&first, Operation::Insert), &natural_key).unwrap();
+        let second = generate(&event(&second, Operation::Patch), &natural_key).unwrap();
+        let other = generate(&event(&other, Operation::Insert), &natural_key).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(first.get_version_num(), 5);
+    }
+
+    #[test]
+    fn generates_random_id_without_natural_key() {
+        let data = raw(json!({"a": 1}));
+
+        let first = generate(&event(&data, Operation::Insert), &[]).unwrap();
+        let second = generate(&event(&data, Operation::Insert), &[]).unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn derives_same_id_from_same_idempotency_key() {
+        let first = raw(json!({"a": 1}));
+        let redelivered = raw(json!({"a": 1}));
+
+        let first = generate(&event_with_key(&first, "order-1234"), &[]).unwrap();
+        let redelivered = generate(&event_with_key(&redelivered, "order-1234"), &[]).unwrap();
+        let other = generate(&event_with_key(&raw(json!({"a": 1})), "order-1235"), &[]).unwrap();
+
+        assert_eq!(first, redelivered);
+        assert_ne!(first, other);
+        assert_eq!(first.get_version_num(), 5);
+    }
+
+    #[test_case(json!({"a": 1}), &["/a", "/b"], Operation::Insert ; "missing field")]
+    #[test_case(json!({"a": null}), &["/a"], Operation::Insert ; "null field")]
+    #[test_case(json!({"a": 1}), &[], Operation::Patch ; "patch without natural key")]
+    #[test_case(json!(null), &[], Operation::Delete ; "delete without natural key")]
+    fn fails_to_generate(data: Value, fields: &[&str], operation: Operation) {
+        let data = raw(data);
+
+        assert!(generate(&event(&data, operation), &key(fields)).is_err());
+    }
+}
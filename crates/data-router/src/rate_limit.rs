@@ -0,0 +1,295 @@
+use metrics_utils::{self as metrics, counter, gauge};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RateLimitSettings {
+    #[serde(default)]
+    pub policy: RateLimitPolicy,
+    /// Limit of every schema, which is not listed in `schemas`
+    pub default: Option<Limit>,
+    #[serde(default)]
+    pub schemas: HashMap<Uuid, Limit>,
+    /// Limits of messages with `repository_id` option, applied in addition to limits of their schema
+    #[serde(default)]
+    pub repositories: HashMap<String, Limit>,
+}
+
+/// Token bucket refilled at the given rate, which holds at most one second of traffic
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Limit {
+    pub messages_per_second: Option<f64>,
+    pub bytes_per_second: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitPolicy {
+    /// Message waits until quota is available
+    Delay,
+    /// Message is rejected immediately
+    Reject,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        RateLimitPolicy::Delay
+    }
+}
+
+/// Returned when message is over quota and policy rejects it
+#[derive(Debug)]
+pub struct QuotaExceeded {
+    key: String,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rate limit of {} exceeded", self.key)
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    buckets: Mutex<HashMap<String, Buckets>>,
+}
+
+struct Buckets {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            settings,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Takes quota of the message from limits of its schema and repository.
+    /// Depending on policy, waits until quota is available or returns [`QuotaExceeded`].
+    pub async fn acquire(
+        &self,
+        schema_id: Uuid,
+        repository_id: Option<&str>,
+        bytes: usize,
+    ) -> Result<(), QuotaExceeded> {
+        let schema_limit = self
+            .settings
+            .schemas
+            .get(&schema_id)
+            .or_else(|| self.settings.default.as_ref())
+            .map(|limit| (format!("schema {}", schema_id), limit));
+        let repository_limit = repository_id.and_then(|repository_id| {
+            self.settings
+                .repositories
+                .get(repository_id)
+                .map(|limit| (format!("repository {}", repository_id), limit))
+        });
+        let limits: Vec<_> = schema_limit.into_iter().chain(repository_limit).collect();
+        if limits.is_empty() {
+            return Ok(());
+        }
+
+        let wait = {
+            let mut buckets = self.buckets.lock().unwrap();
+            let now = Instant::now();
+
+            for (key, limit) in &limits {
+                let buckets = buckets
+                    .entry(key.clone())
+                    .or_insert_with(|| Buckets::new(limit, now));
+                if self.settings.policy == RateLimitPolicy::Reject && !buckets.available(bytes, now)
+                {
+                    counter!("cdl.data-router.rate-limit.rejected", 1, "key" => key.clone());
+                    return Err(QuotaExceeded { key: key.clone() });
+                }
+            }
+
+            let mut wait = Duration::default();
+            for (key, _) in &limits {
+                let buckets = buckets.get_mut(key).unwrap();
+                wait = wait.max(buckets.take(bytes, now));
+                buckets.report(key);
+            }
+            wait
+        };
+
+        if wait > Duration::default() {
+            counter!("cdl.data-router.rate-limit.delayed", 1);
+            tokio::time::sleep(wait).await;
+        }
+
+        Ok(())
+    }
+}
+
+impl Buckets {
+    fn new(limit: &Limit, now: Instant) -> Self {
+        Self {
+            messages: limit
+                .messages_per_second
+                .map(|rate| TokenBucket::new(rate, now)),
+            bytes: limit
+                .bytes_per_second
+                .map(|rate| TokenBucket::new(rate, now)),
+        }
+    }
+
+    fn available(&mut self, bytes: usize, now: Instant) -> bool {
+        self.messages
+            .as_mut()
+            .map_or(true, |bucket| bucket.available(1.0, now))
+            && self
+                .bytes
+                .as_mut()
+                .map_or(true, |bucket| bucket.available(bytes as f64, now))
+    }
+
+    fn take(&mut self, bytes: usize, now: Instant) -> Duration {
+        let messages = self
+            .messages
+            .as_mut()
+            .map_or_else(Duration::default, |bucket| bucket.take(1.0, now));
+        let bytes = self
+            .bytes
+            .as_mut()
+            .map_or_else(Duration::default, |bucket| bucket.take(bytes as f64, now));
+        messages.max(bytes)
+    }
+
+    fn report(&self, key: &str) {
+        if let Some(bucket) = &self.messages {
+            gauge!("cdl.data-router.rate-limit.messages", bucket.tokens, "key" => key.to_owned());
+        }
+        if let Some(bucket) = &self.bytes {
+            gauge!("cdl.data-router.rate-limit.bytes", bucket.tokens, "key" => key.to_owned());
+        }
+    }
+}
+
+struct TokenBucket {
+    rate: f64,
+    /// Negative when quota was borrowed by delayed messages
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.updated = now;
+    }
+
+    /// Messages bigger than whole bucket are let through when it's full
+    fn available(&mut self, amount: f64, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= amount.min(self.rate)
+    }
+
+    /// Takes tokens, returning time after which they would have been available
+    fn take(&mut self, amount: f64, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= amount;
+        if self.tokens >= 0.0 {
+            Duration::default()
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+
+        assert_eq!(bucket.take(2.0, start), Duration::default());
+        assert!(!bucket.available(1.0, start));
+        assert!(bucket.available(1.0, start + Duration::from_millis(500)));
+        assert!(!bucket.available(2.0, start + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn bucket_never_holds_more_than_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+
+        assert!(bucket.available(100.0, start + Duration::from_secs(10)));
+        assert_eq!(bucket.tokens, 2.0);
+    }
+
+    #[test]
+    fn delays_message_borrowing_quota() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(10.0, start);
+
+        assert_eq!(bucket.take(10.0, start), Duration::default());
+        assert_eq!(bucket.take(5.0, start), Duration::from_millis(500));
+        assert_eq!(bucket.take(5.0, start), Duration::from_secs(1));
+    }
+
+    fn limiter(policy: RateLimitPolicy) -> RateLimiter {
+        RateLimiter::new(RateLimitSettings {
+            policy,
+            default: Some(Limit {
+                messages_per_second: Some(1.0),
+                bytes_per_second: None,
+            }),
+            schemas: Default::default(),
+            repositories: vec![(
+                "repo".to_owned(),
+                Limit {
+                    messages_per_second: None,
+                    bytes_per_second: Some(100.0),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        })
+    }
+
+    #[tokio::test]
+    async fn rejects_over_quota_per_schema() {
+        let limiter = limiter(RateLimitPolicy::Reject);
+
+        assert!(limiter.acquire(Uuid::from_u128(1), None, 10).await.is_ok());
+        assert!(limiter.acquire(Uuid::from_u128(1), None, 10).await.is_err());
+        assert!(limiter.acquire(Uuid::from_u128(2), None, 10).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_over_quota_per_repository() {
+        let limiter = limiter(RateLimitPolicy::Reject);
+
+        assert!(limiter
+            .acquire(Uuid::from_u128(1), Some("repo"), 80)
+            .await
+            .is_ok());
+        let err = limiter
+            .acquire(Uuid::from_u128(2), Some("repo"), 80)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Rate limit of repository repo exceeded");
+    }
+}
@@ -0,0 +1,303 @@
+use anyhow::{bail, Context};
+use cdl_dto::ingestion::{DataRouterInsertMessage, Operation};
+use metrics_utils::{self as metrics, counter};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use uuid::Uuid;
+
+/// Overrides insert destination of inserted objects, which payload matches the predicate
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RoutingRule {
+    /// Used as `rule` label of metrics
+    pub name: String,
+    /// Rule applies to messages of every schema when missing
+    #[serde(default)]
+    pub schema_id: Option<Uuid>,
+    /// JSON pointer into `data` of the message, eg. `/location/region`,
+    /// or JSONPath of a single field, eg. `$.location.region`
+    pub path: String,
+    pub operator: RuleOperator,
+    #[serde(default)]
+    pub value: Value,
+    pub insert_destination: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOperator {
+    Equals,
+    NotEquals,
+    /// `value` is an array of accepted values
+    In,
+    /// Field is present and not null, `value` is ignored
+    Exists,
+}
+
+impl RoutingRule {
+    pub fn check(&self) -> anyhow::Result<()> {
+        let pointer = self
+            .pointer()
+            .with_context(|| format!("Path of routing rule `{}` is not valid", self.name))?;
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            anyhow::bail!(
+                "Path of routing rule `{}` is neither JSON pointer nor JSONPath",
+                self.name
+            );
+        }
+        if self.operator == RuleOperator::In && !self.value.is_array() {
+            anyhow::bail!(
+                "Value of routing rule `{}` with `in` operator has to be an array",
+                self.name
+            );
+        }
+        Ok(())
+    }
+
+    fn applies_to(&self, schema_id: Uuid) -> bool {
+        self.schema_id.map_or(true, |id| id == schema_id)
+    }
+
+    fn pointer(&self) -> anyhow::Result<Cow<'_, str>> {
+        if self.path.starts_with('$') {
+            json_path_to_pointer(&self.path).map(Cow::Owned)
+        } else {
+            Ok(Cow::Borrowed(&self.path))
+        }
+    }
+
+    fn matches(&self, data: &Value) -> bool {
+        let field = match self.pointer() {
+            Ok(pointer) => data.pointer(&pointer),
+            Err(_) => None,
+        };
+
+        match self.operator {
+            RuleOperator::Equals => field == Some(&self.value),
+            RuleOperator::NotEquals => field != Some(&self.value),
+            RuleOperator::In => match (field, self.value.as_array()) {
+                (Some(field), Some(values)) => values.contains(field),
+                _ => false,
+            },
+            RuleOperator::Exists => !matches!(field, None | Some(Value::Null)),
+        }
+    }
+}
+
+/// Destination of the first rule matching the message.
+/// Deletes and patches don't carry fields the object was routed by, so they aren't matched by rules.
+/// They have to name repository holding the object instead, when there is any rule for their schema.
+pub fn destination<'a>(
+    rules: &'a [RoutingRule],
+    event: &DataRouterInsertMessage<'_>,
+) -> anyhow::Result<Option<&'a str>> {
+    let mut rules = rules
+        .iter()
+        .filter(|rule| rule.applies_to(event.schema_id))
+        .peekable();
+
+    // Payload is parsed only when there is any rule for its schema
+    if rules.peek().is_none() {
+        return Ok(None);
+    }
+    if event.operation != Operation::Insert {
+        if event.options.repository_id.is_none() {
+            bail!(
+                "Schema has routing rules, so delete and patch have to be sent with `repositoryId`"
+            );
+        }
+        return Ok(None);
+    }
+    let data: Value = serde_json::from_str(event.data.get())?;
+
+    Ok(rules.find(|rule| rule.matches(&data)).map(|rule| {
+        counter!("cdl.data-router.routing-rule", 1, "rule" => rule.name.clone());
+        rule.insert_destination.as_str()
+    }))
+}
+
+/// Converts JSONPath of a single field, eg. `$.location.region` or `$['items'][0]`, to JSON pointer.
+/// Wildcards, filters and recursive descent aren't supported, as rule compares a single value.
+fn json_path_to_pointer(path: &str) -> anyhow::Result<String> {
+    let mut rest = path
+        .strip_prefix('$')
+        .context("JSONPath has to start with `$`")?;
+    let mut pointer = String::new();
+
+    while !rest.is_empty() {
+        let (segment, remaining) = if let Some(dotted) = rest.strip_prefix('.') {
+            let end = dotted.find(&['.', '['][..]).unwrap_or_else(|| dotted.len());
+            (&dotted[..end], &dotted[end..])
+        } else if let Some(bracketed) = rest.strip_prefix('[') {
+            let end = bracketed
+                .find(']')
+                .context("Unclosed bracket in JSONPath")?;
+            let inner = &bracketed[..end];
+            let segment = match inner.as_bytes().first() {
+                Some(b'\'') | Some(b'"') if inner.len() >= 2 && inner.ends_with(&inner[..1]) => {
+                    &inner[1..inner.len() - 1]
+                }
+                _ if !inner.is_empty() && inner.bytes().all(|c| c.is_ascii_digit()) => inner,
+                _ => bail!("Unsupported JSONPath selector `[{}]`", inner),
+            };
+            (segment, &bracketed[end + 1..])
+        } else {
+            bail!("Unexpected `{}` in JSONPath", rest);
+        };
+
+        if segment.is_empty() || segment == "*" {
+            bail!("Unsupported JSONPath `{}`", path);
+        }
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        rest = remaining;
+    }
+
+    Ok(pointer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cdl_dto::ingestion::Options;
+    use serde_json::json;
+    use serde_json::value::RawValue;
+    use test_case::test_case;
+
+    fn rule(
+        name: &str,
+        schema_id: Option<Uuid>,
+        operator: RuleOperator,
+        value: Value,
+    ) -> RoutingRule {
+        RoutingRule {
+            name: name.to_owned(),
+            schema_id,
+            path: "/location/region".to_owned(),
+            operator,
+            value,
+            insert_destination: name.to_owned(),
+        }
+    }
+
+    fn route(rules: &[RoutingRule], schema_id: Uuid, data: Value) -> Option<String> {
+        route_operation(rules, schema_id, data, Operation::Insert, None).unwrap()
+    }
+
+    fn route_operation(
+        rules: &[RoutingRule],
+        schema_id: Uuid,
+        data: Value,
+        operation: Operation,
+        repository_id: Option<&str>,
+    ) -> anyhow::Result<Option<String>> {
+        let data = RawValue::from_string(data.to_string()).unwrap();
+        let event = DataRouterInsertMessage {
+            version: "1.0",
+            object_id: Some(Uuid::from_u128(3)),
+            schema_id,
+            data: &data,
+            operation,
+            idempotency_key: None,
+            options: Options {
+                repository_id: repository_id.map(str::to_owned),
+            },
+        };
+
+        destination(rules, &event).map(|destination| destination.map(str::to_owned))
+    }
+
+    #[test_case(RuleOperator::Equals, json!("eu"), json!({"location": {"region": "eu"}}) => true)]
+    #[test_case(RuleOperator::Equals, json!("eu"), json!({"location": {"region": "us"}}) => false)]
+    #[test_case(RuleOperator::Equals, json!("eu"), json!({}) => false)]
+    #[test_case(RuleOperator::NotEquals, json!("eu"), json!({"location": {"region": "us"}}) => true)]
+    #[test_case(RuleOperator::NotEquals, json!("eu"), json!({}) => true)]
+    #[test_case(RuleOperator::In, json!(["eu", "uk"]), json!({"location": {"region": "uk"}}) => true)]
+    #[test_case(RuleOperator::In, json!(["eu", "uk"]), json!({"location": {"region": "us"}}) => false)]
+    #[test_case(RuleOperator::Exists, Value::Null, json!({"location": {"region": 1}}) => true)]
+    #[test_case(RuleOperator::Exists, Value::Null, json!({"location": {"region": null}}) => false)]
+    fn matches_payload(operator: RuleOperator, value: Value, data: Value) -> bool {
+        rule("rule", None, operator, value).matches(&data)
+    }
+
+    #[test]
+    fn first_matching_rule_of_schema_wins() {
+        let schema_id = Uuid::from_u128(1);
+        let rules = vec![
+            rule(
+                "other-schema",
+                Some(Uuid::from_u128(2)),
+                RuleOperator::Exists,
+                Value::Null,
+            ),
+            rule("eu", Some(schema_id), RuleOperator::Equals, json!("eu")),
+            rule("any", None, RuleOperator::Exists, Value::Null),
+        ];
+
+        assert_eq!(
+            route(&rules, schema_id, json!({"location": {"region": "eu"}})),
+            Some("eu".to_owned())
+        );
+        assert_eq!(
+            route(&rules, schema_id, json!({"location": {"region": "us"}})),
+            Some("any".to_owned())
+        );
+        assert_eq!(route(&rules, schema_id, json!({})), None);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(rule("in", None, RuleOperator::In, json!("eu"))
+            .check()
+            .is_err());
+
+        let mut invalid_path = rule("path", None, RuleOperator::Exists, Value::Null);
+        invalid_path.path = "location.region".to_owned();
+        assert!(invalid_path.check().is_err());
+    }
+
+    #[test_case(Operation::Delete, json!(null))]
+    #[test_case(Operation::Patch, json!({"name": "John"}))]
+    #[test_case(Operation::Patch, json!({"location": {"region": "us"}}))]
+    fn deletes_and_patches_are_routed_by_repository(operation: Operation, data: Value) {
+        let schema_id = Uuid::from_u128(1);
+        let rules = vec![rule(
+            "not-eu",
+            Some(schema_id),
+            RuleOperator::NotEquals,
+            json!("eu"),
+        )];
+
+        assert!(route_operation(&rules, schema_id, data.clone(), operation, None).is_err());
+        assert_eq!(
+            route_operation(&rules, schema_id, data.clone(), operation, Some("eu")).unwrap(),
+            None
+        );
+        // Schemas without rules are routed as before
+        assert_eq!(
+            route_operation(&rules, Uuid::from_u128(2), data, operation, None).unwrap(),
+            None
+        );
+    }
+
+    #[test_case("$.location.region" => Some("/location/region".to_owned()))]
+    #[test_case("$['location'][\"region\"]" => Some("/location/region".to_owned()))]
+    #[test_case("$.items[0].a/b" => Some("/items/0/a~1b".to_owned()))]
+    #[test_case("$" => Some("".to_owned()))]
+    #[test_case("$..region" => None)]
+    #[test_case("$.items[*]" => None)]
+    #[test_case("$.items[?(@.a)]" => None)]
+    #[test_case("location.region" => None)]
+    fn converts_json_path(path: &str) -> Option<String> {
+        json_path_to_pointer(path).ok()
+    }
+
+    #[test]
+    fn matches_json_path() {
+        let mut rule = rule("eu", None, RuleOperator::Equals, json!("eu"));
+        rule.path = "$.location.region".to_owned();
+
+        assert!(rule.check().is_ok());
+        assert!(rule.matches(&json!({"location": {"region": "eu"}})));
+    }
+}
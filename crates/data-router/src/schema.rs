@@ -2,7 +2,14 @@ use cache::{CacheSupplier, DynamicCache};
 use rpc::schema_registry::Id;
 use uuid::Uuid;
 
-pub type SchemaCache = DynamicCache<SchemaMetadataSupplier, Uuid, String>;
+pub type SchemaCache = DynamicCache<SchemaMetadataSupplier, Uuid, SchemaMetadata>;
+
+#[derive(Clone, Debug)]
+pub struct SchemaMetadata {
+    pub insert_destination: String,
+    /// JSON pointers to fields, from which ids of objects sent without them are derived
+    pub natural_key: Vec<String>,
+}
 
 pub struct SchemaMetadataSupplier {
     schema_registry_url: String,
@@ -17,16 +24,20 @@ impl SchemaMetadataSupplier {
 }
 
 #[async_trait::async_trait]
-impl CacheSupplier<Uuid, String> for SchemaMetadataSupplier {
-    async fn retrieve(&self, key: Uuid) -> anyhow::Result<String> {
+impl CacheSupplier<Uuid, SchemaMetadata> for SchemaMetadataSupplier {
+    async fn retrieve(&self, key: Uuid) -> anyhow::Result<SchemaMetadata> {
         let mut client = rpc::schema_registry::connect(self.schema_registry_url.to_owned()).await?;
 
-        Ok(client
+        let metadata = client
             .get_schema_metadata(Id {
                 id: key.to_string(),
             })
             .await?
-            .into_inner()
-            .insert_destination)
+            .into_inner();
+
+        Ok(SchemaMetadata {
+            insert_destination: metadata.insert_destination,
+            natural_key: metadata.natural_key,
+        })
     }
 }
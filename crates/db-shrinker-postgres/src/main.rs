@@ -59,14 +59,14 @@ fn main() -> anyhow::Result<()> {
     for id in changed_ids {
         let object_id: Uuid = id.get(0);
 
-        shrink_by_id(&mut client, object_id)
+        shrink_by_id(&mut client, &opts.schema, object_id)
             .with_context(|| format!("Shrinking of {}", object_id))?;
     }
 
     Ok(())
 }
 
-fn shrink_by_id(client: &mut Client, object_id: Uuid) -> anyhow::Result<()> {
+fn shrink_by_id(client: &mut Client, schema: &str, object_id: Uuid) -> anyhow::Result<()> {
     let rows = get_all_with_id(client, object_id)?;
     let version: i64 = rows
         .last()
@@ -81,7 +81,8 @@ fn shrink_by_id(client: &mut Client, object_id: Uuid) -> anyhow::Result<()> {
     );
     trace!("Rows: {:?}", rows);
 
-    let document = minimize_document(rows).context("Failed minimizing document")?;
+    let document =
+        minimize_document(client, schema, rows)?.context("Failed minimizing document")?;
 
     trace!(
         "After minimization: {}",
@@ -95,7 +96,7 @@ fn shrink_by_id(client: &mut Client, object_id: Uuid) -> anyhow::Result<()> {
         &[&object_id, &version],
     )?;
     transaction.query(
-        "UPDATE data SET payload = $1 WHERE object_id = $2 AND version = $3",
+        "UPDATE data SET payload = $1, patch = NULL WHERE object_id = $2 AND version = $3",
         &[&document, &object_id, &version],
     )?;
 
@@ -104,18 +105,48 @@ fn shrink_by_id(client: &mut Client, object_id: Uuid) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn minimize_document(rows: impl IntoIterator<Item = Row>) -> Option<JsonValue> {
-    rows.into_iter()
-        .map(|row| row.get::<_, JsonValue>(1))
-        .reduce(|mut acc, next| {
-            merge(&mut acc, next);
-            acc
-        })
+/// Versions created by `patch` operation carry the applied patch. It is merged with the same
+/// `json_merge_patch` function command service uses, so fields removed by the patch stay removed.
+fn minimize_document(
+    client: &mut Client,
+    schema: &str,
+    rows: impl IntoIterator<Item = Row>,
+) -> anyhow::Result<Option<JsonValue>> {
+    let mut document: Option<JsonValue> = None;
+    for row in rows {
+        let payload: JsonValue = row.get(1);
+        let patch: Option<JsonValue> = row.get(2);
+
+        document = match (document, patch) {
+            (document, Some(patch)) => Some(merge_patch(client, schema, document, patch)?),
+            (Some(mut document), None) => {
+                merge(&mut document, payload);
+                Some(document)
+            }
+            (None, None) => Some(payload),
+        };
+    }
+    Ok(document)
+}
+
+fn merge_patch(
+    client: &mut Client,
+    schema: &str,
+    target: Option<JsonValue>,
+    patch: JsonValue,
+) -> anyhow::Result<JsonValue> {
+    let row = client
+        .query_one(
+            format!("SELECT {}.json_merge_patch($1::jsonb, $2::jsonb)", schema).as_str(),
+            &[&target, &patch],
+        )
+        .context("Applying merge patch")?;
+    Ok(row.get(0))
 }
 
 fn get_all_with_id(client: &mut Client, object_id: Uuid) -> anyhow::Result<Vec<Row>> {
     let entries = client.query(
-        "SELECT version, payload FROM data WHERE object_id = $1 ORDER BY version ASC",
+        "SELECT version, payload, patch FROM data WHERE object_id = $1 ORDER BY version ASC",
         &[&object_id],
     )?;
     Ok(entries)
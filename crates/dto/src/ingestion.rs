@@ -7,7 +7,25 @@ pub trait OwnMessage {
     fn to_owned_message(&self) -> Self::Owned;
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Operation applied to the object by the repository
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    /// Stores `data` as a new version of the object
+    Insert,
+    /// Removes the object, `data` is ignored
+    Delete,
+    /// Stores latest version of the object with `data` applied as JSON merge patch (RFC 7396)
+    Patch,
+}
+
+impl Default for Operation {
+    fn default() -> Self {
+        Operation::Insert
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BorrowedInsertMessage<'a> {
     pub object_id: Uuid,
@@ -15,6 +33,15 @@ pub struct BorrowedInsertMessage<'a> {
     pub timestamp: i64,
     #[serde(borrow)]
     pub data: &'a RawValue,
+    #[serde(default)]
+    pub operation: Operation,
+    /// Client supplied key, repositories store only the first message with given key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Names of repositories of composite command service the message is written to, all when missing.
+    /// Dead-lettered messages carry only repositories which failed to store them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -25,6 +52,15 @@ pub struct OwnedInsertMessage {
     pub schema_id: Uuid,
     pub timestamp: i64,
     pub data: Box<RawValue>,
+    #[serde(default)]
+    pub operation: Operation,
+    /// Client supplied key, repositories store only the first message with given key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Names of repositories of composite command service the message is written to, all when missing.
+    /// Dead-lettered messages carry only repositories which failed to store them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<Vec<String>>,
 }
 
 impl OwnMessage for BorrowedInsertMessage<'_> {
@@ -36,6 +72,9 @@ impl OwnMessage for BorrowedInsertMessage<'_> {
             schema_id: self.schema_id,
             timestamp: self.timestamp,
             data: self.data.to_owned(),
+            operation: self.operation,
+            idempotency_key: self.idempotency_key.clone(),
+            repositories: self.repositories.clone(),
         }
     }
 }
@@ -55,11 +94,18 @@ where
 #[serde(rename_all = "camelCase")]
 pub struct DataRouterInsertMessage<'a> {
     pub version: &'a str,
-    pub object_id: Uuid,
+    /// Assigned by data router when missing, either derived from natural key of the schema or generated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_id: Option<Uuid>,
     pub schema_id: Uuid,
     #[serde(borrow)]
     pub data: &'a RawValue,
     #[serde(default)]
+    pub operation: Operation,
+    /// Client supplied key, repositories store only the first message with given key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    #[serde(default)]
     pub options: Options,
 }
 
@@ -68,3 +114,13 @@ pub struct DataRouterInsertMessage<'a> {
 pub struct Options {
     pub repository_id: Option<String>,
 }
+
+/// Message which command service failed to store, published to dead-letter destination
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterMessage {
+    pub message: OwnedInsertMessage,
+    /// Resolution of the last attempt
+    pub error: String,
+    pub attempts: u32,
+}
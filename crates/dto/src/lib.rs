@@ -1,6 +1,7 @@
 pub mod edges;
 pub mod ingestion;
 pub mod materialization;
+pub mod query;
 
 use thiserror::Error;
 
@@ -19,7 +19,7 @@ use rpc::schema_registry::types::LogicOperator;
 use crate::{RequestError, RequestResult, ResponseResult, TryFromRpc, TryIntoRpc};
 
 /// View's filter
-#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc)]
 #[serde(rename_all = "snake_case")]
 #[rpc(
     rpc = "rpc::schema_registry::Filter",
@@ -33,13 +33,15 @@ pub enum Filter {
     ComplexFilter(ComplexFilter),
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(
+    Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc,
+)]
 #[rpc(transparent, rpc = "rpc::schema_registry::SimpleFilter")]
 pub struct SimpleFilter {
     pub filter: SimpleFilterKind,
 }
 
-#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc)]
 #[serde(rename_all = "snake_case")]
 #[rpc(
     rpc = "rpc::schema_registry::SimpleFilter",
@@ -50,14 +52,16 @@ pub enum SimpleFilterKind {
     Equals(EqualsFilter),
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(
+    Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc,
+)]
 #[rpc(rpc = "rpc::schema_registry::EqualsFilter")]
 pub struct EqualsFilter {
     pub lhs: FilterValue,
     pub rhs: FilterValue,
 }
 
-#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc)]
 #[serde(rename_all = "snake_case")]
 #[rpc(
     rpc = "rpc::schema_registry::FilterValue",
@@ -71,7 +75,7 @@ pub enum FilterValue {
     Computed(ComputedFilter),
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SchemaFieldFilter {
     pub schema_id: LocalId,
     pub field_path: String,
@@ -96,7 +100,7 @@ impl TryIntoRpc for SchemaFieldFilter {
     }
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ViewPathFilter {
     pub field_path: String,
 }
@@ -118,7 +122,7 @@ impl TryIntoRpc for ViewPathFilter {
     }
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(transparent)]
 pub struct RawValueFilter {
     pub value: Json<Value>,
@@ -141,13 +145,15 @@ impl TryIntoRpc for RawValueFilter {
     }
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(
+    Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc,
+)]
 #[rpc(rpc = "rpc::schema_registry::ComputedFilter")]
 pub struct ComputedFilter {
     pub computation: Computation,
 }
 
-#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(Clone, Debug, Union, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc)]
 #[serde(rename_all = "snake_case")]
 #[rpc(
     rpc = "rpc::schema_registry::Computation",
@@ -162,7 +168,7 @@ pub enum Computation {
     Equals(EqualsComputation),
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(transparent)]
 pub struct RawValueComputation {
     pub value: Json<Value>,
@@ -185,7 +191,7 @@ impl TryIntoRpc for RawValueComputation {
     }
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq)]
 pub struct FieldValueComputation {
     pub schema_id: LocalId,
     pub field_path: String,
@@ -214,7 +220,9 @@ impl TryIntoRpc for FieldValueComputation {
     }
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, TryFromRpc, TryIntoRpc)]
+#[derive(
+    Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq, TryFromRpc, TryIntoRpc,
+)]
 #[rpc(rpc = "rpc::schema_registry::EqualsComputation")]
 pub struct EqualsComputation {
     #[rpc(boxed, into_boxed)] // TODO: Document whats the difference between boxed and into_boxed
@@ -223,7 +231,7 @@ pub struct EqualsComputation {
     pub rhs: Box<Computation>,
 }
 
-#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, SimpleObject, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ComplexFilter {
     pub operator: LogicOperator,
     pub operands: Vec<Filter>,
@@ -397,6 +405,13 @@ impl TryFromRpc<rpc::schema_registry::FullView> for FullView {
 pub struct Request {
     pub view_id: Uuid,
     pub schemas: HashMap<Uuid, Schema>,
+    /// Additional filter, applied together with view's own filters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+    /// Deleted objects, rows built from them are removed from the view.
+    /// Request with no `schemas` but with deleted objects does not rebuild the whole view.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub deleted_object_ids: HashSet<Uuid>,
 }
 
 impl Request {
@@ -414,7 +429,7 @@ pub struct Schema {
 }
 
 impl TryFrom<rpc::object_builder::View> for Request {
-    type Error = uuid::Error;
+    type Error = RequestError;
 
     fn try_from(value: rpc::object_builder::View) -> Result<Self, Self::Error> {
         let view_id = value.view_id.parse()?;
@@ -436,8 +451,14 @@ impl TryFrom<rpc::object_builder::View> for Request {
                 Ok((schema_id, Schema { object_ids }))
             })
             .collect::<Result<_, Self::Error>>()?;
+        let filter = value.filter.map(Filter::try_from_rpc).transpose()?;
 
-        Ok(Self { view_id, schemas })
+        Ok(Self {
+            view_id,
+            schemas,
+            filter,
+            deleted_object_ids: HashSet::new(),
+        })
     }
 }
 
@@ -446,6 +467,16 @@ pub struct PostgresMaterializerOptions {
     pub table: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SqliteMaterializerOptions {
+    pub table: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FileMaterializerOptions {
+    pub file: String,
+}
+
 fn create_non_zero_u8(num: u32) -> RequestResult<NonZeroU8> {
     let num: u8 = num.try_into().map_err(|err| {
         RequestError::new(format!(
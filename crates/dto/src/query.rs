@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{RequestResult, ResponseResult};
+
+/// Body of paged query of schema objects
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaQuery {
+    /// Maximal number of objects in the page, 100 when missing and at most 1000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Continue after the last object of previous page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Cursor>,
+    /// Objects with equal values are ordered by their ids
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_by: Option<Ordering>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Predicate>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Ordering {
+    /// JSON pointer to the field
+    pub path: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// Last object of the page
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Cursor {
+    pub object_id: Uuid,
+    /// Value of the ordering field. Required when `orderBy` is present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// Condition on payloads, fields are selected by JSON pointers.
+/// Missing fields don't match any comparison.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Predicate {
+    And(Vec<Predicate>),
+    Or(Vec<Predicate>),
+    Not(Box<Predicate>),
+    Equals {
+        path: String,
+        value: Value,
+    },
+    /// Matches only values of the same JSON type as bounds
+    Range {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lower: Option<Bound>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        upper: Option<Bound>,
+    },
+    In {
+        path: String,
+        values: Vec<Value>,
+    },
+    /// Field is present and is not `null`
+    Exists {
+        path: String,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Bound {
+    pub value: Value,
+    #[serde(default)]
+    pub inclusive: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectPage {
+    pub objects: Vec<PageObject>,
+    /// Cursor of the last object, missing when there are no more objects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Cursor>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PageObject {
+    pub object_id: Uuid,
+    pub payload: Value,
+}
+
+impl SchemaQuery {
+    pub fn into_rpc(self, schema_id: Uuid) -> ResponseResult<rpc::query_service::SchemaQuery> {
+        Ok(rpc::query_service::SchemaQuery {
+            schema_id: schema_id.to_string(),
+            limit: self.limit.unwrap_or_default(),
+            after: self.after.map(Cursor::into_rpc).transpose()?,
+            order_by: self.order_by.map(|order_by| rpc::query_service::Ordering {
+                path: order_by.path,
+                descending: order_by.descending,
+            }),
+            filter: self.filter.map(Predicate::into_rpc).transpose()?,
+        })
+    }
+}
+
+impl Cursor {
+    pub fn from_rpc(rpc: rpc::query_service::Cursor) -> RequestResult<Self> {
+        Ok(Self {
+            object_id: rpc.object_id.parse()?,
+            value: if rpc.value.is_empty() {
+                None
+            } else {
+                Some(serde_json::from_str(&rpc.value)?)
+            },
+        })
+    }
+
+    pub fn into_rpc(self) -> ResponseResult<rpc::query_service::Cursor> {
+        Ok(rpc::query_service::Cursor {
+            object_id: self.object_id.to_string(),
+            value: self
+                .value
+                .map(|value| serde_json::to_string(&value))
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl Predicate {
+    pub fn into_rpc(self) -> ResponseResult<rpc::query_service::Predicate> {
+        use rpc::query_service::predicate::Predicate as Rpc;
+
+        let predicate = match self {
+            Predicate::And(predicates) => Rpc::And(Self::list_into_rpc(predicates)?),
+            Predicate::Or(predicates) => Rpc::Or(Self::list_into_rpc(predicates)?),
+            Predicate::Not(predicate) => Rpc::Not(Box::new(predicate.into_rpc()?)),
+            Predicate::Equals { path, value } => Rpc::Equals(rpc::query_service::EqualsPredicate {
+                path,
+                value: serde_json::to_string(&value)?,
+            }),
+            Predicate::Range { path, lower, upper } => {
+                Rpc::Range(rpc::query_service::RangePredicate {
+                    path,
+                    lower: lower.map(Bound::into_rpc).transpose()?,
+                    upper: upper.map(Bound::into_rpc).transpose()?,
+                })
+            }
+            Predicate::In { path, values } => Rpc::InList(rpc::query_service::InPredicate {
+                path,
+                values: values
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<Result<_, _>>()?,
+            }),
+            Predicate::Exists { path } => Rpc::Exists(rpc::query_service::ExistsPredicate { path }),
+        };
+
+        Ok(rpc::query_service::Predicate {
+            predicate: Some(predicate),
+        })
+    }
+
+    fn list_into_rpc(
+        predicates: Vec<Predicate>,
+    ) -> ResponseResult<rpc::query_service::PredicateList> {
+        Ok(rpc::query_service::PredicateList {
+            predicates: predicates
+                .into_iter()
+                .map(Predicate::into_rpc)
+                .collect::<ResponseResult<_>>()?,
+        })
+    }
+}
+
+impl Bound {
+    fn into_rpc(self) -> ResponseResult<rpc::query_service::Bound> {
+        Ok(rpc::query_service::Bound {
+            value: serde_json::to_string(&self.value)?,
+            inclusive: self.inclusive,
+        })
+    }
+}
+
+impl ObjectPage {
+    pub fn from_rpc(rpc: rpc::query_service::ObjectPage) -> RequestResult<Self> {
+        Ok(Self {
+            objects: rpc
+                .objects
+                .into_iter()
+                .map(|object| -> RequestResult<_> {
+                    Ok(PageObject {
+                        object_id: object.object_id.parse()?,
+                        payload: serde_json::from_slice(&object.payload)?,
+                    })
+                })
+                .collect::<RequestResult<_>>()?,
+            next: rpc.next.map(Cursor::from_rpc).transpose()?,
+        })
+    }
+}
@@ -10,9 +10,9 @@ use itertools::Itertools;
 use metrics_utils::{self as metrics, counter};
 use rpc::edge_registry::edge_registry_server::EdgeRegistry;
 use rpc::edge_registry::{
-    AddSchemaRelation, Edge, Empty, ObjectIdQuery, ObjectRelations, RelationDetails, RelationId,
-    RelationIdQuery, RelationList, RelationQuery, RelationResponse, RelationTree, SchemaId,
-    SchemaRelation, TreeObject, TreeQuery, ValidateRelationQuery,
+    AddSchemaRelation, ChildObjectsQuery, Edge, Empty, ObjectIdQuery, ObjectIds, ObjectRelations,
+    RelationDetails, RelationId, RelationIdQuery, RelationList, RelationQuery, RelationResponse,
+    RelationTree, SchemaId, SchemaRelation, TreeObject, TreeQuery, ValidateRelationQuery,
 };
 use serde::{Deserialize, Serialize};
 use settings_utils::PostgresSettings;
@@ -315,6 +315,24 @@ impl EdgeRegistryImpl {
             .map(|row| (row.get(0), row.get(1))))
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn get_parents_impl(
+        &self,
+        relation_id: Uuid,
+        child_object_ids: &[Uuid],
+    ) -> anyhow::Result<impl Iterator<Item = Uuid>> {
+        counter!("cdl.edge-registry.get-parents", 1);
+        let conn = self.connect().await?;
+        Ok(conn
+            .query(
+                "SELECT DISTINCT parent_object_id FROM edges WHERE relation_id = $1 AND child_object_id = ANY($2)",
+                &[&relation_id, &child_object_ids],
+            )
+            .await?
+            .into_iter()
+            .map(|row| row.get(0)))
+    }
+
     fn resolve_tree_recursive<'a, F, S, R>(
         &'a self,
         conn: &'a PooledConnection<PostgresConnectionManager<NoTls>>,
@@ -714,6 +732,37 @@ impl EdgeRegistry for EdgeRegistryImpl {
         }))
     }
 
+    async fn get_parents(
+        &self,
+        request: Request<ChildObjectsQuery>,
+    ) -> Result<Response<ObjectIds>, Status> {
+        let request = request.into_inner();
+
+        trace!(
+            "Received `get_parents` message with relation_id `{}` and {} children",
+            request.relation_id,
+            request.child_object_ids.len()
+        );
+
+        let relation_id = Uuid::from_str(&request.relation_id)
+            .map_err(|_| Status::invalid_argument("relation_id"))?;
+        let child_object_ids = request
+            .child_object_ids
+            .iter()
+            .map(|child_object_id| Uuid::from_str(child_object_id))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| Status::invalid_argument("child_object_ids"))?;
+
+        let rows = self
+            .get_parents_impl(relation_id, &child_object_ids)
+            .await
+            .map_err(|err| db_communication_error("get_parents", err))?;
+
+        Ok(Response::new(ObjectIds {
+            object_ids: rows.map(|uuid| uuid.to_string()).collect(),
+        }))
+    }
+
     #[tracing::instrument(skip(self))]
     async fn heartbeat(&self, _request: Request<Empty>) -> Result<Response<Empty>, Status> {
         //empty
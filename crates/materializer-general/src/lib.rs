@@ -1,10 +1,13 @@
 use crate::view::ViewCache;
 use cache::DynamicCache;
-use plugins::{MaterializerPlugin, PostgresMaterializer};
-use rpc::materializer_general::{general_materializer_server::GeneralMaterializer, Empty, Options};
+use plugins::{FileMaterializer, MaterializerPlugin, PostgresMaterializer, SqliteMaterializer};
+use rpc::materializer_general::{
+    general_materializer_server::GeneralMaterializer, DeletedObjects, Empty, Options,
+};
 use rpc::{common::RowDefinition, materializer_general::MaterializedView};
 use serde::Serialize;
-use settings_utils::PostgresSettings;
+use serde_json::Value;
+use settings::Settings;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -54,31 +57,75 @@ impl IntoSerialize<MaterializationNotification> for MaterializedView {
 type MaterializerNotificationPublisher =
     Arc<Mutex<NotificationPublisher<MaterializedView, MaterializationNotification>>>;
 
+/// Key in `materializer_options` selecting plugin which handles the view
+const PLUGIN_KEY: &str = "plugin";
+
 pub struct MaterializerImpl {
-    materializer: Arc<dyn MaterializerPlugin>,
+    plugins: HashMap<String, Arc<dyn MaterializerPlugin>>,
+    default_plugin: String,
     notification_publisher: MaterializerNotificationPublisher,
     view_cache: ViewCache,
 }
 
 impl MaterializerImpl {
     pub async fn new(
-        args: PostgresSettings,
+        settings: &Settings,
         notification_publisher: MaterializerNotificationPublisher,
-        schema_registry_url: String,
-        cache_size: usize,
     ) -> anyhow::Result<Self> {
+        let mut plugins: HashMap<String, Arc<dyn MaterializerPlugin>> = HashMap::new();
+        if let Some(postgres) = &settings.postgres {
+            plugins.insert(
+                "postgres".to_string(),
+                Arc::new(PostgresMaterializer::new(postgres).await?),
+            );
+        }
+        if let Some(sqlite) = &settings.sqlite {
+            plugins.insert(
+                "sqlite".to_string(),
+                Arc::new(SqliteMaterializer::new(sqlite).await?),
+            );
+        }
+        if let Some(file) = &settings.file {
+            plugins.insert(
+                "file".to_string(),
+                Arc::new(FileMaterializer::new(file).await?),
+            );
+        }
+
+        if plugins.is_empty() {
+            anyhow::bail!("No materializer plugin configured");
+        }
+        tracing::info!(plugins = ?plugins.keys().collect::<Vec<_>>(), "Registered materializer plugins");
+
         Ok(Self {
-            materializer: Arc::new(PostgresMaterializer::new(&args).await?),
+            plugins,
+            default_plugin: settings.default_plugin.clone(),
             notification_publisher,
-            view_cache: DynamicCache::new(cache_size, ViewSupplier::new(schema_registry_url)),
+            view_cache: DynamicCache::new(
+                settings.cache_capacity,
+                ViewSupplier::new(settings.services.schema_registry_url.clone()),
+            ),
         })
     }
 }
 
 impl MaterializerImpl {
+    fn plugin(&self, options: &Value) -> anyhow::Result<&dyn MaterializerPlugin> {
+        let name = match options.get(PLUGIN_KEY) {
+            Some(Value::String(name)) => name.as_str(),
+            Some(other) => anyhow::bail!("`{}` must be a string, got {}", PLUGIN_KEY, other),
+            None => self.default_plugin.as_str(),
+        };
+
+        self.plugins
+            .get(name)
+            .map(|plugin| plugin.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("Materializer plugin `{}` is not enabled", name))
+    }
+
     fn validate_options_inner(&self, options: &str) -> anyhow::Result<()> {
-        let options: serde_json::Value = serde_json::from_str(&options)?;
-        self.materializer.validate_options(options)?;
+        let options: Value = serde_json::from_str(&options)?;
+        self.plugin(&options)?.validate_options(options)?;
         Ok(())
     }
 }
@@ -118,13 +165,17 @@ impl GeneralMaterializer for MaterializerImpl {
             .map_err(anyhow::Error::from)
             .map_err(error_handler)?;
         let view_definition = self.view_cache.get(view_id).await.map_err(error_handler)?;
+        let options: Value = serde_json::from_str(&materialized_view.options.options)
+            .map_err(anyhow::Error::from)
+            .map_err(error_handler)?;
+        let plugin = self.plugin(&options).map_err(error_handler)?;
 
         let publisher = self.notification_publisher.clone();
         let publisher = publisher.lock().await; // TODO: Should we have lock active for the whole time?
         let instance =
             NotificationPublisher::clone(&publisher).with_message_body(&materialized_view);
 
-        self.materializer
+        plugin
             .upsert_view(materialized_view, view_definition.clone())
             .await
             .map_err(error_handler)?;
@@ -133,4 +184,36 @@ impl GeneralMaterializer for MaterializerImpl {
         }
         Ok(tonic::Response::new(Empty {}))
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_objects(
+        &self,
+        request: tonic::Request<DeletedObjects>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        let deleted = request.into_inner();
+        tracing::debug!(?deleted, "deleted objects");
+
+        let error_handler = |err| {
+            tracing::error!("Materialization error` {:?}", err);
+            tonic::Status::internal(format!("{}", err))
+        };
+
+        let options: Value = serde_json::from_str(&deleted.options.options)
+            .map_err(anyhow::Error::from)
+            .map_err(error_handler)?;
+        let object_ids = deleted
+            .object_ids
+            .iter()
+            .map(|id| id.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|err| tonic::Status::invalid_argument(format!("object_ids: {}", err)))?;
+
+        self.plugin(&options)
+            .map_err(error_handler)?
+            .delete_objects(options, object_ids)
+            .await
+            .map_err(error_handler)?;
+
+        Ok(tonic::Response::new(Empty {}))
+    }
 }
@@ -31,13 +31,8 @@ async fn main() -> anyhow::Result<()> {
         )
         .await?;
 
-    let materializer = MaterializerImpl::new(
-        settings.postgres,
-        Arc::new(Mutex::new(notification_publisher)),
-        settings.services.schema_registry_url,
-        settings.cache_capacity,
-    )
-    .await?;
+    let materializer =
+        MaterializerImpl::new(&settings, Arc::new(Mutex::new(notification_publisher))).await?;
 
     utils::status_endpoints::mark_as_started();
 
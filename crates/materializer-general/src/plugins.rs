@@ -1,6 +1,9 @@
-use cdl_dto::materialization::FullView;
+use anyhow::Context;
+use cdl_dto::materialization::{FieldDefinition, FullView};
 use rpc::materializer_general::MaterializedView;
 use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
 
 #[async_trait::async_trait]
 pub trait MaterializerPlugin: Send + Sync {
@@ -10,8 +13,118 @@ pub trait MaterializerPlugin: Send + Sync {
         view: MaterializedView,
         view_definition: FullView,
     ) -> anyhow::Result<()>;
+    /// Removes rows built from any of the deleted objects
+    async fn delete_objects(&self, options: Value, object_ids: Vec<Uuid>) -> anyhow::Result<()>;
 }
 
+mod file;
 mod postgres;
+mod sqlite;
 
+pub use file::FileMaterializer;
 pub use postgres::PostgresMaterializer;
+pub use sqlite::SqliteMaterializer;
+
+#[derive(Debug)]
+struct RowDefinition {
+    object_ids: Vec<Uuid>,
+    fields: HashMap<String, Value>,
+}
+
+#[derive(Debug)]
+struct Field {
+    sql_name: String,
+    name: String,
+    json_path: String,
+}
+
+impl Field {
+    fn value<'a>(&self, row: &'a RowDefinition) -> anyhow::Result<&'a Value> {
+        let field = row.fields.get(&self.name).context("Field not found")?;
+        field
+            .pointer(&self.json_path)
+            .context("Subobject not found")
+    }
+}
+
+fn parse_rows(rows: Vec<rpc::common::RowDefinition>) -> anyhow::Result<Vec<RowDefinition>> {
+    rows.into_iter()
+        .map(|row| {
+            let object_ids = row
+                .object_ids
+                .into_iter()
+                .map(|oid| oid.parse())
+                .collect::<Result<_, _>>()?;
+            let fields = row
+                .fields
+                .into_iter()
+                .map(|(key, field)| {
+                    let field = serde_json::from_str(&field)?;
+                    Ok((key, field))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(RowDefinition { object_ids, fields })
+        })
+        .collect()
+}
+
+fn validate_identifier(identifier: &str) -> anyhow::Result<()> {
+    let mut chars = identifier.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    anyhow::ensure!(valid, "`{}` is not a valid identifier", identifier);
+    Ok(())
+}
+
+fn get_field_list(definition: &FullView) -> Vec<Field> {
+    #[derive(Debug)]
+    struct PartialFieldDefinition<'a> {
+        sql_name: String,
+        field_name: String,
+        json_path: Vec<String>,
+        definition: &'a FieldDefinition,
+    }
+
+    let mut fields = vec![];
+
+    let mut fields_to_process = definition
+        .fields
+        .iter()
+        .map(|x| PartialFieldDefinition {
+            json_path: vec!["".to_owned()],
+            sql_name: x.0.to_owned(),
+            field_name: x.0.to_owned(),
+            definition: x.1,
+        })
+        .collect::<VecDeque<_>>();
+
+    while let Some(field) = fields_to_process.pop_front() {
+        match field.definition {
+            FieldDefinition::Simple { .. } | FieldDefinition::Computed { .. } => {
+                fields.push(Field {
+                    sql_name: field.sql_name,
+                    name: field.field_name,
+                    json_path: field.json_path.join("/"),
+                });
+            }
+            FieldDefinition::SubObject { fields, .. } => {
+                fields_to_process = fields
+                    .iter()
+                    .map(|x| PartialFieldDefinition {
+                        definition: x.1,
+                        sql_name: format!("{}_{}", field.sql_name, x.0),
+                        field_name: field.field_name.clone(),
+                        json_path: {
+                            let mut vec = field.json_path.clone();
+                            vec.push(x.0.clone());
+                            vec
+                        },
+                    })
+                    .chain(fields_to_process.into_iter())
+                    .collect()
+            }
+        }
+    }
+
+    fields
+}
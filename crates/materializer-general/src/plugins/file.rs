@@ -0,0 +1,152 @@
+use super::{get_field_list, parse_rows, MaterializerPlugin};
+use crate::settings::FileSettings;
+use cdl_dto::materialization::{FileMaterializerOptions, FullView};
+use metrics_utils::{self as metrics, counter};
+use rpc::materializer_general::MaterializedView;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Stores materialized rows as JSON lines in a file in configured directory.
+/// Like table plugins, file holds one line per `object_ids` - upserted rows replace earlier lines
+/// built from the same objects and deleted objects remove every line built from any of them.
+/// File is rewritten on each change, therefore this plugin is meant for small views.
+pub struct FileMaterializer {
+    directory: PathBuf,
+    // Serializes read-modify-write cycles of files
+    lock: Mutex<()>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct FileRow {
+    object_ids: Vec<Uuid>,
+    fields: Map<String, Value>,
+}
+
+impl FileMaterializer {
+    pub async fn new(settings: &FileSettings) -> anyhow::Result<Self> {
+        tokio::fs::create_dir_all(&settings.directory).await?;
+
+        Ok(Self {
+            directory: settings.directory.clone(),
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn resolve_path(&self, options: &FileMaterializerOptions) -> anyhow::Result<PathBuf> {
+        let file = Path::new(&options.file);
+        anyhow::ensure!(
+            file.components().all(|c| matches!(c, Component::Normal(_))),
+            "File `{}` must be a relative path inside the output directory",
+            options.file
+        );
+        Ok(self.directory.join(file))
+    }
+
+    async fn rewrite(
+        &self,
+        path: &Path,
+        update: impl FnOnce(&mut Vec<FileRow>),
+    ) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().await;
+
+        let mut rows = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<FileRow>, _>>()?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err.into()),
+        };
+
+        update(&mut rows);
+
+        let mut buffer = Vec::new();
+        for row in rows.iter() {
+            serde_json::to_writer(&mut buffer, row)?;
+            buffer.push(b'\n');
+        }
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Readers never observe partially written file
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        tokio::fs::write(&tmp_path, &buffer).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl MaterializerPlugin for FileMaterializer {
+    fn validate_options(&self, options: Value) -> anyhow::Result<()> {
+        let options: FileMaterializerOptions = serde_json::from_value(options)?;
+        self.resolve_path(&options)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn upsert_view(
+        &self,
+        view: MaterializedView,
+        view_definition: FullView,
+    ) -> anyhow::Result<()> {
+        counter!("cdl.materializer.file.upsert-materialized-view", 1);
+
+        let options: FileMaterializerOptions = serde_json::from_str(&view.options.options)?;
+        let path = self.resolve_path(&options)?;
+        let rows = parse_rows(view.rows)?;
+        let fields = get_field_list(&view_definition);
+
+        let mut new_rows = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            let mut values = Map::new();
+            for field in fields.iter() {
+                values.insert(field.sql_name.clone(), field.value(row)?.clone());
+            }
+            new_rows.push(FileRow {
+                object_ids: row.object_ids.clone(),
+                fields: values,
+            });
+        }
+
+        self.rewrite(&path, |file_rows| {
+            file_rows.retain(|file_row| {
+                new_rows
+                    .iter()
+                    .all(|new_row| new_row.object_ids != file_row.object_ids)
+            });
+            file_rows.extend(new_rows);
+        })
+        .await?;
+
+        counter!("cdl.materializer.file.store", rows.len() as u64);
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_objects(&self, options: Value, object_ids: Vec<Uuid>) -> anyhow::Result<()> {
+        counter!("cdl.materializer.file.delete-objects", 1);
+
+        let options: FileMaterializerOptions = serde_json::from_value(options)?;
+        let path = self.resolve_path(&options)?;
+
+        let object_ids: HashSet<Uuid> = object_ids.into_iter().collect();
+        self.rewrite(&path, |file_rows| {
+            file_rows.retain(|file_row| {
+                file_row
+                    .object_ids
+                    .iter()
+                    .all(|object_id| !object_ids.contains(object_id))
+            })
+        })
+        .await
+    }
+}
@@ -1,15 +1,15 @@
-use std::collections::VecDeque;
-use std::{collections::HashMap, convert::TryFrom, convert::TryInto};
+use std::convert::{TryFrom, TryInto};
 
-use super::MaterializerPlugin;
-use anyhow::Context;
+use super::{
+    get_field_list, parse_rows, validate_identifier, Field, MaterializerPlugin, RowDefinition,
+};
 use bb8_postgres::tokio_postgres::{types::Type, Config, NoTls};
 use bb8_postgres::{bb8, PostgresConnectionManager};
 use bb8_postgres::{
     bb8::{Pool, PooledConnection},
     tokio_postgres::{binary_copy::BinaryCopyInWriter, types::ToSql},
 };
-use cdl_dto::materialization::{FieldDefinition, FullView, PostgresMaterializerOptions};
+use cdl_dto::materialization::{FullView, PostgresMaterializerOptions};
 use futures::pin_mut;
 use itertools::Itertools;
 use metrics_utils::{self as metrics, counter};
@@ -18,8 +18,6 @@ use serde_json::Value;
 use settings_utils::PostgresSettings;
 use uuid::Uuid;
 
-// TODO: Move some of those structs to dto crate
-
 pub struct PostgresMaterializer {
     pool: Pool<PostgresConnectionManager<NoTls>>,
     schema: String,
@@ -31,46 +29,12 @@ struct PsqlView {
     rows: Vec<RowDefinition>,
 }
 
-#[derive(Debug)]
-struct RowDefinition {
-    object_ids: Vec<Uuid>,
-    fields: HashMap<String, Value>,
-}
-
-#[derive(Debug)]
-struct Field {
-    sql_name: String,
-    name: String,
-    json_path: String,
-    type_: Type,
-}
-
 impl TryFrom<MaterializedView> for PsqlView {
     type Error = anyhow::Error;
 
     fn try_from(view: MaterializedView) -> Result<Self, Self::Error> {
         let options = serde_json::from_str(&view.options.options)?;
-
-        let rows = view
-            .rows
-            .into_iter()
-            .map(|row| {
-                let object_ids = row
-                    .object_ids
-                    .into_iter()
-                    .map(|oid| oid.parse())
-                    .collect::<Result<_, _>>()?;
-                let fields = row
-                    .fields
-                    .into_iter()
-                    .map(|(key, field)| {
-                        let field = serde_json::from_str(&field)?;
-                        Ok((key, field))
-                    })
-                    .collect::<anyhow::Result<_>>()?;
-                Ok(RowDefinition { object_ids, fields })
-            })
-            .collect::<anyhow::Result<_>>()?;
+        let rows = parse_rows(view.rows)?;
 
         Ok(PsqlView { options, rows })
     }
@@ -78,10 +42,9 @@ impl TryFrom<MaterializedView> for PsqlView {
 
 #[async_trait::async_trait]
 impl MaterializerPlugin for PostgresMaterializer {
-    fn validate_options(&self, _options: Value) -> anyhow::Result<()> {
-        // TODO: Validate if options have proper structure
-        // TODO: Validate if table contains only ASCII letters (prevent sql injection)
-        Ok(())
+    fn validate_options(&self, options: Value) -> anyhow::Result<()> {
+        let options: PostgresMaterializerOptions = serde_json::from_value(options)?;
+        validate_identifier(&options.table)
     }
 
     #[tracing::instrument(skip(self))]
@@ -95,9 +58,9 @@ impl MaterializerPlugin for PostgresMaterializer {
         let psql_view: PsqlView = view.try_into()?;
         tracing::trace!(?psql_view, "PSQL View");
 
-        let mut conn = self.connect().await?;
+        validate_identifier(&psql_view.options.table)?;
 
-        //TODO: Validate if column names are only in ASCII letters and numbers (prevent sql injection)
+        let mut conn = self.connect().await?;
 
         if psql_view.rows.is_empty() {
             tracing::warn!("Materialized view is empty, skipping upserting");
@@ -134,6 +97,43 @@ impl MaterializerPlugin for PostgresMaterializer {
 
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_objects(&self, options: Value, object_ids: Vec<Uuid>) -> anyhow::Result<()> {
+        counter!("cdl.materializer.postgres.delete-objects", 1);
+
+        let options: PostgresMaterializerOptions = serde_json::from_value(options)?;
+        validate_identifier(&options.table)?;
+
+        let conn = self.connect().await?;
+
+        // Table is created with the first upsert, there is nothing to delete before it.
+        // Its name is not quoted, so Postgres stores it in lowercase
+        let exists: bool = conn
+            .query_one(
+                "SELECT EXISTS (\
+                     SELECT FROM information_schema.tables \
+                     WHERE table_schema = $1 AND table_name = lower($2)\
+                 )",
+                &[&self.schema, &options.table],
+            )
+            .await?
+            .get(0);
+        if !exists {
+            return Ok(());
+        }
+
+        let deleted = conn
+            .execute(
+                format!("DELETE FROM {} WHERE object_ids && $1", options.table).as_str(),
+                &[&object_ids],
+            )
+            .await?;
+
+        counter!("cdl.materializer.postgres.delete", deleted);
+
+        Ok(())
+    }
 }
 
 impl PostgresMaterializer {
@@ -150,8 +150,7 @@ impl PostgresMaterializer {
             row.clear();
             row.push(&m.object_ids);
             for field in fields {
-                let f = m.fields.get(&field.name).context("Field not found")?;
-                row.push(f.pointer(&field.json_path).context("Subobject not found")?);
+                row.push(field.value(m)?);
             }
 
             writer.as_mut().write(&row).await?;
@@ -169,6 +168,10 @@ impl PostgresMaterializer {
         let table = &view.options.table;
 
         let fields = get_field_list(definition);
+        // Field names are formatted into statements, so they can't be passed as parameters
+        for field in fields.iter() {
+            validate_identifier(&field.sql_name)?;
+        }
 
         let columns = fields.iter().map(|x| x.sql_name.to_owned()).join(", ");
         let update_columns = fields
@@ -193,7 +196,7 @@ impl PostgresMaterializer {
             .map(|f| format!("{} JSON NOT NULL", f.sql_name))
             .join(", ");
 
-        types.extend(fields.iter().map(|x| x.type_.clone()));
+        types.extend(fields.iter().map(|_| Type::JSON));
 
         tracing::debug!(?insert_stm, ?copy_stm, ?types, "Build query");
         Ok((copy_stm, insert_stm, types, columns, fields))
@@ -222,60 +225,6 @@ impl PostgresMaterializer {
     }
 }
 
-fn get_field_list(definition: &FullView) -> Vec<Field> {
-    #[derive(Debug)]
-    struct PartialFieldDefinition<'a> {
-        sql_name: String,
-        field_name: String,
-        json_path: Vec<String>,
-        definition: &'a FieldDefinition,
-    }
-
-    let mut fields = vec![];
-
-    let mut fields_to_process = definition
-        .fields
-        .iter()
-        .map(|x| PartialFieldDefinition {
-            json_path: vec!["".to_owned()],
-            sql_name: x.0.to_owned(),
-            field_name: x.0.to_owned(),
-            definition: x.1,
-        })
-        .collect::<VecDeque<_>>();
-
-    while let Some(field) = fields_to_process.pop_front() {
-        match field.definition {
-            FieldDefinition::Simple { .. } | FieldDefinition::Computed { .. } => {
-                fields.push(Field {
-                    sql_name: field.sql_name,
-                    name: field.field_name,
-                    json_path: field.json_path.join("/"),
-                    type_: Type::JSON,
-                });
-            }
-            FieldDefinition::SubObject { fields, .. } => {
-                fields_to_process = fields
-                    .iter()
-                    .map(|x| PartialFieldDefinition {
-                        definition: x.1,
-                        sql_name: format!("{}_{}", field.sql_name, x.0),
-                        field_name: field.field_name.clone(),
-                        json_path: {
-                            let mut vec = field.json_path.clone();
-                            vec.push(x.0.clone());
-                            vec
-                        },
-                    })
-                    .chain(fields_to_process.into_iter())
-                    .collect()
-            }
-        }
-    }
-
-    fields
-}
-
 impl PostgresMaterializer {
     async fn set_schema(
         &self,
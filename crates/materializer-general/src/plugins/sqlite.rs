@@ -0,0 +1,136 @@
+use super::{get_field_list, parse_rows, validate_identifier, MaterializerPlugin};
+use crate::settings::SqliteSettings;
+use cdl_dto::materialization::{FullView, SqliteMaterializerOptions};
+use itertools::Itertools;
+use metrics_utils::{self as metrics, counter};
+use rpc::materializer_general::MaterializedView;
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use uuid::Uuid;
+
+pub struct SqliteMaterializer {
+    pool: SqlitePool,
+}
+
+impl SqliteMaterializer {
+    pub async fn new(settings: &SqliteSettings) -> anyhow::Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(&settings.path)
+            .create_if_missing(true);
+
+        Ok(Self {
+            pool: SqlitePoolOptions::new().connect_with(options).await?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl MaterializerPlugin for SqliteMaterializer {
+    fn validate_options(&self, options: Value) -> anyhow::Result<()> {
+        let options: SqliteMaterializerOptions = serde_json::from_value(options)?;
+        validate_identifier(&options.table)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn upsert_view(
+        &self,
+        view: MaterializedView,
+        view_definition: FullView,
+    ) -> anyhow::Result<()> {
+        counter!("cdl.materializer.sqlite.upsert-materialized-view", 1);
+
+        let options: SqliteMaterializerOptions = serde_json::from_str(&view.options.options)?;
+        validate_identifier(&options.table)?;
+        let rows = parse_rows(view.rows)?;
+
+        if rows.is_empty() {
+            tracing::warn!("Materialized view is empty, skipping upserting");
+        }
+
+        let fields = get_field_list(&view_definition);
+        for field in fields.iter() {
+            validate_identifier(&field.sql_name)?;
+        }
+
+        let create_stm = format!(
+            "CREATE TABLE IF NOT EXISTS {table} (object_ids TEXT NOT NULL PRIMARY KEY{columns})",
+            table = options.table,
+            columns = fields
+                .iter()
+                .map(|f| format!(", {} TEXT NOT NULL", f.sql_name))
+                .join("")
+        );
+        let insert_stm = format!(
+            "INSERT INTO {table} (object_ids{columns}) VALUES (?{placeholders}) \
+             ON CONFLICT (object_ids) DO UPDATE SET {updates}",
+            table = options.table,
+            columns = fields.iter().map(|f| format!(", {}", f.sql_name)).join(""),
+            placeholders = ", ?".repeat(fields.len()),
+            updates = std::iter::once("object_ids = excluded.object_ids".to_owned())
+                .chain(
+                    fields
+                        .iter()
+                        .map(|f| format!("{field} = excluded.{field}", field = f.sql_name))
+                )
+                .join(", ")
+        );
+        tracing::debug!(?create_stm, ?insert_stm, "Build query");
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&create_stm).execute(&mut tx).await?;
+        for row in rows.iter() {
+            let mut query = sqlx::query(&insert_stm).bind(serde_json::to_string(&row.object_ids)?);
+            for field in fields.iter() {
+                query = query.bind(serde_json::to_string(field.value(row)?)?);
+            }
+            query.execute(&mut tx).await?;
+        }
+        tx.commit().await?;
+
+        counter!("cdl.materializer.sqlite.store", rows.len() as u64);
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_objects(&self, options: Value, object_ids: Vec<Uuid>) -> anyhow::Result<()> {
+        counter!("cdl.materializer.sqlite.delete-objects", 1);
+
+        let options: SqliteMaterializerOptions = serde_json::from_value(options)?;
+        validate_identifier(&options.table)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // Table is created with the first upsert, there is nothing to delete before it
+        let exists =
+            sqlx::query("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+                .bind(&options.table)
+                .fetch_optional(&mut tx)
+                .await?
+                .is_some();
+        if !exists {
+            return Ok(());
+        }
+
+        // `object_ids` are stored as JSON array
+        let delete_stm = format!(
+            "DELETE FROM {table} WHERE EXISTS (\
+                 SELECT 1 FROM json_each({table}.object_ids) WHERE json_each.value = ?\
+             )",
+            table = options.table
+        );
+        let mut deleted = 0;
+        for object_id in object_ids {
+            deleted += sqlx::query(&delete_stm)
+                .bind(object_id.to_string())
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+
+        counter!("cdl.materializer.sqlite.delete", deleted);
+
+        Ok(())
+    }
+}
@@ -1,14 +1,20 @@
 use communication_utils::publisher::CommonPublisher;
 use serde::Deserialize;
 use settings_utils::{LogSettings, MonitoringSettings, PostgresSettings};
+use std::path::PathBuf;
 use utils::notification::NotificationSettings;
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub input_port: u16,
     pub cache_capacity: usize,
+    /// Plugin used for views which do not specify `plugin` in their materializer options
+    #[serde(default = "default_plugin")]
+    pub default_plugin: String,
 
-    pub postgres: PostgresSettings,
+    pub postgres: Option<PostgresSettings>,
+    pub sqlite: Option<SqliteSettings>,
+    pub file: Option<FileSettings>,
     pub kafka: Option<KafkaProducerSettings>,
     #[serde(default)]
     pub notifications: NotificationSettings,
@@ -25,6 +31,16 @@ pub struct ServicesSettings {
     pub schema_registry_url: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SqliteSettings {
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FileSettings {
+    pub directory: PathBuf,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct KafkaProducerSettings {
     pub brokers: String,
@@ -39,3 +55,7 @@ impl Settings {
         }
     }
 }
+
+fn default_plugin() -> String {
+    "postgres".to_string()
+}
@@ -0,0 +1,476 @@
+use async_trait::async_trait;
+use communication_utils::{consumer::ConsumerHandler, message::CommunicationMessage};
+use futures::{stream, StreamExt};
+use lru::LruCache;
+use metrics_utils::{self as metrics, counter};
+use prost::Message;
+use rpc::common::RowDefinition;
+use rpc::materializer_ondemand::Schema;
+use rpc::schema_registry::{Filter, FullView, Relation};
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::settings::CacheSettings;
+use crate::RowStream;
+
+/// Identifies result of object builder: the view in given revision, requested objects and filter
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    view_id: String,
+    revision: u64,
+    schemas: Vec<(String, Vec<String>)>,
+    filter: Option<Vec<u8>>,
+}
+
+impl CacheKey {
+    pub fn new(
+        view: &FullView,
+        schemas: &HashMap<String, Schema>,
+        filter: Option<&Filter>,
+    ) -> Self {
+        let mut schemas: Vec<(String, Vec<String>)> = schemas
+            .iter()
+            .map(|(schema_id, schema)| {
+                let mut object_ids = schema.object_ids.clone();
+                object_ids.sort();
+                (schema_id.clone(), object_ids)
+            })
+            .collect();
+        schemas.sort();
+
+        Self {
+            view_id: view.id.clone(),
+            revision: view_revision(view),
+            schemas,
+            filter: filter.map(encode),
+        }
+    }
+}
+
+/// Hash of view definition. Changes whenever view is updated in schema registry.
+fn view_revision(view: &FullView) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    view.base_schema_id.hash(&mut hasher);
+    view.materializer_options.hash(&mut hasher);
+    let mut fields: Vec<_> = view.fields.iter().collect();
+    fields.sort();
+    fields.hash(&mut hasher);
+    view.filters.as_ref().map(encode).hash(&mut hasher);
+    for relation in view.relations.iter() {
+        encode(relation).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn encode(message: &impl Message) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(message.encoded_len());
+    message
+        .encode(&mut buffer)
+        .expect("Buffer has sufficient capacity");
+    buffer
+}
+
+/// Data which, when changed, makes cached rows outdated
+#[derive(Debug)]
+pub struct Dependencies {
+    base_schema_id: Uuid,
+    relation_ids: HashSet<Uuid>,
+    object_ids: HashSet<Uuid>,
+    /// Request was not narrowed to specific objects, so any new object of base schema changes the result
+    unscoped: bool,
+}
+
+impl Dependencies {
+    pub fn new(view: &FullView, schemas: &HashMap<String, Schema>) -> Self {
+        fn collect_relations(relations: &[Relation], ids: &mut HashSet<Uuid>) {
+            for relation in relations {
+                if let Ok(id) = relation.global_id.parse() {
+                    ids.insert(id);
+                }
+                collect_relations(&relation.relations, ids);
+            }
+        }
+
+        let mut relation_ids = HashSet::new();
+        collect_relations(&view.relations, &mut relation_ids);
+
+        Self {
+            base_schema_id: view.base_schema_id.parse().unwrap_or_default(),
+            relation_ids,
+            object_ids: schemas
+                .values()
+                .flat_map(|schema| schema.object_ids.iter())
+                .filter_map(|id| id.parse().ok())
+                .collect(),
+            unscoped: schemas.is_empty(),
+        }
+    }
+
+    fn is_affected_by(&self, notification: &Notification) -> bool {
+        match notification {
+            Notification::CommandService {
+                object_id,
+                schema_id,
+            } => {
+                self.object_ids.contains(object_id)
+                    || (self.unscoped && self.base_schema_id == *schema_id)
+            }
+            Notification::EdgeRegistry {
+                relation_id,
+                parent_object_id,
+            } => {
+                self.object_ids.contains(parent_object_id)
+                    || (self.unscoped && self.relation_ids.contains(relation_id))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Notification {
+    #[serde(rename_all = "camelCase")]
+    CommandService { object_id: Uuid, schema_id: Uuid },
+    #[serde(rename_all = "camelCase")]
+    EdgeRegistry {
+        relation_id: Uuid,
+        parent_object_id: Uuid,
+    },
+}
+
+struct CacheEntry {
+    rows: Arc<Vec<RowDefinition>>,
+    dependencies: Dependencies,
+    inserted_at: Instant,
+}
+
+/// Number of recent notifications checked against results computed while they arrived
+const RECENT_NOTIFICATIONS: usize = 1024;
+
+struct Entries {
+    results: LruCache<CacheKey, CacheEntry>,
+    /// Last notifications, the newest one at the back
+    recent: VecDeque<Notification>,
+}
+
+pub struct ResultCache {
+    entries: Mutex<Entries>,
+    /// Incremented on every invalidation. Results computed while an invalidation affecting them happened
+    /// are not cached, as they could be built from outdated data.
+    generation: AtomicU64,
+    ttl: Duration,
+    max_rows: usize,
+}
+
+impl ResultCache {
+    pub fn new(settings: &CacheSettings) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                results: LruCache::new(settings.capacity),
+                recent: VecDeque::with_capacity(RECENT_NOTIFICATIONS),
+            }),
+            generation: AtomicU64::new(0),
+            ttl: Duration::from_secs(settings.ttl_secs),
+            max_rows: settings.max_rows,
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<RowStream> {
+        let mut entries = self.entries.lock().unwrap();
+        let expired = match entries.results.get(key) {
+            Some(entry) => entry.inserted_at.elapsed() > self.ttl,
+            None => {
+                counter!("cdl.materializer-ondemand.cache.miss", 1);
+                return None;
+            }
+        };
+        if expired {
+            entries.results.pop(key);
+            counter!("cdl.materializer-ondemand.cache.miss", 1);
+            return None;
+        }
+
+        counter!("cdl.materializer-ondemand.cache.hit", 1);
+        let rows = entries.results.get(key)?.rows.clone();
+        Some(Box::pin(stream::iter(
+            (0..rows.len()).map(move |idx| Ok(rows[idx].clone())),
+        )))
+    }
+
+    fn insert(&self, key: CacheKey, entry: CacheEntry, generation: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        let missed = (self.generation() - generation) as usize;
+        // Notifications older than the recent ones can't be checked, so the result may be outdated
+        if missed > entries.recent.len() {
+            return;
+        }
+        let outdated = entries
+            .recent
+            .iter()
+            .rev()
+            .take(missed)
+            .any(|notification| entry.dependencies.is_affected_by(notification));
+        if !outdated {
+            entries.results.put(key, entry);
+        }
+    }
+
+    pub fn invalidate(&self, notification: &Notification) {
+        let mut entries = self.entries.lock().unwrap();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        if entries.recent.len() == RECENT_NOTIFICATIONS {
+            entries.recent.pop_front();
+        }
+        entries.recent.push_back(notification.clone());
+
+        let outdated: Vec<CacheKey> = entries
+            .results
+            .iter()
+            .filter(|(_, entry)| entry.dependencies.is_affected_by(notification))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        counter!(
+            "cdl.materializer-ondemand.cache.invalidated",
+            outdated.len() as u64
+        );
+        for key in outdated {
+            entries.results.pop(&key);
+        }
+    }
+
+    /// Passes rows through, storing them in cache once the stream is finished successfully.
+    /// Results bigger than `max_rows` are not cached.
+    pub fn record(
+        self: Arc<Self>,
+        key: CacheKey,
+        dependencies: Dependencies,
+        generation: u64,
+        rows: RowStream,
+    ) -> RowStream {
+        let recording = Recording {
+            key,
+            dependencies,
+            generation,
+            buffer: Vec::new(),
+            cache: self,
+        };
+
+        Box::pin(stream::unfold(
+            (rows, Some(recording)),
+            |(mut rows, mut recording)| async move {
+                match rows.next().await {
+                    Some(Ok(row)) => {
+                        let full = match recording.as_mut() {
+                            Some(recording) => !recording.push(&row),
+                            None => false,
+                        };
+                        if full {
+                            recording = None;
+                        }
+                        Some((Ok(row), (rows, recording)))
+                    }
+                    Some(Err(err)) => Some((Err(err), (rows, None))),
+                    None => {
+                        if let Some(recording) = recording {
+                            recording.finish();
+                        }
+                        None
+                    }
+                }
+            },
+        ))
+    }
+}
+
+struct Recording {
+    key: CacheKey,
+    dependencies: Dependencies,
+    generation: u64,
+    buffer: Vec<RowDefinition>,
+    cache: Arc<ResultCache>,
+}
+
+impl Recording {
+    /// Returns `false` if result is too big to be cached
+    fn push(&mut self, row: &RowDefinition) -> bool {
+        if self.buffer.len() >= self.cache.max_rows {
+            return false;
+        }
+        self.buffer.push(row.clone());
+        true
+    }
+
+    fn finish(mut self) {
+        self.dependencies.object_ids.extend(
+            self.buffer
+                .iter()
+                .flat_map(|row| row.object_ids.iter())
+                .filter_map(|id| id.parse::<Uuid>().ok()),
+        );
+        self.cache.insert(
+            self.key,
+            CacheEntry {
+                rows: Arc::new(self.buffer),
+                dependencies: self.dependencies,
+                inserted_at: Instant::now(),
+            },
+            self.generation,
+        );
+    }
+}
+
+/// Removes cached results affected by command service and edge registry notifications
+pub struct CacheInvalidator {
+    cache: Arc<ResultCache>,
+}
+
+impl CacheInvalidator {
+    pub fn new(cache: Arc<ResultCache>) -> Self {
+        Self { cache }
+    }
+}
+
+#[async_trait]
+impl ConsumerHandler for CacheInvalidator {
+    #[tracing::instrument(skip(self, msg))]
+    async fn handle<'a>(&'a mut self, msg: &'a dyn CommunicationMessage) -> anyhow::Result<()> {
+        let payload = msg.payload()?;
+        match serde_json::from_str::<Notification>(payload) {
+            Ok(notification) => {
+                tracing::trace!(?notification, "Invalidating cache");
+                self.cache.invalidate(&notification);
+            }
+            Err(err) => {
+                tracing::debug!("Skipping notification {}: {}", payload, err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+
+    const BASE_SCHEMA: &str = "00000000-0000-0000-0000-000000000001";
+    const OBJECT: &str = "00000000-0000-0000-0000-000000000002";
+    const OTHER_OBJECT: &str = "00000000-0000-0000-0000-000000000003";
+
+    fn view() -> FullView {
+        FullView {
+            id: "00000000-0000-0000-0000-000000000004".to_owned(),
+            base_schema_id: BASE_SCHEMA.to_owned(),
+            name: "view".to_owned(),
+            materializer_address: "".to_owned(),
+            materializer_options: "{}".to_owned(),
+            fields: Default::default(),
+            filters: None,
+            relations: vec![],
+        }
+    }
+
+    fn cache(max_rows: usize) -> Arc<ResultCache> {
+        Arc::new(ResultCache::new(&CacheSettings {
+            capacity: 10,
+            ttl_secs: 60,
+            max_rows,
+        }))
+    }
+
+    fn rows(ids: &[&str]) -> RowStream {
+        let rows: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                Ok(RowDefinition {
+                    object_ids: vec![id.to_string()],
+                    fields: Default::default(),
+                })
+            })
+            .collect();
+        Box::pin(stream::iter(rows))
+    }
+
+    async fn fill(cache: &Arc<ResultCache>, key: &CacheKey, generation: u64, ids: &[&str]) {
+        let dependencies = Dependencies::new(&view(), &HashMap::new());
+        let _: Vec<_> = cache
+            .clone()
+            .record(key.clone(), dependencies, generation, rows(ids))
+            .try_collect()
+            .await
+            .unwrap();
+    }
+
+    fn notification(object_id: &str) -> Notification {
+        serde_json::from_value(serde_json::json!({
+            "objectId": object_id,
+            "schemaId": "00000000-0000-0000-0000-000000000005",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn returns_recorded_rows_until_invalidated() {
+        let cache = cache(10);
+        let key = CacheKey::new(&view(), &HashMap::new(), None);
+        fill(&cache, &key, cache.generation(), &[OBJECT]).await;
+
+        let cached: Vec<_> = cache.get(&key).unwrap().try_collect().await.unwrap();
+        assert_eq!(cached.len(), 1);
+
+        cache.invalidate(&notification(OTHER_OBJECT));
+        assert!(cache.get(&key).is_some());
+
+        cache.invalidate(&notification(OBJECT));
+        assert!(cache.get(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn skips_results_outdated_or_too_big() {
+        let cache = cache(1);
+        let key = CacheKey::new(&view(), &HashMap::new(), None);
+
+        fill(&cache, &key, cache.generation(), &[OBJECT, OTHER_OBJECT]).await;
+        assert!(cache.get(&key).is_none());
+
+        let generation = cache.generation();
+        cache.invalidate(&notification(OBJECT));
+        fill(&cache, &key, generation, &[OBJECT]).await;
+        assert!(cache.get(&key).is_none());
+    }
+
+    #[tokio::test]
+    async fn caches_results_computed_during_unrelated_notifications() {
+        let cache = cache(10);
+        let key = CacheKey::new(&view(), &HashMap::new(), None);
+
+        let generation = cache.generation();
+        cache.invalidate(&notification(OTHER_OBJECT));
+        fill(&cache, &key, generation, &[OBJECT]).await;
+
+        assert!(cache.get(&key).is_some());
+    }
+
+    #[test]
+    fn key_changes_with_view_definition() {
+        let mut updated = view();
+        updated.materializer_options = "{\"table\":\"t\"}".to_owned();
+
+        assert_ne!(
+            CacheKey::new(&view(), &HashMap::new(), None),
+            CacheKey::new(&updated, &HashMap::new(), None)
+        );
+    }
+}
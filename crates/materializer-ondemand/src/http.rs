@@ -0,0 +1,457 @@
+use cdl_dto::materialization::Filter as ViewFilter;
+use cdl_dto::TryIntoRpc;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use rpc::common::RowDefinition;
+use rpc::materializer_ondemand::{on_demand_request::Page, OnDemandRequest, Ordering, Schema};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use uuid::Uuid;
+use warp::hyper::header::CONTENT_TYPE;
+use warp::hyper::{Body, Response, StatusCode};
+use warp::{reject::Reject, Filter, Rejection, Reply};
+
+use crate::MaterializerImpl;
+
+const APPLICATION_NDJSON: &str = "application/x-ndjson";
+const TEXT_CSV: &str = "text/csv";
+
+#[derive(Debug)]
+pub enum Error {
+    NotAcceptable(String),
+    InvalidQuery(String),
+    Materialization(tonic::Status),
+}
+
+impl Reject for Error {}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Comma separated list of fields
+    fields: Option<String>,
+    order_by: Option<String>,
+    #[serde(default)]
+    descending: bool,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    /// JSON object mapping schema ids to lists of object ids, same as `schemas` of gRPC request
+    schemas: Option<String>,
+    /// JSON encoded filter, in the same format as view's `filters`
+    filter: Option<String>,
+}
+
+impl ExportQuery {
+    fn schemas(&self) -> Result<HashMap<String, Schema>, Error> {
+        let schemas: HashMap<Uuid, Vec<Uuid>> = match &self.schemas {
+            Some(schemas) => serde_json::from_str(schemas)
+                .map_err(|err| Error::InvalidQuery(format!("schemas: {}", err)))?,
+            None => return Ok(HashMap::new()),
+        };
+
+        Ok(schemas
+            .into_iter()
+            .map(|(schema_id, object_ids)| {
+                (
+                    schema_id.to_string(),
+                    Schema {
+                        object_ids: object_ids.iter().map(Uuid::to_string).collect(),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    fn filter(&self) -> Result<Option<rpc::schema_registry::Filter>, Error> {
+        self.filter
+            .as_deref()
+            .map(|filter| {
+                let filter: ViewFilter = serde_json::from_str(filter)
+                    .map_err(|err| Error::InvalidQuery(format!("filter: {}", err)))?;
+                filter
+                    .try_into_rpc()
+                    .map_err(|err| Error::InvalidQuery(format!("filter: {}", err)))
+            })
+            .transpose()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ndjson,
+    Csv,
+}
+
+impl Format {
+    /// Picks supported media range with the highest quality, the first one listed wins ties.
+    /// Ranges with `q=0` are never chosen.
+    fn from_accept(accept: Option<&str>) -> Result<Self, Error> {
+        let accept = match accept {
+            Some(accept) => accept,
+            None => return Ok(Format::Ndjson),
+        };
+
+        let mut best: Option<(Format, f32)> = None;
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';');
+            let format = match params.next().unwrap_or_default().trim() {
+                TEXT_CSV => Format::Csv,
+                APPLICATION_NDJSON | "application/*" | "*/*" => Format::Ndjson,
+                _ => continue,
+            };
+            let quality = params
+                .filter_map(|param| {
+                    let (name, value) = param.split_once('=')?;
+                    (name.trim() == "q").then(|| value.trim().parse::<f32>().unwrap_or(0.0))
+                })
+                .next()
+                .unwrap_or(1.0);
+
+            if quality > 0.0 && best.map_or(true, |(_, best)| quality > best) {
+                best = Some((format, quality));
+            }
+        }
+
+        best.map(|(format, _)| format)
+            .ok_or_else(|| Error::NotAcceptable(accept.to_owned()))
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Ndjson => APPLICATION_NDJSON,
+            Format::Csv => TEXT_CSV,
+        }
+    }
+}
+
+pub fn routes(
+    materializer: MaterializerImpl,
+) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone {
+    let materializer_filter = warp::any().map(move || materializer.clone());
+
+    warp::get()
+        .and(warp::path!("views" / Uuid))
+        .and(warp::query::<ExportQuery>())
+        .and(warp::header::optional::<String>("accept"))
+        .and(materializer_filter)
+        .and_then(export_view)
+        .recover(recover)
+}
+
+#[tracing::instrument(skip(materializer))]
+async fn export_view(
+    view_id: Uuid,
+    query: ExportQuery,
+    accept: Option<String>,
+    materializer: MaterializerImpl,
+) -> Result<Response<Body>, Rejection> {
+    let format = Format::from_accept(accept.as_deref())?;
+
+    let fields: Vec<String> = query
+        .fields
+        .iter()
+        .flat_map(|fields| fields.split(','))
+        .map(|field| field.trim().to_owned())
+        .filter(|field| !field.is_empty())
+        .collect();
+    let descending = query.descending;
+
+    let request = OnDemandRequest {
+        view_id: view_id.to_string(),
+        schemas: query.schemas()?,
+        filter: query.filter()?,
+        fields: fields.clone(),
+        order_by: query.order_by.map(|field| Ordering {
+            field,
+            descending: Some(descending),
+        }),
+        limit: query.limit,
+        page: query.offset.map(Page::Offset),
+    };
+
+    let mut rows = materializer
+        .materialize_rows(request)
+        .await
+        .map_err(Error::Materialization)?;
+
+    // Errors before the first row are still reported with a status code
+    let first = rows.try_next().await.map_err(Error::Materialization)?;
+    let rows = stream::iter(first.map(Ok)).chain(rows);
+
+    // Hyper polls the stream only when the connection is ready for the next chunk,
+    // so rows are pulled from object builder at the pace of the client.
+    let body = match format {
+        Format::Ndjson => {
+            Body::wrap_stream(abort_on_error(rows.map_ok(|row| ndjson_line(&row)), format))
+        }
+        Format::Csv => {
+            let mut encoder = CsvEncoder::new(fields);
+            Body::wrap_stream(abort_on_error(
+                rows.map_ok(move |row| encoder.encode(&row)),
+                format,
+            ))
+        }
+    };
+
+    let mut response = Response::new(body);
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        warp::http::HeaderValue::from_static(format.content_type()),
+    );
+    Ok(response)
+}
+
+async fn recover(rejection: Rejection) -> Result<impl Reply, Infallible> {
+    let (message, code) = match rejection.find::<Error>() {
+        Some(Error::NotAcceptable(accept)) => (
+            format!(
+                "Unsupported media type `{}`, expected `{}` or `{}`",
+                accept, APPLICATION_NDJSON, TEXT_CSV
+            ),
+            StatusCode::NOT_ACCEPTABLE,
+        ),
+        Some(Error::InvalidQuery(message)) => (message.clone(), StatusCode::BAD_REQUEST),
+        Some(Error::Materialization(status)) => (
+            status.message().to_owned(),
+            match status.code() {
+                tonic::Code::InvalidArgument => StatusCode::BAD_REQUEST,
+                tonic::Code::NotFound => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        ),
+        None if rejection.is_not_found() => ("Not found".to_owned(), StatusCode::NOT_FOUND),
+        None => (format!("{:?}", rejection), StatusCode::BAD_REQUEST),
+    };
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({ "message": message })),
+        code,
+    ))
+}
+
+/// Status code is already sent when materialization fails in the middle of the stream.
+/// NDJSON body gets a final `{"error": ...}` line, then the stream fails, so hyper aborts
+/// the response without terminating chunk and client can tell it is incomplete.
+fn abort_on_error(
+    chunks: impl Stream<Item = Result<String, tonic::Status>>,
+    format: Format,
+) -> impl Stream<Item = Result<String, tonic::Status>> {
+    chunks
+        .scan(false, |failed, chunk| {
+            if *failed {
+                return futures::future::ready(None);
+            }
+            let chunks = match chunk {
+                Ok(chunk) => vec![Ok(chunk)],
+                Err(err) => {
+                    tracing::error!("Export of materialized view failed: {:?}", err);
+                    *failed = true;
+                    let mut chunks = vec![];
+                    if format == Format::Ndjson {
+                        let mut line = serde_json::json!({ "error": err.message() }).to_string();
+                        line.push('\n');
+                        chunks.push(Ok(line));
+                    }
+                    chunks.push(Err(err));
+                    chunks
+                }
+            };
+            futures::future::ready(Some(stream::iter(chunks)))
+        })
+        .flatten()
+}
+
+fn ndjson_line(row: &RowDefinition) -> String {
+    let fields: serde_json::Map<String, Value> = row
+        .fields
+        .iter()
+        .map(|(name, value)| (name.clone(), parse_field(value)))
+        .collect();
+
+    let mut line = serde_json::json!({
+        "object_ids": row.object_ids,
+        "fields": fields,
+    })
+    .to_string();
+    line.push('\n');
+    line
+}
+
+fn parse_field(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()))
+}
+
+/// Writes header before the first row. Columns are either requested fields,
+/// or fields of the first row in alphabetical order.
+struct CsvEncoder {
+    columns: Option<Vec<String>>,
+    header_written: bool,
+}
+
+impl CsvEncoder {
+    fn new(fields: Vec<String>) -> Self {
+        Self {
+            columns: if fields.is_empty() {
+                None
+            } else {
+                Some(fields)
+            },
+            header_written: false,
+        }
+    }
+
+    fn encode(&mut self, row: &RowDefinition) -> String {
+        let mut chunk = String::new();
+
+        let columns = self.columns.get_or_insert_with(|| {
+            let mut columns: Vec<String> = row.fields.keys().cloned().collect();
+            columns.sort();
+            columns
+        });
+
+        if !self.header_written {
+            write_record(
+                &mut chunk,
+                std::iter::once("object_ids".to_owned()).chain(columns.iter().cloned()),
+            );
+            self.header_written = true;
+        }
+
+        let values = columns.iter().map(|column| {
+            match row.fields.get(column).map(|value| parse_field(value)) {
+                Some(Value::String(value)) => value,
+                Some(Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            }
+        });
+        write_record(
+            &mut chunk,
+            std::iter::once(row.object_ids.join(";")).chain(values),
+        );
+
+        chunk
+    }
+}
+
+fn write_record(out: &mut String, values: impl Iterator<Item = String>) {
+    for (idx, value) in values.enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        if value.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+            out.push('"');
+            out.push_str(&value.replace('"', "\"\""));
+            out.push('"');
+        } else {
+            out.push_str(&value);
+        }
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: &[(&str, &str)]) -> RowDefinition {
+        RowDefinition {
+            object_ids: vec!["1".to_owned(), "2".to_owned()],
+            fields: fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn picks_format_from_accept_header() {
+        assert_eq!(Format::from_accept(None).unwrap(), Format::Ndjson);
+        assert_eq!(
+            Format::from_accept(Some("text/csv; charset=utf-8")).unwrap(),
+            Format::Csv
+        );
+        assert_eq!(
+            Format::from_accept(Some("text/html, */*;q=0.8")).unwrap(),
+            Format::Ndjson
+        );
+        assert!(Format::from_accept(Some("text/html")).is_err());
+    }
+
+    #[test]
+    fn honors_quality_of_media_ranges() {
+        assert_eq!(
+            Format::from_accept(Some("application/x-ndjson;q=0.5, text/csv")).unwrap(),
+            Format::Csv
+        );
+        assert_eq!(
+            Format::from_accept(Some("text/csv;q=0.2, */*;q=0.9")).unwrap(),
+            Format::Ndjson
+        );
+        assert!(Format::from_accept(Some("text/csv;q=0, text/html")).is_err());
+    }
+
+    #[test]
+    fn parses_scoping_of_export() {
+        let schema_id = Uuid::new_v4();
+        let object_id = Uuid::new_v4();
+        let query = ExportQuery {
+            fields: None,
+            order_by: None,
+            descending: false,
+            limit: None,
+            offset: None,
+            schemas: Some(format!("{{\"{}\": [\"{}\"]}}", schema_id, object_id)),
+            filter: None,
+        };
+
+        let schemas = query.schemas().unwrap();
+        assert_eq!(
+            schemas[&schema_id.to_string()].object_ids,
+            vec![object_id.to_string()]
+        );
+        assert!(query.filter().unwrap().is_none());
+
+        let query = ExportQuery {
+            schemas: Some("[]".to_owned()),
+            ..query
+        };
+        assert!(matches!(query.schemas(), Err(Error::InvalidQuery(_))));
+    }
+
+    #[tokio::test]
+    async fn ends_stream_with_error_on_failure() {
+        let chunks = stream::iter(vec![
+            Ok("{}\n".to_owned()),
+            Err(tonic::Status::internal("broken")),
+            Ok("{}\n".to_owned()),
+        ]);
+
+        let chunks: Vec<_> = abort_on_error(chunks, Format::Ndjson).collect().await;
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].as_ref().unwrap(), "{}\n");
+        assert_eq!(chunks[1].as_ref().unwrap(), "{\"error\":\"broken\"}\n");
+        assert!(chunks[2].is_err());
+    }
+
+    #[test]
+    fn encodes_csv_with_header() {
+        let mut encoder = CsvEncoder::new(vec![]);
+
+        let first = encoder.encode(&row(&[("b", "\"x,y\""), ("a", "1")]));
+        let second = encoder.encode(&row(&[("a", "{\"c\":\"d\"}")]));
+
+        assert_eq!(first, "object_ids,a,b\r\n1;2,1,\"x,y\"\r\n");
+        assert_eq!(second, "1;2,\"{\"\"c\"\":\"\"d\"\"}\",\r\n");
+    }
+
+    #[test]
+    fn encodes_ndjson_line() {
+        let line = ndjson_line(&row(&[("a", "1")]));
+
+        assert_eq!(
+            line,
+            "{\"fields\":{\"a\":1},\"object_ids\":[\"1\",\"2\"]}\n"
+        );
+    }
+}
@@ -1,55 +1,165 @@
+pub mod cache;
+pub mod http;
 pub mod settings;
 
+mod query;
+
 use std::pin::Pin;
+use std::sync::Arc;
 
+use cache::{CacheKey, Dependencies, ResultCache};
 use futures::Stream;
+use query::RowQuery;
 use rpc::{
     common::RowDefinition,
     materializer_ondemand::on_demand_materializer_server::OnDemandMaterializer,
-    materializer_ondemand::Empty, object_builder::View,
+    materializer_ondemand::{Empty, OnDemandRequest},
+    object_builder::{ObjectBuilderConnectionManager, ObjectBuilderPool, View},
+    schema_registry::{Id, SchemaRegistryConnectionManager, SchemaRegistryPool},
 };
+use settings::Settings;
+
+pub type RowStream =
+    Pin<Box<dyn Stream<Item = Result<RowDefinition, tonic::Status>> + 'static + Send + Sync>>;
 
+#[derive(Clone)]
 pub struct MaterializerImpl {
-    object_builder_addr: String, //TODO: Replace by bb8
+    object_builder_pool: ObjectBuilderPool,
+    cache: Option<Cache>,
+}
+
+#[derive(Clone)]
+struct Cache {
+    results: Arc<ResultCache>,
+    schema_registry_pool: SchemaRegistryPool,
 }
 
 impl MaterializerImpl {
-    pub async fn new(object_builder_addr: &str) -> anyhow::Result<Self> {
+    pub async fn new(settings: &Settings) -> anyhow::Result<Self> {
+        let object_builder_pool = bb8::Pool::builder()
+            .build(ObjectBuilderConnectionManager {
+                address: settings.services.object_builder_url.to_string(),
+            })
+            .await?;
+
+        let cache = match &settings.cache {
+            Some(cache_settings) => {
+                let schema_registry_url = settings
+                    .services
+                    .schema_registry_url
+                    .as_ref()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Schema registry url is required when cache is enabled")
+                    })?;
+                let schema_registry_pool = bb8::Pool::builder()
+                    .build(SchemaRegistryConnectionManager {
+                        address: schema_registry_url.to_string(),
+                    })
+                    .await?;
+
+                Some(Cache {
+                    results: Arc::new(ResultCache::new(cache_settings)),
+                    schema_registry_pool,
+                })
+            }
+            None => None,
+        };
+
         Ok(Self {
-            object_builder_addr: object_builder_addr.to_string(),
+            object_builder_pool,
+            cache,
         })
     }
-}
 
-#[tonic::async_trait]
-impl OnDemandMaterializer for MaterializerImpl {
-    type MaterializeStream =
-        Pin<Box<dyn Stream<Item = Result<RowDefinition, tonic::Status>> + 'static + Send + Sync>>;
+    pub fn result_cache(&self) -> Option<Arc<ResultCache>> {
+        self.cache.as_ref().map(|cache| cache.results.clone())
+    }
 
     #[tracing::instrument(skip(self))]
-    async fn materialize(
+    pub async fn materialize_rows(
         &self,
-        request: tonic::Request<rpc::materializer_ondemand::OnDemandRequest>,
-    ) -> Result<tonic::Response<Self::MaterializeStream>, tonic::Status> {
-        let request = request.into_inner();
-
+        request: OnDemandRequest,
+    ) -> Result<RowStream, tonic::Status> {
         tracing::debug!(?request, "Handling");
 
-        let view_id = request.view_id;
-        let schemas = request
-            .schemas
+        let OnDemandRequest {
+            view_id,
+            schemas,
+            filter,
+            fields,
+            order_by,
+            limit,
+            page,
+        } = request;
+        let query = RowQuery::new(fields, order_by, limit, page)?;
+
+        let recording = match &self.cache {
+            Some(cache) => {
+                let view = cache
+                    .schema_registry_pool
+                    .get()
+                    .await
+                    .map_err(|e| tonic::Status::internal(format!("{}", e)))?
+                    .get_view(Id {
+                        id: view_id.clone(),
+                    })
+                    .await?
+                    .into_inner();
+                let key = CacheKey::new(&view, &schemas, filter.as_ref());
+
+                if let Some(rows) = cache.results.get(&key) {
+                    return query.apply(rows).await;
+                }
+
+                // Taken before object builder is called, so invalidations during materialization
+                // prevent storing possibly outdated result
+                let generation = cache.results.generation();
+                let dependencies = Dependencies::new(&view, &schemas);
+                Some((cache.results.clone(), key, dependencies, generation))
+            }
+            None => None,
+        };
+
+        let schemas = schemas
             .into_iter()
             .map(|(k, v)| (k, into_object_builder_schemas(v)))
             .collect();
 
-        let stream = rpc::object_builder::connect(self.object_builder_addr.as_str())
+        let stream = self
+            .object_builder_pool
+            .get()
             .await
             .map_err(|e| tonic::Status::internal(format!("{}", e)))?
-            .materialize(View { view_id, schemas })
+            .materialize(View {
+                view_id,
+                schemas,
+                filter,
+            })
             .await
             .map_err(|e| tonic::Status::internal(format!("{}", e)))?;
 
-        let stream = Box::pin(stream.into_inner());
+        let rows: RowStream = Box::pin(stream.into_inner());
+        let rows = match recording {
+            Some((cache, key, dependencies, generation)) => {
+                cache.record(key, dependencies, generation, rows)
+            }
+            None => rows,
+        };
+
+        query.apply(rows).await
+    }
+}
+
+#[tonic::async_trait]
+impl OnDemandMaterializer for MaterializerImpl {
+    type MaterializeStream = RowStream;
+
+    #[tracing::instrument(skip(self))]
+    async fn materialize(
+        &self,
+        request: tonic::Request<OnDemandRequest>,
+    ) -> Result<tonic::Response<Self::MaterializeStream>, tonic::Status> {
+        let stream = self.materialize_rows(request.into_inner()).await?;
 
         Ok(tonic::Response::new(stream))
     }
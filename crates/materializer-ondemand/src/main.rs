@@ -1,4 +1,4 @@
-use materializer_ondemand::{settings::Settings, MaterializerImpl};
+use materializer_ondemand::{cache::CacheInvalidator, http, settings::Settings, MaterializerImpl};
 use rpc::materializer_ondemand::on_demand_materializer_server::OnDemandMaterializerServer;
 use settings_utils::load_settings;
 use tonic::transport::Server;
@@ -18,7 +18,35 @@ async fn main() -> anyhow::Result<()> {
     utils::status_endpoints::serve(&settings.monitoring);
     metrics_utils::serve(&settings.monitoring);
 
-    let materializer = MaterializerImpl::new(&settings.services.object_builder_url).await?;
+    let materializer = MaterializerImpl::new(&settings).await?;
+
+    if let Some(result_cache) = materializer.result_cache() {
+        let consumer = settings.consumer().await?;
+        let handler = CacheInvalidator::new(result_cache);
+        tokio::spawn(async {
+            tracing::info!("Listening for cache invalidation notifications via MQ");
+
+            match consumer.run(handler).await {
+                Ok(_) => {
+                    tracing::error!("MQ consumer finished work");
+                }
+                Err(err) => {
+                    tracing::error!("MQ consumer returned with error: {:?}", err);
+                }
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+            std::process::abort();
+        });
+    }
+
+    if let Some(http_port) = settings.http_port {
+        tokio::spawn(tracing_utils::http::serve(
+            http::routes(materializer.clone()),
+            ([0, 0, 0, 0], http_port),
+        ));
+    }
 
     utils::status_endpoints::mark_as_started();
 
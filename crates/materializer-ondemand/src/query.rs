@@ -0,0 +1,361 @@
+use futures::{future, stream, StreamExt, TryStreamExt};
+use rpc::common::RowDefinition;
+use rpc::materializer_ondemand::{on_demand_request::Page as RpcPage, Ordering as RpcOrdering};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::RowStream;
+
+/// Projection, ordering and paging applied to rows received from object builder
+#[derive(Debug)]
+pub struct RowQuery {
+    fields: HashSet<String>,
+    order_by: Option<OrderBy>,
+    limit: Option<usize>,
+    page: Option<Page>,
+}
+
+#[derive(Debug)]
+struct OrderBy {
+    field: String,
+    descending: bool,
+}
+
+#[derive(Debug)]
+enum Page {
+    Offset(usize),
+    After(RowKey),
+}
+
+/// Total ordering key of the row: value of the ordering field and sorted object ids
+#[derive(Debug)]
+struct RowKey {
+    value: Value,
+    object_ids: Vec<String>,
+}
+
+impl RowQuery {
+    pub fn new(
+        fields: Vec<String>,
+        order_by: Option<RpcOrdering>,
+        limit: Option<u64>,
+        page: Option<RpcPage>,
+    ) -> Result<Self, tonic::Status> {
+        let order_by = order_by.map(|order_by| OrderBy {
+            field: order_by.field,
+            descending: order_by.descending.unwrap_or_default(),
+        });
+        let page = match page {
+            None => None,
+            Some(RpcPage::Offset(offset)) => Some(Page::Offset(offset as usize)),
+            Some(RpcPage::After(cursor)) => {
+                let value = match (&order_by, cursor.value) {
+                    (Some(_), Some(value)) => serde_json::from_str(&value).map_err(|err| {
+                        tonic::Status::invalid_argument(format!("cursor value: {}", err))
+                    })?,
+                    (Some(_), None) => {
+                        return Err(tonic::Status::invalid_argument(
+                            "cursor value is required when ordering by field",
+                        ))
+                    }
+                    (None, _) => Value::Null,
+                };
+                let mut object_ids = cursor.object_ids;
+                object_ids.sort();
+                Some(Page::After(RowKey { value, object_ids }))
+            }
+        };
+
+        Ok(Self {
+            fields: fields.into_iter().collect(),
+            order_by,
+            limit: limit.map(|limit| limit as usize),
+            page,
+        })
+    }
+
+    /// Rows have to be ordered when ordering field or cursor is given, otherwise pages follow the order
+    /// in which object builder emits rows. Cursor without `order_by` orders rows by object ids.
+    fn requires_sorting(&self) -> bool {
+        self.order_by.is_some() || matches!(self.page, Some(Page::After(_)))
+    }
+
+    pub async fn apply(self, rows: RowStream) -> Result<RowStream, tonic::Status> {
+        let offset = match self.page {
+            Some(Page::Offset(offset)) => offset,
+            _ => 0,
+        };
+
+        if !self.requires_sorting() {
+            let fields = self.fields;
+            let mut skipped = 0;
+            let rows = rows
+                .try_filter(move |_| {
+                    skipped += 1;
+                    future::ready(skipped > offset)
+                })
+                .take(self.limit.unwrap_or(usize::MAX))
+                .map_ok(move |row| project(row, &fields));
+
+            return Ok(Box::pin(rows));
+        }
+
+        // Only rows which can still end up in the page are kept, the greatest one is dropped first
+        let kept = self.limit.map(|limit| offset.saturating_add(limit));
+        let descending = self.descending();
+        let mut heap = BinaryHeap::new();
+        let mut rows = rows;
+        while let Some(row) = rows.try_next().await? {
+            let key = self.key(&row);
+            if let Some(Page::After(cursor)) = &self.page {
+                if compare_keys(&key, cursor, descending) != Ordering::Greater {
+                    continue;
+                }
+            }
+
+            heap.push(Ranked {
+                key,
+                row,
+                descending,
+            });
+            if matches!(kept, Some(kept) if heap.len() > kept) {
+                heap.pop();
+            }
+        }
+
+        let rows: Vec<_> = heap
+            .into_sorted_vec()
+            .into_iter()
+            .skip(offset)
+            .map(|ranked| Ok(project(ranked.row, &self.fields)))
+            .collect();
+
+        Ok(Box::pin(stream::iter(rows)))
+    }
+
+    fn descending(&self) -> bool {
+        matches!(
+            self.order_by,
+            Some(OrderBy {
+                descending: true,
+                ..
+            })
+        )
+    }
+
+    fn key(&self, row: &RowDefinition) -> RowKey {
+        let value = self
+            .order_by
+            .as_ref()
+            .and_then(|order_by| row.fields.get(&order_by.field))
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or(Value::Null);
+        let mut object_ids = row.object_ids.clone();
+        object_ids.sort();
+
+        RowKey { value, object_ids }
+    }
+}
+
+/// Row ordered by its key, for selecting rows of the page
+struct Ranked {
+    key: RowKey,
+    row: RowDefinition,
+    descending: bool,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_keys(&self.key, &other.key, self.descending)
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+fn compare_keys(lhs: &RowKey, rhs: &RowKey, descending: bool) -> Ordering {
+    let ordering =
+        compare_values(&lhs.value, &rhs.value).then_with(|| lhs.object_ids.cmp(&rhs.object_ids));
+
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+fn project(mut row: RowDefinition, fields: &HashSet<String>) -> RowDefinition {
+    if !fields.is_empty() {
+        row.fields.retain(|name, _| fields.contains(name));
+    }
+    row
+}
+
+/// Orders values of different types: null < bool < number < string < array < object
+fn compare_values(lhs: &Value, rhs: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (lhs, rhs) {
+        (Value::Bool(lhs), Value::Bool(rhs)) => lhs.cmp(rhs),
+        (Value::Number(lhs), Value::Number(rhs)) => lhs
+            .as_f64()
+            .partial_cmp(&rhs.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(lhs), Value::String(rhs)) => lhs.cmp(rhs),
+        (lhs, rhs) if rank(lhs) == rank(rhs) => lhs.to_string().cmp(&rhs.to_string()),
+        (lhs, rhs) => rank(lhs).cmp(&rank(rhs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rpc::materializer_ondemand::Cursor;
+    use serde_json::json;
+
+    fn row(id: &str, value: Value) -> RowDefinition {
+        RowDefinition {
+            object_ids: vec![id.to_owned()],
+            fields: vec![
+                ("value".to_owned(), value.to_string()),
+                ("other".to_owned(), "null".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    async fn run(query: RowQuery, rows: Vec<RowDefinition>) -> Vec<RowDefinition> {
+        let rows: RowStream = Box::pin(stream::iter(rows.into_iter().map(Ok)));
+        query
+            .apply(rows)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn ids(rows: &[RowDefinition]) -> Vec<&str> {
+        rows.iter().map(|row| row.object_ids[0].as_str()).collect()
+    }
+
+    fn rows() -> Vec<RowDefinition> {
+        vec![
+            row("a", json!(3)),
+            row("b", json!(1)),
+            row("c", json!(2)),
+            row("d", json!(1)),
+        ]
+    }
+
+    fn order_by(descending: bool) -> Option<RpcOrdering> {
+        Some(RpcOrdering {
+            field: "value".to_owned(),
+            descending: Some(descending),
+        })
+    }
+
+    #[tokio::test]
+    async fn applies_offset_and_limit() {
+        let query = RowQuery::new(vec![], None, Some(2), Some(RpcPage::Offset(1))).unwrap();
+        assert_eq!(ids(&run(query, rows()).await), vec!["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn pages_by_offset_follow_input_order_without_ordering() {
+        let mut shuffled = rows();
+        shuffled.reverse();
+        let query = RowQuery::new(vec![], None, Some(2), Some(RpcPage::Offset(1))).unwrap();
+        assert_eq!(ids(&run(query, shuffled).await), vec!["c", "b"]);
+    }
+
+    #[tokio::test]
+    async fn pages_by_offset_of_ordered_rows() {
+        let query =
+            RowQuery::new(vec![], order_by(false), Some(2), Some(RpcPage::Offset(1))).unwrap();
+        assert_eq!(ids(&run(query, rows()).await), vec!["d", "c"]);
+
+        let query =
+            RowQuery::new(vec![], order_by(true), Some(0), Some(RpcPage::Offset(1))).unwrap();
+        assert!(run(query, rows()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn continues_after_cursor_in_order_of_object_ids() {
+        let mut shuffled = rows();
+        shuffled.reverse();
+        let cursor = Cursor {
+            object_ids: vec!["b".to_owned()],
+            value: None,
+        };
+        let query = RowQuery::new(vec![], None, Some(2), Some(RpcPage::After(cursor))).unwrap();
+        assert_eq!(ids(&run(query, shuffled).await), vec!["c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn orders_by_field_and_object_ids() {
+        let query = RowQuery::new(vec![], order_by(false), None, None).unwrap();
+        assert_eq!(ids(&run(query, rows()).await), vec!["b", "d", "c", "a"]);
+
+        let query = RowQuery::new(vec![], order_by(true), None, None).unwrap();
+        assert_eq!(ids(&run(query, rows()).await), vec!["a", "c", "d", "b"]);
+    }
+
+    #[tokio::test]
+    async fn continues_after_cursor() {
+        let cursor = Cursor {
+            object_ids: vec!["b".to_owned()],
+            value: Some("1".to_owned()),
+        };
+        let query = RowQuery::new(
+            vec![],
+            order_by(false),
+            Some(2),
+            Some(RpcPage::After(cursor)),
+        )
+        .unwrap();
+        assert_eq!(ids(&run(query, rows()).await), vec!["d", "c"]);
+    }
+
+    #[test]
+    fn cursor_requires_value_when_ordered() {
+        let cursor = Cursor {
+            object_ids: vec!["b".to_owned()],
+            value: None,
+        };
+        assert!(
+            RowQuery::new(vec![], order_by(false), None, Some(RpcPage::After(cursor))).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn projects_fields() {
+        let query = RowQuery::new(vec!["value".to_owned()], None, None, None).unwrap();
+        let rows = run(query, rows()).await;
+        assert!(rows
+            .iter()
+            .all(|row| row.fields.len() == 1 && row.fields.contains_key("value")));
+    }
+}
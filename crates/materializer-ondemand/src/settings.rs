@@ -1,9 +1,20 @@
+use communication_utils::consumer::{CommonConsumer, CommonConsumerConfig};
 use serde::Deserialize;
-use settings_utils::{LogSettings, MonitoringSettings};
+use settings_utils::*;
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub input_port: u16,
+    /// Port of HTTP endpoint exporting views as NDJSON or CSV, disabled when not set
+    pub http_port: Option<u16>,
+
+    /// Caching of materialization results, disabled when not set
+    pub cache: Option<CacheSettings>,
+
+    /// Source of notifications invalidating cached results, required only when cache is enabled
+    pub communication_method: Option<CommunicationMethod>,
+    pub kafka: Option<ConsumerKafkaSettings>,
+    pub amqp: Option<AmqpSettings>,
 
     pub services: ServicesSettings,
 
@@ -12,7 +23,52 @@ pub struct Settings {
     pub log: LogSettings,
 }
 
+impl Settings {
+    pub async fn consumer(&self) -> anyhow::Result<CommonConsumer> {
+        match (&self.kafka, &self.amqp, &self.communication_method) {
+            (Some(kafka), _, Some(CommunicationMethod::Kafka)) => {
+                Ok(CommonConsumer::new(CommonConsumerConfig::Kafka {
+                    brokers: &kafka.brokers,
+                    group_id: &kafka.group_id,
+                    topic: &kafka.ingest_topic,
+                })
+                .await?)
+            }
+            (_, Some(amqp), Some(CommunicationMethod::Amqp)) => {
+                Ok(CommonConsumer::new(CommonConsumerConfig::Amqp {
+                    connection_string: &amqp.exchange_url,
+                    consumer_tag: &amqp.tag,
+                    queue_name: &amqp.ingest_queue,
+                    options: amqp.consume_options,
+                })
+                .await?)
+            }
+            _ => anyhow::bail!("Unsupported consumer specification"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CacheSettings {
+    /// Maximum number of cached results
+    pub capacity: usize,
+    pub ttl_secs: u64,
+    /// Results with more rows are not cached
+    pub max_rows: usize,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ServicesSettings {
     pub object_builder_url: String,
+    /// Required only when cache is enabled
+    pub schema_registry_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommunicationMethod {
+    Kafka,
+    Amqp,
+    #[serde(other)]
+    Other,
 }
@@ -1,14 +1,22 @@
 use async_trait::async_trait;
 use bb8::Pool;
-use cdl_dto::{edges::RelationTree, materialization};
-use communication_utils::{consumer::ConsumerHandler, message::CommunicationMessage};
+use cdl_dto::{
+    edges::RelationTree,
+    materialization::{self, ComplexFilter, Filter},
+};
+use communication_utils::{
+    consumer::ConsumerHandler, message::CommunicationMessage,
+    parallel_consumer::ParallelConsumerHandler,
+};
 use futures::{future::ready, Stream, StreamExt, TryStreamExt};
 use metrics_utils::{self as metrics, counter};
 use row_builder::RowBuilder;
 use rpc::common::RowDefinition as RpcRowDefinition;
-use rpc::materializer_general::{MaterializedView as RpcMaterializedView, Options};
-use rpc::object_builder::{object_builder_server::ObjectBuilder, Empty, View};
-use rpc::schema_registry::types::SchemaType;
+use rpc::materializer_general::{
+    DeletedObjects as RpcDeletedObjects, MaterializedView as RpcMaterializedView, Options,
+};
+use rpc::object_builder::{object_builder_server::ObjectBuilder, Empty, View, ViewId};
+use rpc::schema_registry::types::{LogicOperator, SchemaType};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashSet;
@@ -187,24 +195,50 @@ impl TryInto<RpcMaterializedView> for MaterializedView {
     }
 }
 
-#[async_trait]
-impl ConsumerHandler for ObjectBuilderImpl {
-    #[tracing::instrument(skip(self, msg))]
-    async fn handle<'a>(&'a mut self, msg: &'a dyn CommunicationMessage) -> anyhow::Result<()> {
+impl ObjectBuilderImpl {
+    async fn handle_message(&self, msg: &dyn CommunicationMessage) -> anyhow::Result<()> {
         let payload = msg.payload()?;
         tracing::debug!(?payload, "Handle MQ message");
         counter!("cdl.object-builder.build-object.mq", 1);
         let request: materialization::Request = serde_json::from_str(&payload)?;
-        let view_id = request.view_id;
 
-        let view = self.get_view(view_id);
-        let chunks = self.build_materialized_chunks(request);
+        self.update_materialized_view(request).await
+    }
 
-        let (view, mut chunks) = futures::try_join!(view, chunks)?;
+    /// Builds rows of the view and upserts them in its materializer
+    async fn update_materialized_view(
+        &self,
+        mut request: materialization::Request,
+    ) -> anyhow::Result<()> {
+        let view_id = request.view_id;
+        let deleted_object_ids = std::mem::take(&mut request.deleted_object_ids);
 
+        let view = self.get_view(view_id).await?;
         let mut materializer =
-            rpc::materializer_general::connect(view.materializer_address).await?;
+            rpc::materializer_general::connect(view.materializer_address.clone()).await?;
+
+        if !deleted_object_ids.is_empty() {
+            counter!(
+                "cdl.object-builder.delete-objects",
+                deleted_object_ids.len() as u64
+            );
+            materializer
+                .delete_objects(RpcDeletedObjects {
+                    view_id: view_id.to_string(),
+                    options: Options {
+                        options: serde_json::to_string(&view.materializer_options)?,
+                    },
+                    object_ids: deleted_object_ids.iter().map(|id| id.to_string()).collect(),
+                })
+                .await?;
+
+            if request.schemas.is_empty() {
+                // Only deletions, empty `schemas` would otherwise rebuild the whole view
+                return Ok(());
+            }
+        }
 
+        let mut chunks = self.build_materialized_chunks(request).await?;
         while let Some(chunk) = chunks.try_next().await? {
             let rpc_output: RpcMaterializedView = chunk.try_into()?;
             materializer.upsert_view(rpc_output).await?;
@@ -214,6 +248,23 @@ impl ConsumerHandler for ObjectBuilderImpl {
     }
 }
 
+#[async_trait]
+impl ConsumerHandler for ObjectBuilderImpl {
+    #[tracing::instrument(skip(self, msg))]
+    async fn handle<'a>(&'a mut self, msg: &'a dyn CommunicationMessage) -> anyhow::Result<()> {
+        self.handle_message(msg).await
+    }
+}
+
+/// Used for gRPC communication method, where materialization requests are received via generic RPC
+#[async_trait]
+impl ParallelConsumerHandler for ObjectBuilderImpl {
+    #[tracing::instrument(skip(self, msg))]
+    async fn handle<'a>(&'a self, msg: &'a dyn CommunicationMessage) -> anyhow::Result<()> {
+        self.handle_message(msg).await
+    }
+}
+
 #[tonic::async_trait]
 impl ObjectBuilder for ObjectBuilderImpl {
     type MaterializeStream = MaterializeStream;
@@ -227,7 +278,7 @@ impl ObjectBuilder for ObjectBuilderImpl {
 
         let request: materialization::Request = view
             .try_into()
-            .map_err(|_| tonic::Status::invalid_argument("view"))?;
+            .map_err(|err| tonic::Status::invalid_argument(format!("view: {}", err)))?;
 
         let rows = self
             .build_rows(request)
@@ -252,6 +303,29 @@ impl ObjectBuilder for ObjectBuilderImpl {
         Ok(tonic::Response::new(stream))
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn refresh_view(
+        &self,
+        request: tonic::Request<ViewId>,
+    ) -> Result<tonic::Response<Empty>, tonic::Status> {
+        counter!("cdl.object-builder.refresh-view.grpc", 1);
+        let view_id = request
+            .into_inner()
+            .view_id
+            .parse()
+            .map_err(|err| tonic::Status::invalid_argument(format!("view_id: {}", err)))?;
+
+        // Request without schemas materializes every object of the view
+        self.update_materialized_view(materialization::Request::new(view_id))
+            .await
+            .map_err(|err| {
+                tracing::error!("Could not refresh view {}: {:?}", view_id, err);
+                tonic::Status::internal(format!("Could not refresh view: {}", err))
+            })?;
+
+        Ok(tonic::Response::new(Empty {}))
+    }
+
     #[tracing::instrument(skip(self))]
     async fn heartbeat(
         &self,
@@ -300,9 +374,23 @@ impl ObjectBuilderImpl {
 
     #[tracing::instrument(skip(self))]
     async fn build_rows(&self, request: materialization::Request) -> anyhow::Result<RowStream> {
-        let materialization::Request { view_id, schemas } = request;
-
-        let view = self.get_view(view_id).await?;
+        let materialization::Request {
+            view_id,
+            schemas,
+            filter,
+            ..
+        } = request;
+
+        let mut view = self.get_view(view_id).await?;
+        if let Some(filter) = filter {
+            view.filters = Some(match view.filters.take() {
+                Some(view_filter) => Filter::ComplexFilter(ComplexFilter {
+                    operator: LogicOperator::And,
+                    operands: vec![view_filter, filter],
+                }),
+                None => filter,
+            });
+        }
         tracing::debug!(?view, "View");
 
         let object_filters = create_object_filters(&schemas);
@@ -1,4 +1,5 @@
-use communication_utils::consumer::{CommonConsumer, CommonConsumerConfig};
+use communication_utils::consumer::{CommonConsumer, CommonConsumerConfig, ConsumerHandler};
+use communication_utils::parallel_consumer::{ParallelCommonConsumer, ParallelConsumerHandler};
 use serde::Deserialize;
 use settings_utils::*;
 
@@ -10,6 +11,7 @@ pub struct Settings {
 
     pub kafka: Option<ConsumerKafkaSettings>,
     pub amqp: Option<AmqpSettings>,
+    pub grpc: Option<GRpcSettings>,
 
     pub services: ServicesSettings,
 
@@ -19,25 +21,50 @@ pub struct Settings {
     pub log: LogSettings,
 }
 
+pub enum Consumer {
+    Common(CommonConsumer),
+    Parallel(ParallelCommonConsumer),
+}
+
+impl Consumer {
+    pub async fn run<H>(self, handler: H) -> communication_utils::Result<()>
+    where
+        H: ConsumerHandler + ParallelConsumerHandler,
+    {
+        match self {
+            Consumer::Common(consumer) => consumer.run(handler).await,
+            Consumer::Parallel(consumer) => consumer.par_run(handler).await,
+        }
+    }
+}
+
 impl Settings {
-    pub async fn consumer(&self) -> anyhow::Result<CommonConsumer> {
-        match (&self.kafka, &self.amqp, &self.communication_method) {
-            (Some(kafka), _, CommunicationMethod::Kafka) => {
-                Ok(CommonConsumer::new(CommonConsumerConfig::Kafka {
+    pub async fn consumer(&self) -> anyhow::Result<Consumer> {
+        match (
+            &self.kafka,
+            &self.amqp,
+            &self.grpc,
+            &self.communication_method,
+        ) {
+            (Some(kafka), _, _, CommunicationMethod::Kafka) => Ok(Consumer::Common(
+                CommonConsumer::new(CommonConsumerConfig::Kafka {
                     brokers: &kafka.brokers,
                     group_id: &kafka.group_id,
                     topic: &kafka.ingest_topic,
                 })
-                .await?)
-            }
-            (_, Some(amqp), CommunicationMethod::Amqp) => {
-                Ok(CommonConsumer::new(CommonConsumerConfig::Amqp {
+                .await?,
+            )),
+            (_, Some(amqp), _, CommunicationMethod::Amqp) => Ok(Consumer::Common(
+                CommonConsumer::new(CommonConsumerConfig::Amqp {
                     connection_string: &amqp.exchange_url,
                     consumer_tag: &amqp.tag,
                     queue_name: &amqp.ingest_queue,
                     options: amqp.consume_options,
                 })
-                .await?)
+                .await?,
+            )),
+            (_, _, Some(grpc), CommunicationMethod::GRpc) => {
+                Ok(Consumer::Parallel(grpc.parallel_consumer().await?))
             }
             _ => anyhow::bail!("Unsupported consumer specification"),
         }
@@ -55,6 +82,8 @@ pub struct ServicesSettings {
 pub enum CommunicationMethod {
     Kafka,
     Amqp,
+    #[serde(rename = "grpc")]
+    GRpc,
     #[serde(other)]
     Other,
 }
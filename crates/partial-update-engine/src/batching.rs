@@ -0,0 +1,311 @@
+use cdl_dto::materialization::Request;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct BatchingSettings {
+    /// Maximum number of notifications in a single batch
+    pub max_size: usize,
+    /// Maximum time between receiving notification and sending materialization request it caused
+    pub max_age_ms: u64,
+    /// Request for a view is sent only when no notification touched it for this long
+    pub debounce_ms: u64,
+}
+
+impl BatchingSettings {
+    fn max_age(&self) -> Duration {
+        Duration::from_millis(self.max_age_ms)
+    }
+
+    fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+}
+
+/// Notifications received since last flush.
+/// `A` acknowledges a single notification, once requests it caused are sent.
+#[derive(Debug)]
+pub struct Batch<N, A> {
+    pub notifications: HashSet<N>,
+    acks: Vec<A>,
+    started: Option<Instant>,
+    last_received: Option<Instant>,
+    oldest_timestamp: Option<i64>,
+}
+
+impl<N, A> Default for Batch<N, A> {
+    fn default() -> Self {
+        Self {
+            notifications: HashSet::new(),
+            acks: Vec::new(),
+            started: None,
+            last_received: None,
+            oldest_timestamp: None,
+        }
+    }
+}
+
+impl<N: Eq + Hash, A> Batch<N, A> {
+    /// `timestamp` is the creation time of the notification message, in milliseconds since Unix epoch
+    pub fn add(&mut self, notification: N, timestamp: Option<i64>, ack: A) {
+        let now = Instant::now();
+        self.notifications.insert(notification);
+        self.acks.push(ack);
+        self.started.get_or_insert(now);
+        self.last_received = Some(now);
+        self.oldest_timestamp = min_timestamp(self.oldest_timestamp, timestamp);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.started.is_none()
+    }
+
+    /// Number of received notifications, duplicates included
+    pub fn len(&self) -> usize {
+        self.acks.len()
+    }
+
+    pub fn deadline(&self, settings: &BatchingSettings) -> Option<Instant> {
+        self.started.map(|started| started + settings.max_age())
+    }
+
+    pub fn is_due(&self, settings: &BatchingSettings, now: Instant) -> bool {
+        self.len() >= settings.max_size
+            || matches!(self.deadline(settings), Some(deadline) if deadline <= now)
+    }
+}
+
+/// Notifications of the flushed batch, acknowledged once requests for every view it affected are sent
+#[derive(Debug)]
+struct FlushedBatch<A> {
+    acks: Vec<A>,
+    views: HashSet<Uuid>,
+}
+
+#[derive(Debug)]
+struct PendingView {
+    request: Request,
+    first_change: Instant,
+    last_change: Instant,
+    oldest_timestamp: Option<i64>,
+}
+
+/// Materialization request ready to be sent
+#[derive(Debug)]
+pub struct ReadyRequest {
+    pub request: Request,
+    /// When the oldest notification which caused this request was received
+    pub first_change: Instant,
+    /// When the oldest notification which caused this request was created, in milliseconds
+    /// since Unix epoch. Missing when messages carried no timestamp
+    pub oldest_timestamp: Option<i64>,
+}
+
+/// Requests waiting for the debounce period of their views to pass
+#[derive(Debug)]
+pub struct PendingRequests<A> {
+    views: HashMap<Uuid, PendingView>,
+    batches: Vec<FlushedBatch<A>>,
+    max_age: Duration,
+    debounce: Duration,
+}
+
+impl<A> PendingRequests<A> {
+    pub fn new(settings: &BatchingSettings) -> Self {
+        Self {
+            views: HashMap::new(),
+            batches: Vec::new(),
+            max_age: settings.max_age(),
+            debounce: settings.debounce(),
+        }
+    }
+
+    /// Merges requests produced from `batch` into pending ones
+    pub fn add<N>(&mut self, batch: Batch<N, A>, requests: HashMap<Uuid, Request>) {
+        let now = Instant::now();
+        let first_change = batch.started.unwrap_or(now);
+        let last_change = batch.last_received.unwrap_or(now);
+        let oldest_timestamp = batch.oldest_timestamp;
+
+        self.batches.push(FlushedBatch {
+            acks: batch.acks,
+            views: requests.keys().copied().collect(),
+        });
+
+        for (view_id, request) in requests {
+            let pending = self.views.entry(view_id).or_insert_with(|| PendingView {
+                request: Request::new(view_id),
+                first_change,
+                last_change,
+                oldest_timestamp,
+            });
+
+            for (schema_id, schema) in request.schemas {
+                pending
+                    .request
+                    .schemas
+                    .entry(schema_id)
+                    .or_default()
+                    .object_ids
+                    .extend(schema.object_ids);
+            }
+            pending
+                .request
+                .deleted_object_ids
+                .extend(request.deleted_object_ids);
+            pending.last_change = last_change;
+            pending.oldest_timestamp = min_timestamp(pending.oldest_timestamp, oldest_timestamp);
+        }
+    }
+
+    fn view_deadline(&self, view: &PendingView) -> Instant {
+        (view.last_change + self.debounce).min(view.first_change + self.max_age)
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.views
+            .values()
+            .map(|view| self.view_deadline(view))
+            .min()
+    }
+
+    /// Removes requests of views which were not changed for the debounce period,
+    /// or which were waiting for longer than the maximum batch age
+    pub fn take_ready(&mut self, now: Instant) -> Vec<ReadyRequest> {
+        let ready: Vec<Uuid> = self
+            .views
+            .iter()
+            .filter(|(_, view)| self.view_deadline(view) <= now)
+            .map(|(view_id, _)| *view_id)
+            .collect();
+
+        ready
+            .into_iter()
+            .filter_map(|view_id| self.views.remove(&view_id))
+            .map(|view| ReadyRequest {
+                request: view.request,
+                first_change: view.first_change,
+                oldest_timestamp: view.oldest_timestamp,
+            })
+            .collect()
+    }
+
+    pub fn take_all(&mut self) -> Vec<ReadyRequest> {
+        self.views
+            .drain()
+            .map(|(_, view)| ReadyRequest {
+                request: view.request,
+                first_change: view.first_change,
+                oldest_timestamp: view.oldest_timestamp,
+            })
+            .collect()
+    }
+
+    /// Returns acknowledgements of batches whose requests were all taken
+    pub fn completed_acks(&mut self) -> Vec<A> {
+        let pending = &self.views;
+        for batch in self.batches.iter_mut() {
+            batch.views.retain(|view_id| pending.contains_key(view_id));
+        }
+
+        let (completed, batches): (Vec<_>, Vec<_>) = std::mem::take(&mut self.batches)
+            .into_iter()
+            .partition(|batch| batch.views.is_empty());
+        self.batches = batches;
+
+        completed.into_iter().flat_map(|batch| batch.acks).collect()
+    }
+}
+
+fn min_timestamp(lhs: Option<i64>, rhs: Option<i64>) -> Option<i64> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(lhs.min(rhs)),
+        (lhs, rhs) => lhs.or(rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(debounce_ms: u64) -> BatchingSettings {
+        BatchingSettings {
+            max_size: 2,
+            max_age_ms: 60_000,
+            debounce_ms,
+        }
+    }
+
+    fn requests(view_ids: &[u128]) -> HashMap<Uuid, Request> {
+        view_ids
+            .iter()
+            .map(|id| {
+                let view_id = Uuid::from_u128(*id);
+                (view_id, Request::new(view_id))
+            })
+            .collect()
+    }
+
+    fn batch(id: u32) -> Batch<u32, u32> {
+        let mut batch = Batch::default();
+        batch.add(id, Some(id as i64), id);
+        batch
+    }
+
+    #[test]
+    fn batch_is_due_when_full() {
+        let settings = settings(0);
+        let mut batch = batch(1);
+        assert!(!batch.is_due(&settings, Instant::now()));
+
+        batch.add(2, None, 2);
+        assert!(batch.is_due(&settings, Instant::now()));
+        assert!(batch.is_due(&settings, Instant::now() + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn waits_for_debounce_of_view() {
+        let mut pending = PendingRequests::new(&settings(1_000));
+        pending.add(batch(1), requests(&[1]));
+
+        assert!(pending.take_ready(Instant::now()).is_empty());
+        let ready = pending.take_ready(Instant::now() + Duration::from_secs(2));
+        assert_eq!(ready.len(), 1);
+        assert!(pending.deadline().is_none());
+    }
+
+    #[test]
+    fn keeps_oldest_timestamp_of_view() {
+        let mut pending = PendingRequests::new(&settings(0));
+        pending.add(batch(5), requests(&[1]));
+        pending.add(batch(3), requests(&[1, 2]));
+        pending.add(batch(4), requests(&[2]));
+
+        let mut ready = pending.take_all();
+        ready.sort_by_key(|ready| ready.request.view_id);
+        let timestamps: Vec<_> = ready.iter().map(|ready| ready.oldest_timestamp).collect();
+        assert_eq!(timestamps, vec![Some(3), Some(3)]);
+    }
+
+    #[test]
+    fn acknowledges_batch_when_all_its_views_are_taken() {
+        let mut pending = PendingRequests::new(&settings(0));
+        pending.add(batch(1), requests(&[1]));
+        pending.add(batch(2), requests(&[2]));
+        pending.add(batch(3), requests(&[]));
+        pending.add(batch(4), requests(&[1, 2]));
+
+        pending.views.remove(&Uuid::from_u128(2));
+        let mut acks = pending.completed_acks();
+        acks.sort_unstable();
+        assert_eq!(acks, vec![2, 3]);
+
+        pending.views.remove(&Uuid::from_u128(1));
+        let mut acks = pending.completed_acks();
+        acks.sort_unstable();
+        assert_eq!(acks, vec![1, 4]);
+    }
+}
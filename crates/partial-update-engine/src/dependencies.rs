@@ -0,0 +1,254 @@
+use anyhow::Result;
+use rpc::edge_registry::{ChildObjectsQuery, EdgeRegistryConn};
+use rpc::schema_registry::{FullView, Id, Relation, SchemaRegistryConn};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Chain of relations leading from base schema of the view to the changed schema
+#[derive(Debug, PartialEq)]
+pub struct RelationPath {
+    pub view_id: Uuid,
+    pub base_schema_id: Uuid,
+    pub relations: Vec<Uuid>,
+}
+
+#[derive(Debug)]
+struct ViewRelations {
+    view_id: Uuid,
+    base_schema_id: Uuid,
+    relations: Vec<Relation>,
+}
+
+/// Views together with their relation trees, used to find views depending on objects of given schema
+#[derive(Debug)]
+pub struct ReverseDependencies {
+    /// Relation id -> child schema id
+    child_schemas: HashMap<Uuid, Uuid>,
+    views: Vec<ViewRelations>,
+}
+
+impl ReverseDependencies {
+    /// Loads only views which can contain any of `schema_ids` in their relation trees,
+    /// that is views based on schemas from which relations lead to `schema_ids`.
+    /// Views of schemas are taken from `views_of_schemas` when already fetched in the batch.
+    pub async fn load(
+        sr_client: &mut SchemaRegistryConn,
+        er_client: &mut EdgeRegistryConn,
+        schema_ids: impl IntoIterator<Item = Uuid>,
+        views_of_schemas: &mut HashMap<Uuid, Vec<FullView>>,
+    ) -> Result<Self> {
+        let mut child_schemas = HashMap::new();
+        let mut parent_schemas: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        let relations = er_client
+            .list_relations(rpc::edge_registry::Empty {})
+            .await?
+            .into_inner()
+            .items;
+        for relation in relations {
+            let child_schema_id: Uuid = relation.child_schema_id.parse()?;
+            child_schemas.insert(relation.relation_id.parse()?, child_schema_id);
+            parent_schemas
+                .entry(child_schema_id)
+                .or_default()
+                .push(relation.parent_schema_id.parse()?);
+        }
+
+        let mut views = vec![];
+        for base_schema_id in ancestor_schemas(&parent_schemas, schema_ids) {
+            let schema_views = match views_of_schemas.entry(base_schema_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    sr_client
+                        .get_all_views_of_schema(Id {
+                            id: base_schema_id.to_string(),
+                        })
+                        .await?
+                        .into_inner()
+                        .views,
+                ),
+            };
+            for view in schema_views.iter() {
+                if view.relations.is_empty() {
+                    continue;
+                }
+                views.push(ViewRelations {
+                    view_id: view.id.parse()?,
+                    base_schema_id,
+                    relations: view.relations.clone(),
+                });
+            }
+        }
+
+        Ok(Self {
+            child_schemas,
+            views,
+        })
+    }
+
+    /// Finds every relation path (in every view) which ends in `schema_id`
+    pub fn paths_to_schema(&self, schema_id: Uuid) -> Vec<RelationPath> {
+        let mut paths = vec![];
+        for view in self.views.iter() {
+            let mut stack = vec![];
+            self.collect_paths(view, &view.relations, schema_id, &mut stack, &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths(
+        &self,
+        view: &ViewRelations,
+        relations: &[Relation],
+        schema_id: Uuid,
+        stack: &mut Vec<Uuid>,
+        paths: &mut Vec<RelationPath>,
+    ) {
+        for relation in relations {
+            let relation_id = match relation.global_id.parse() {
+                Ok(relation_id) => relation_id,
+                Err(_) => {
+                    tracing::warn!(
+                        "View {} contains invalid relation id {}",
+                        view.view_id,
+                        relation.global_id
+                    );
+                    continue;
+                }
+            };
+
+            stack.push(relation_id);
+            if self.child_schemas.get(&relation_id) == Some(&schema_id) {
+                paths.push(RelationPath {
+                    view_id: view.view_id,
+                    base_schema_id: view.base_schema_id,
+                    relations: stack.clone(),
+                });
+            }
+            self.collect_paths(view, &relation.relations, schema_id, stack, paths);
+            stack.pop();
+        }
+    }
+}
+
+/// Schemas from which a chain of relations leads to any of `schema_ids`
+fn ancestor_schemas(
+    parent_schemas: &HashMap<Uuid, Vec<Uuid>>,
+    schema_ids: impl IntoIterator<Item = Uuid>,
+) -> HashSet<Uuid> {
+    let mut ancestors = HashSet::new();
+    let mut stack: Vec<Uuid> = schema_ids.into_iter().collect();
+    while let Some(schema_id) = stack.pop() {
+        for parent in parent_schemas.get(&schema_id).into_iter().flatten() {
+            // Relations may form cycles
+            if ancestors.insert(*parent) {
+                stack.push(*parent);
+            }
+        }
+    }
+    ancestors
+}
+
+impl RelationPath {
+    /// Walks edges backwards, from changed objects to the objects of view base schema
+    pub async fn find_roots(
+        &self,
+        er_client: &mut EdgeRegistryConn,
+        object_ids: &HashSet<Uuid>,
+    ) -> Result<HashSet<Uuid>> {
+        let mut current = object_ids.clone();
+        for relation_id in self.relations.iter().rev() {
+            if current.is_empty() {
+                break;
+            }
+
+            current = er_client
+                .get_parents(ChildObjectsQuery {
+                    relation_id: relation_id.to_string(),
+                    child_object_ids: current.iter().map(|id| id.to_string()).collect(),
+                })
+                .await?
+                .into_inner()
+                .object_ids
+                .into_iter()
+                .map(|id| id.parse())
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rpc::schema_registry::SearchFor;
+
+    fn relation(id: u128, relations: Vec<Relation>) -> Relation {
+        Relation {
+            global_id: Uuid::from_u128(id).to_string(),
+            local_id: 0,
+            search_for: SearchFor { search_for: 1 },
+            relations,
+        }
+    }
+
+    #[test]
+    fn finds_nested_paths() {
+        let changed_schema = Uuid::from_u128(100);
+        let dependencies = ReverseDependencies {
+            child_schemas: vec![
+                (Uuid::from_u128(1), Uuid::from_u128(101)),
+                (Uuid::from_u128(2), changed_schema),
+                (Uuid::from_u128(3), changed_schema),
+            ]
+            .into_iter()
+            .collect(),
+            views: vec![ViewRelations {
+                view_id: Uuid::from_u128(10),
+                base_schema_id: Uuid::from_u128(11),
+                relations: vec![relation(1, vec![relation(2, vec![])]), relation(3, vec![])],
+            }],
+        };
+
+        let paths = dependencies.paths_to_schema(changed_schema);
+
+        assert_eq!(
+            paths
+                .into_iter()
+                .map(|path| path.relations)
+                .collect::<Vec<_>>(),
+            vec![
+                vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+                vec![Uuid::from_u128(3)],
+            ]
+        );
+        assert!(dependencies
+            .paths_to_schema(Uuid::from_u128(999))
+            .is_empty());
+    }
+
+    #[test]
+    fn finds_ancestor_schemas() {
+        let parent_schemas: HashMap<Uuid, Vec<Uuid>> = vec![
+            (Uuid::from_u128(3), vec![Uuid::from_u128(2)]),
+            (
+                Uuid::from_u128(2),
+                vec![Uuid::from_u128(1), Uuid::from_u128(3)],
+            ),
+            (Uuid::from_u128(5), vec![Uuid::from_u128(4)]),
+        ]
+        .into_iter()
+        .collect();
+
+        let ancestors = ancestor_schemas(&parent_schemas, vec![Uuid::from_u128(3)]);
+
+        assert_eq!(
+            ancestors,
+            vec![Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3)]
+                .into_iter()
+                .collect()
+        );
+        assert!(ancestor_schemas(&parent_schemas, vec![Uuid::from_u128(4)]).is_empty());
+    }
+}
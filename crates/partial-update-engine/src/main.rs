@@ -1,50 +1,132 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
+use batching::{Batch, BatchingSettings, PendingRequests, ReadyRequest};
+use cdl_dto::ingestion::Operation;
 use cdl_dto::materialization::Request;
-use misc_utils::set_aborting_panic_hook;
-use rdkafka::consumer::Consumer;
-use rdkafka::{
-    consumer::{CommitMode, DefaultConsumerContext, StreamConsumer},
-    message::{BorrowedMessage, OwnedHeaders},
-    producer::{FutureProducer, FutureRecord},
-    ClientConfig, Message, Offset, TopicPartitionList,
+use chrono::{DateTime, Utc};
+use communication_utils::{
+    message::CommunicationMessage,
+    parallel_consumer::{ParallelCommonConsumer, ParallelConsumerHandler},
+    publisher::CommonPublisher,
 };
-use rpc::schema_registry::{FullView, Id};
+use dependencies::ReverseDependencies;
+use leader_utils::{LeaderElectionSettings, LeaderElector, LeadershipHandler};
+use metrics_utils::{self as metrics, counter, histogram};
+use misc_utils::set_aborting_panic_hook;
+use refresh::{RefreshSchedule, RefreshSettings};
+use rpc::edge_registry::EdgeRegistryConn;
+use rpc::schema_registry::{FullView, Id, SchemaRegistryConn};
 use serde::{Deserialize, Serialize};
 use settings_utils::*;
 use std::collections::hash_map::Entry;
 use std::{
     collections::{HashMap, HashSet},
-    time::Duration,
+    sync::Mutex,
+    time::{Duration, Instant},
 };
-use tokio::time::sleep;
-use tokio_stream::StreamExt;
-use tracing::{trace, Instrument};
+use task_utils::task_limiter::TaskLimiter;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{sleep, sleep_until};
+use tracing::trace;
 use uuid::Uuid;
 
+mod batching;
+mod dependencies;
+mod refresh;
+
 #[derive(Deserialize, Debug, Serialize)]
 struct Settings {
     communication_method: CommunicationMethod,
-    sleep_phase_length: u64,
 
-    kafka: PublisherKafkaSettings,
-    notification_consumer: NotificationConsumerSettings,
+    batching: BatchingSettings,
+    /// Scheduled full refresh of views, disabled when not set
+    refresh: Option<RefreshSettings>,
+
+    kafka: Option<ConsumerKafkaSettings>,
+    amqp: Option<AmqpSettings>,
+    grpc: Option<GRpcSettings>,
+
+    materialization: MaterializationSettings,
     services: ServicesSettings,
 
+    /// Lets hot standbys wait for the active instance to fail, disabled when not set
+    leader_election: Option<LeaderElectionSettings>,
+
     monitoring: MonitoringSettings,
 
     log: LogSettings,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
-struct NotificationConsumerSettings {
-    pub brokers: String,
-    pub group_id: String,
-    pub source: String,
+struct MaterializationSettings {
+    /// Kafka topic, AMQP exchange or object builder address (for gRPC) receiving materialization requests
+    pub destination: String,
 }
 
 #[derive(Deserialize, Debug, Serialize)]
 struct ServicesSettings {
     pub schema_registry_url: String,
+    pub edge_registry_url: String,
+}
+
+/// Clients of registries, connected once at startup. Channels reconnect on their own,
+/// so the same clients serve every batch.
+struct Registries {
+    schema_registry: SchemaRegistryConn,
+    edge_registry: EdgeRegistryConn,
+}
+
+impl ServicesSettings {
+    async fn connect(&self) -> Result<Registries> {
+        Ok(Registries {
+            schema_registry: rpc::schema_registry::connect(self.schema_registry_url.to_owned())
+                .await?,
+            edge_registry: rpc::edge_registry::connect(self.edge_registry_url.to_owned()).await?,
+        })
+    }
+}
+
+impl Settings {
+    async fn consumer(&self) -> Result<ParallelCommonConsumer> {
+        // Every notification of a batch is handled concurrently until the batch is flushed
+        let task_limiter = TaskLimiter::new(self.batching.max_size);
+
+        match (
+            &self.kafka,
+            &self.amqp,
+            &self.grpc,
+            &self.communication_method,
+        ) {
+            (Some(kafka), _, _, CommunicationMethod::Kafka) => {
+                kafka.parallel_consumer(task_limiter).await
+            }
+            (_, Some(amqp), _, CommunicationMethod::Amqp) => {
+                amqp.parallel_consumer(task_limiter).await
+            }
+            (_, _, Some(grpc), CommunicationMethod::GRpc) => grpc.parallel_consumer().await,
+            _ => anyhow::bail!("Unsupported consumer specification"),
+        }
+    }
+
+    async fn publisher(&self) -> Result<CommonPublisher> {
+        Ok(
+            match (
+                &self.kafka,
+                &self.amqp,
+                &self.grpc,
+                &self.communication_method,
+            ) {
+                (Some(kafka), _, _, CommunicationMethod::Kafka) => {
+                    CommonPublisher::new_kafka(&kafka.brokers).await?
+                }
+                (_, Some(amqp), _, CommunicationMethod::Amqp) => {
+                    CommonPublisher::new_amqp(&amqp.exchange_url).await?
+                }
+                (_, _, Some(_), CommunicationMethod::GRpc) => CommonPublisher::new_grpc().await?,
+                _ => anyhow::bail!("Unsupported publisher specification"),
+            },
+        )
+    }
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
@@ -59,6 +141,8 @@ enum PartialNotification {
 struct CommandServiceNotification {
     pub object_id: Uuid,
     pub schema_id: Uuid,
+    #[serde(default)]
+    pub operation: Operation,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
@@ -68,6 +152,86 @@ struct EdgeRegistryNotification {
     pub parent_object_id: Uuid,
 }
 
+/// Notification with timestamp of its message and the channel acknowledging it,
+/// used after materialization requests it caused are sent
+type ReceivedNotification = (PartialNotification, Option<i64>, oneshot::Sender<()>);
+
+/// Passes notifications to the batching loop. Message is acknowledged (Kafka offset stored,
+/// AMQP delivery acked, gRPC call answered) only after handler returns, which happens once
+/// the batch containing the notification has been processed.
+struct NotificationHandler {
+    sender: mpsc::UnboundedSender<ReceivedNotification>,
+}
+
+#[async_trait]
+impl ParallelConsumerHandler for NotificationHandler {
+    #[tracing::instrument(skip(self, msg))]
+    async fn handle<'a>(&'a self, msg: &'a dyn CommunicationMessage) -> Result<()> {
+        let payload = msg.payload()?;
+        let notification: PartialNotification = match serde_json::from_str(payload) {
+            Ok(notification) => notification,
+            Err(err) => {
+                tracing::error!("Skipping invalid notification {}: {}", payload, err);
+                return Ok(());
+            }
+        };
+        trace!("new notification {:#?}", notification);
+
+        let (ack, acked) = oneshot::channel();
+        self.sender
+            .send((notification, msg.timestamp(), ack))
+            .map_err(|_| anyhow::anyhow!("Batching loop has finished"))?;
+        acked
+            .await
+            .context("Notification was not processed, batch failed")?;
+
+        Ok(())
+    }
+}
+
+/// Lets the service start once it becomes the leader. Service aborts when leadership is lost,
+/// unacknowledged notifications are then redelivered to the new leader.
+struct Leadership {
+    acquired: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+#[async_trait]
+impl LeadershipHandler for Leadership {
+    async fn on_acquired(&self) -> Result<()> {
+        if let Some(acquired) = self.acquired.lock().unwrap().take() {
+            let _ = acquired.send(());
+        }
+        Ok(())
+    }
+
+    async fn on_lost(&self) -> Result<()> {
+        tracing::error!("Leadership lost, stopping");
+        std::process::abort();
+    }
+}
+
+async fn wait_for_leadership(settings: LeaderElectionSettings) -> Result<()> {
+    let (acquired, on_acquired) = oneshot::channel();
+    let leadership = Leadership {
+        acquired: Mutex::new(Some(acquired)),
+    };
+
+    let elector = LeaderElector::new(settings).await?;
+    tokio::spawn(async {
+        if let Err(err) = elector.run(leadership).await {
+            tracing::error!("Leader election returned with error: {:?}", err);
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+        std::process::abort();
+    });
+
+    on_acquired
+        .await
+        .context("Leader election finished before acquiring leadership")
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     set_aborting_panic_hook();
@@ -81,67 +245,103 @@ async fn main() -> anyhow::Result<()> {
     tracing::debug!(?settings, "application environment");
 
     metrics_utils::serve(&settings.monitoring);
+    utils::status_endpoints::serve(&settings.monitoring);
+
+    if let Some(leader_election) = settings.leader_election.clone() {
+        // Standbys are started, they are also ready unless `ready_only_when_leader` is set
+        utils::status_endpoints::mark_as_started();
+        wait_for_leadership(leader_election).await?;
+    }
+
+    let consumer = settings.consumer().await?;
+    let publisher = settings.publisher().await?;
+    let mut registries = settings.services.connect().await?;
+    utils::status_endpoints::mark_as_started();
+
+    let (sender, mut notifications) = mpsc::unbounded_channel();
+    tokio::spawn(async {
+        tracing::info!("Listening for notifications");
 
-    let consumer: StreamConsumer<DefaultConsumerContext> = ClientConfig::new()
-        .set("group.id", &settings.notification_consumer.group_id)
-        .set("bootstrap.servers", &settings.notification_consumer.brokers)
-        .set("enable.partition.eof", "false")
-        .set("session.timeout.ms", "6000")
-        .create()
-        .context("Consumer creation failed")?;
-    let topics = [settings.notification_consumer.source.as_str()];
-
-    consumer
-        .subscribe(&topics)
-        .context("Can't subscribe to specified topics")?;
-
-    let producer = ClientConfig::new()
-        .set("bootstrap.servers", &settings.kafka.brokers)
-        .set("message.timeout.ms", "5000")
-        .set("acks", "all")
-        .set("compression.type", "none")
-        .set("max.in.flight.requests.per.connection", "5")
-        .create()?;
-
-    let mut message_stream = Box::pin(consumer.stream().timeout(Duration::from_secs(2))); // TODO: configure?
-    let mut changes: HashSet<PartialNotification> = HashSet::new();
-    let mut offsets: HashMap<i32, i64> = HashMap::new();
+        match consumer.par_run(NotificationHandler { sender }).await {
+            Ok(_) => {
+                tracing::error!("Notification consumer finished work");
+            }
+            Err(err) => {
+                tracing::error!("Notification consumer returned with error: {:?}", err);
+            }
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+        std::process::abort();
+    });
+
+    let mut batch: Batch<PartialNotification, oneshot::Sender<()>> = Batch::default();
+    let mut pending = PendingRequests::new(&settings.batching);
+    let mut schedule = RefreshSchedule::default();
+    let mut next_reload = settings.refresh.as_ref().map(|_| Instant::now());
     loop {
-        // TODO: configure max items per batch(?) - otherwise we won't start view recalculation if messages are sent more often then timeout
-        match message_stream.try_next().await {
-            Ok(opt_message) => match opt_message {
-                Some(message) => {
-                    let (partition, offset) = new_notification(&mut changes, message?)?;
-                    offsets.insert(partition, offset);
-                }
-                None => {
-                    process_changes(&producer, &settings, &mut changes).await?;
-                    acknowledge_messages(
-                        &mut offsets,
-                        &consumer,
-                        &settings.notification_consumer.source,
-                    )
-                    .await?;
-                    break;
+        // Whichever limit is hit first: batch age, batch size (checked below), debounce of pending view
+        // or scheduled refresh
+        let deadline = batch
+            .deadline(&settings.batching)
+            .into_iter()
+            .chain(pending.deadline())
+            .chain(next_reload)
+            .chain(schedule.deadline().map(instant_of))
+            .min();
+
+        let finished = tokio::select! {
+            notification = notifications.recv() => match notification {
+                Some((notification, timestamp, ack)) => {
+                    batch.add(notification, timestamp, ack);
+                    false
                 }
+                None => true,
             },
-            Err(_) => {
-                trace!("Timeout");
-                if !changes.is_empty() {
-                    process_changes(&producer, &settings, &mut changes).await?;
-                    acknowledge_messages(
-                        &mut offsets,
-                        &consumer,
-                        &settings.notification_consumer.source,
-                    )
-                    .await?;
+            _ = sleep_until(deadline.unwrap_or_else(far_future).into()) => {
+                trace!("Deadline reached");
+                false
+            }
+        };
+
+        if !batch.is_empty() && (finished || batch.is_due(&settings.batching, Instant::now())) {
+            histogram!("cdl.partial-update-engine.batch.size", batch.len() as f64);
+            let requests = process_changes(&mut registries, &mut batch.notifications).await?;
+            pending.add(std::mem::take(&mut batch), requests);
+        }
+
+        let ready = if finished {
+            pending.take_all()
+        } else {
+            pending.take_ready(Instant::now())
+        };
+        if !ready.is_empty() {
+            send_requests(&publisher, &settings, ready).await?;
+        }
+
+        for ack in pending.completed_acks() {
+            // Handler is gone only if its consumer stopped waiting, nothing left to acknowledge then
+            let _ = ack.send(());
+        }
+
+        if finished {
+            break;
+        }
+
+        if let (Some(refresh), Some(reload)) = (&settings.refresh, next_reload) {
+            if reload <= Instant::now() {
+                if let Err(err) = reload_schedule(&mut registries, &mut schedule).await {
+                    tracing::error!("Could not reload refresh policies: {:?}", err);
                 }
-                let sleep_phase = tracing::info_span!("Sleep phase");
-                sleep(Duration::from_secs(settings.sleep_phase_length))
-                    .instrument(sleep_phase)
-                    .await;
+                next_reload = Some(Instant::now() + refresh.reload_interval());
             }
         }
+
+        let due = schedule.take_due(Utc::now());
+        if !due.is_empty() {
+            send_refreshes(&publisher, &settings, due).await?;
+        }
     }
 
     sleep(tokio::time::Duration::from_secs(3)).await;
@@ -149,41 +349,49 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-#[tracing::instrument(skip(message))]
-fn new_notification(
-    changes: &mut HashSet<PartialNotification>,
-    message: BorrowedMessage,
-) -> Result<(i32, i64)> {
-    tracing_utils::kafka::set_parent_span(&message);
-    let payload = message
-        .payload_view::<str>()
-        .ok_or_else(|| anyhow::anyhow!("Message has no payload"))??;
-
-    let notification: PartialNotification = serde_json::from_str(payload)?;
-    trace!("new notification {:#?}", notification);
-    changes.insert(notification);
-    let partition = message.partition();
-    let offset = message.offset();
-    Ok((partition, offset))
+fn far_future() -> Instant {
+    Instant::now() + Duration::from_secs(3600)
+}
+
+fn instant_of(time: DateTime<Utc>) -> Instant {
+    Instant::now() + (time - Utc::now()).to_std().unwrap_or_default()
+}
+
+async fn reload_schedule(
+    registries: &mut Registries,
+    schedule: &mut RefreshSchedule,
+) -> Result<()> {
+    schedule.reload(&mut registries.schema_registry).await
 }
 
-#[tracing::instrument(skip(producer, settings))]
+#[tracing::instrument(skip(registries))]
 async fn process_changes(
-    producer: &FutureProducer,
-    settings: &Settings,
+    registries: &mut Registries,
     changes: &mut HashSet<PartialNotification>,
-) -> Result<()> {
+) -> Result<HashMap<Uuid, Request>> {
     trace!("processing changes {:#?}", changes);
-    let mut sr_client =
-        rpc::schema_registry::connect(settings.services.schema_registry_url.to_owned()).await?;
+    let Registries {
+        schema_registry: sr_client,
+        edge_registry: er_client,
+    } = registries;
 
     let mut schema_cache: HashMap<Uuid, Vec<FullView>> = HashMap::default();
     let mut relation_cache: HashMap<Uuid, Vec<FullView>> = HashMap::default();
     let mut requests: HashMap<Uuid, Request> = HashMap::default();
+    let mut changed_objects: HashMap<Uuid, HashSet<Uuid>> = HashMap::default();
+    let mut deleted_objects: HashSet<Uuid> = HashSet::default();
 
     for change in changes.drain() {
         match change {
             PartialNotification::CommandServiceNotification(notification) => {
+                if notification.operation == Operation::Delete {
+                    deleted_objects.insert(notification.object_id);
+                }
+                changed_objects
+                    .entry(notification.schema_id)
+                    .or_default()
+                    .insert(notification.object_id);
+
                 // New object or new object version was added
                 let entry = schema_cache.entry(notification.schema_id);
                 let views = match entry {
@@ -202,14 +410,23 @@ async fn process_changes(
 
                 for view in views {
                     let view_id = view.id.parse()?;
-                    requests
+                    let request = requests
                         .entry(view_id)
-                        .or_insert_with(|| Request::new(view_id))
-                        .schemas
-                        .entry(notification.schema_id)
-                        .or_default()
-                        .object_ids
-                        .insert(notification.object_id);
+                        .or_insert_with(|| Request::new(view_id));
+                    match notification.operation {
+                        Operation::Insert | Operation::Patch => {
+                            request
+                                .schemas
+                                .entry(notification.schema_id)
+                                .or_default()
+                                .object_ids
+                                .insert(notification.object_id);
+                        }
+                        // Rows containing deleted object are retracted from the view
+                        Operation::Delete => {
+                            request.deleted_object_ids.insert(notification.object_id);
+                        }
+                    }
                 }
             }
             PartialNotification::EdgeRegistryNotification(notification) => {
@@ -243,40 +460,108 @@ async fn process_changes(
         }
     }
 
+    if !changed_objects.is_empty() {
+        // Changed object may be a child of root objects in views based on other schemas
+        let dependencies = ReverseDependencies::load(
+            sr_client,
+            er_client,
+            changed_objects.keys().copied(),
+            &mut schema_cache,
+        )
+        .await?;
+
+        for (schema_id, object_ids) in changed_objects {
+            for path in dependencies.paths_to_schema(schema_id) {
+                let roots = path.find_roots(er_client, &object_ids).await?;
+                if roots.is_empty() {
+                    continue;
+                }
+
+                trace!(?path, ?roots, "Propagating change to root objects");
+                let request = requests
+                    .entry(path.view_id)
+                    .or_insert_with(|| Request::new(path.view_id));
+                // Rows built from deleted child are retracted, its roots are rebuilt without it
+                request
+                    .deleted_object_ids
+                    .extend(object_ids.intersection(&deleted_objects));
+                request
+                    .schemas
+                    .entry(path.base_schema_id)
+                    .or_default()
+                    .object_ids
+                    .extend(roots);
+            }
+        }
+    }
+
     trace!(?requests, "Requests");
 
-    for request in requests.values() {
-        let payload = serde_json::to_string(&request)?;
-        producer
-            .send(
-                FutureRecord::to(settings.kafka.egest_topic.as_str())
-                    .payload(payload.as_str())
-                    .key(&request.view_id.to_string())
-                    .headers(tracing_utils::kafka::inject_span(OwnedHeaders::new())),
-                Duration::from_secs(5),
+    Ok(requests)
+}
+
+#[tracing::instrument(skip(publisher, settings, requests))]
+async fn send_requests(
+    publisher: &CommonPublisher,
+    settings: &Settings,
+    requests: Vec<ReadyRequest>,
+) -> Result<()> {
+    counter!("cdl.partial-update-engine.requests", requests.len() as u64);
+
+    for ReadyRequest {
+        request,
+        first_change,
+        oldest_timestamp,
+    } in requests
+    {
+        let payload = serde_json::to_vec(&request)?;
+        publisher
+            .publish_message(
+                &settings.materialization.destination,
+                &request.view_id.to_string(),
+                payload,
             )
             .await
-            .map_err(|err| anyhow::anyhow!("Error sending message to Kafka {:?}", err))?;
+            .context("Error sending materialization request")?;
+
+        if let Some(timestamp) = oldest_timestamp {
+            let now = Utc::now().timestamp_millis();
+            histogram!(
+                "cdl.partial-update-engine.lag",
+                (now - timestamp).max(0) as f64 / 1000.0
+            );
+        }
+        histogram!(
+            "cdl.partial-update-engine.buffering-time",
+            first_change.elapsed().as_secs_f64()
+        );
     }
 
     Ok(())
 }
 
-#[tracing::instrument(skip(consumer))]
-async fn acknowledge_messages(
-    offsets: &mut HashMap<i32, i64>,
-    consumer: &StreamConsumer,
-    notification_topic: &str,
+#[tracing::instrument(skip(publisher, settings))]
+async fn send_refreshes(
+    publisher: &CommonPublisher,
+    settings: &Settings,
+    view_ids: Vec<Uuid>,
 ) -> Result<()> {
-    let mut partition_offsets = TopicPartitionList::new();
-    for offset in offsets.iter() {
-        partition_offsets.add_partition_offset(
-            notification_topic,
-            *offset.0,
-            Offset::Offset(*offset.1 + 1),
-        )?;
+    counter!("cdl.partial-update-engine.refreshes", view_ids.len() as u64);
+
+    for view_id in view_ids {
+        tracing::info!(%view_id, "Scheduled refresh of view");
+
+        // Request without schemas materializes every object of the view
+        let payload = serde_json::to_vec(&Request::new(view_id))?;
+        publisher
+            .publish_message(
+                &settings.materialization.destination,
+                &view_id.to_string(),
+                payload,
+            )
+            .await
+            .context("Error sending refresh request")?;
     }
-    rdkafka::consumer::Consumer::commit(consumer, &partition_offsets, CommitMode::Sync)?;
-    offsets.clear();
+
     Ok(())
 }
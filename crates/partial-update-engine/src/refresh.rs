@@ -0,0 +1,259 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use rpc::schema_registry::SchemaRegistryConn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Key in `materializer_options` containing refresh policy of the view
+const REFRESH_KEY: &str = "refresh";
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct RefreshSettings {
+    /// How often refresh policies are reloaded from schema registry
+    pub reload_interval_secs: u64,
+}
+
+impl RefreshSettings {
+    pub fn reload_interval(&self) -> Duration {
+        Duration::from_secs(self.reload_interval_secs)
+    }
+}
+
+/// Refresh policy of the view, eg. `{"refresh": {"interval_secs": 3600}}`
+/// or `{"refresh": {"cron": "0 0 3 * * *"}}`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+enum RefreshPolicy {
+    Interval { interval_secs: u64 },
+    Cron { cron: String },
+}
+
+#[derive(Debug)]
+enum Trigger {
+    Interval(chrono::Duration),
+    Cron(Box<Schedule>),
+}
+
+impl Trigger {
+    fn new(policy: &RefreshPolicy) -> Result<Self> {
+        Ok(match policy {
+            RefreshPolicy::Interval { interval_secs } => {
+                anyhow::ensure!(*interval_secs > 0, "Refresh interval must be positive");
+                Trigger::Interval(chrono::Duration::seconds(*interval_secs as i64))
+            }
+            RefreshPolicy::Cron { cron } => {
+                Trigger::Cron(Box::new(Schedule::from_str(cron).map_err(|err| {
+                    anyhow::anyhow!("Invalid cron expression {}: {}", cron, err)
+                })?))
+            }
+        })
+    }
+
+    fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Trigger::Interval(interval) => Some(time + *interval),
+            Trigger::Cron(schedule) => schedule.after(&time).next(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ScheduledView {
+    policy: RefreshPolicy,
+    trigger: Trigger,
+    next: Option<DateTime<Utc>>,
+}
+
+/// Views with refresh policy, together with the time of their next full refresh
+#[derive(Debug, Default)]
+pub struct RefreshSchedule {
+    views: HashMap<Uuid, ScheduledView>,
+}
+
+impl RefreshSchedule {
+    /// Loads refresh policies of every view, keeping schedule of views whose policy did not change
+    pub async fn reload(&mut self, sr_client: &mut SchemaRegistryConn) -> Result<()> {
+        let schemas = sr_client
+            .get_all_full_schemas(rpc::schema_registry::Empty {})
+            .await?
+            .into_inner()
+            .schemas;
+
+        let views = schemas
+            .into_iter()
+            .flat_map(|schema| schema.views)
+            .filter_map(|view| parse_view(&view.id, &view.materializer_options))
+            .collect();
+
+        self.update(views, Utc::now());
+
+        Ok(())
+    }
+
+    fn update(&mut self, views: Vec<(Uuid, Value)>, now: DateTime<Utc>) {
+        let mut scheduled = HashMap::new();
+        for (view_id, options) in views {
+            let policy = match options.get(REFRESH_KEY) {
+                Some(policy) => policy,
+                None => continue,
+            };
+            let policy: RefreshPolicy = match serde_json::from_value(policy.clone()) {
+                Ok(policy) => policy,
+                Err(err) => {
+                    tracing::warn!("View {} has invalid refresh policy: {}", view_id, err);
+                    continue;
+                }
+            };
+
+            match self.views.remove(&view_id) {
+                Some(view) if view.policy == policy => {
+                    scheduled.insert(view_id, view);
+                }
+                _ => match Trigger::new(&policy) {
+                    Ok(trigger) => {
+                        let next = trigger.next_after(now);
+                        scheduled.insert(
+                            view_id,
+                            ScheduledView {
+                                policy,
+                                trigger,
+                                next,
+                            },
+                        );
+                    }
+                    Err(err) => {
+                        tracing::warn!("View {} has invalid refresh policy: {}", view_id, err);
+                    }
+                },
+            }
+        }
+
+        self.views = scheduled;
+    }
+
+    pub fn deadline(&self) -> Option<DateTime<Utc>> {
+        self.views.values().filter_map(|view| view.next).min()
+    }
+
+    /// Returns views which should be refreshed now, scheduling their next refresh
+    pub fn take_due(&mut self, now: DateTime<Utc>) -> Vec<Uuid> {
+        let mut due = vec![];
+        for (view_id, view) in self.views.iter_mut() {
+            if matches!(view.next, Some(next) if next <= now) {
+                view.next = view.trigger.next_after(now);
+                due.push(*view_id);
+            }
+        }
+        due
+    }
+}
+
+/// Invalid view is skipped, so it doesn't stop refreshes of other views
+fn parse_view(id: &str, materializer_options: &str) -> Option<(Uuid, Value)> {
+    let view_id = match id.parse() {
+        Ok(view_id) => view_id,
+        Err(err) => {
+            tracing::warn!("View {} has invalid id: {}", id, err);
+            return None;
+        }
+    };
+    match serde_json::from_str(materializer_options) {
+        Ok(options) => Some((view_id, options)),
+        Err(err) => {
+            tracing::warn!("View {} has invalid materializer options: {}", id, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn view(id: u128, options: Value) -> (Uuid, Value) {
+        (Uuid::from_u128(id), options)
+    }
+
+    #[test]
+    fn schedules_views_with_refresh_policy() {
+        let start = Utc.ymd(2021, 6, 1).and_hms(12, 30, 0);
+        let mut schedule = RefreshSchedule::default();
+        schedule.update(
+            vec![
+                view(1, json!({ "refresh": { "interval_secs": 60 } })),
+                view(2, json!({ "refresh": { "cron": "0 0 13 * * *" } })),
+                view(3, json!({ "table": "view" })),
+                view(4, json!({ "refresh": { "cron": "invalid" } })),
+            ],
+            start,
+        );
+
+        assert_eq!(
+            schedule.deadline(),
+            Some(start + chrono::Duration::seconds(60))
+        );
+        assert!(schedule.take_due(start).is_empty());
+
+        let due = schedule.take_due(start + chrono::Duration::seconds(60));
+        assert_eq!(due, vec![Uuid::from_u128(1)]);
+
+        let mut due = schedule.take_due(Utc.ymd(2021, 6, 1).and_hms(13, 0, 0));
+        due.sort();
+        assert_eq!(due, vec![Uuid::from_u128(1), Uuid::from_u128(2)]);
+        assert_eq!(
+            schedule.deadline(),
+            Some(Utc.ymd(2021, 6, 1).and_hms(13, 1, 0))
+        );
+    }
+
+    #[test]
+    fn skips_invalid_views() {
+        assert_eq!(
+            parse_view("00000000-0000-0000-0000-000000000001", "{}"),
+            Some((Uuid::from_u128(1), json!({})))
+        );
+        assert_eq!(parse_view("invalid", "{}"), None);
+        assert_eq!(
+            parse_view("00000000-0000-0000-0000-000000000001", "{invalid"),
+            None
+        );
+    }
+
+    #[test]
+    fn keeps_schedule_of_unchanged_policy() {
+        let start = Utc.ymd(2021, 6, 1).and_hms(12, 0, 0);
+        let mut schedule = RefreshSchedule::default();
+        schedule.update(
+            vec![view(1, json!({ "refresh": { "interval_secs": 60 } }))],
+            start,
+        );
+
+        schedule.update(
+            vec![view(1, json!({ "refresh": { "interval_secs": 60 } }))],
+            start + chrono::Duration::seconds(30),
+        );
+        assert_eq!(
+            schedule.deadline(),
+            Some(start + chrono::Duration::seconds(60))
+        );
+
+        schedule.update(
+            vec![view(1, json!({ "refresh": { "interval_secs": 120 } }))],
+            start + chrono::Duration::seconds(30),
+        );
+        assert_eq!(
+            schedule.deadline(),
+            Some(start + chrono::Duration::seconds(150))
+        );
+
+        schedule.update(vec![], start);
+        assert!(schedule.deadline().is_none());
+    }
+}
@@ -1,11 +1,12 @@
+use cdl_dto::{RequestError, ResponseError};
 use rpc::error::ClientError;
 use rpc::schema_registry::types::SchemaType;
+use rpc::tonic::Code;
 use warp::{hyper::StatusCode, reject::Reject, Rejection};
 
 #[derive(Debug)]
 pub enum Error {
     ClientError(ClientError),
-    JsonError(serde_json::Error),
     SingleQueryMissingValue,
     RawQueryMissingValue,
     WrongValueFormat,
@@ -13,15 +14,39 @@ pub enum Error {
     ExpectedSchemaType(SchemaType),
     InvalidRepository(String),
     SchemaFetchError(anyhow::Error),
+    InvalidPageQuery(ResponseError),
+    InvalidObjectPage(RequestError),
 }
 
 impl Reject for Error {}
 
+impl Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Error::ExpectedSchemaType(_)
+            | Error::InvalidRepository(_)
+            | Error::InvalidPageQuery(_) => StatusCode::BAD_REQUEST,
+            // Query service rejects invalid queries and queries its repository can't handle
+            Error::ClientError(ClientError::QueryError { source }) => match source.code() {
+                Code::InvalidArgument => StatusCode::BAD_REQUEST,
+                Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            Error::ClientError(_)
+            | Error::SingleQueryMissingValue
+            | Error::RawQueryMissingValue
+            | Error::WrongValueFormat
+            | Error::InvalidSchemaType(_)
+            | Error::SchemaFetchError(_)
+            | Error::InvalidObjectPage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 pub fn recover(rejection: Rejection) -> Result<impl warp::Reply, Rejection> {
     if let Some(error) = rejection.find::<Error>() {
         let message = match error {
             Error::ClientError(err) => err.to_string(),
-            Error::JsonError(err) => format!("Unable to serialize JSON: {}", err),
             Error::SingleQueryMissingValue => "Value not returned from query".to_owned(),
             Error::WrongValueFormat => "Value incorrectly formatted".to_owned(),
             Error::RawQueryMissingValue => "Value not returned from query".to_owned(),
@@ -34,18 +59,13 @@ pub fn recover(rejection: Rejection) -> Result<impl warp::Reply, Rejection> {
                 repository_id
             ),
             Error::SchemaFetchError(error) => format!("Failed to fetch schema: {}", error),
+            Error::InvalidPageQuery(error) => format!("Invalid query: {}", error),
+            Error::InvalidObjectPage(error) => {
+                format!("Query service returned invalid page: {}", error)
+            }
         };
 
-        let code = match error {
-            Error::ExpectedSchemaType(_) | Error::InvalidRepository(_) => StatusCode::BAD_REQUEST,
-            Error::ClientError(_)
-            | Error::JsonError(_)
-            | Error::SingleQueryMissingValue
-            | Error::RawQueryMissingValue
-            | Error::WrongValueFormat
-            | Error::InvalidSchemaType(_)
-            | Error::SchemaFetchError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let code = error.status_code();
 
         Ok(warp::reply::with_status(
             warp::reply::json(&serde_json::json!({ "message": message })),
@@ -55,3 +75,29 @@ pub fn recover(rejection: Rejection) -> Result<impl warp::Reply, Rejection> {
         Err(rejection)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rpc::tonic::Status;
+
+    fn query_error(status: Status) -> Error {
+        Error::ClientError(ClientError::QueryError { source: status })
+    }
+
+    #[test]
+    fn maps_query_service_status_to_http_status() {
+        assert_eq!(
+            query_error(Status::invalid_argument("Invalid cursor")).status_code(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            query_error(Status::unimplemented("Paging is not supported")).status_code(),
+            StatusCode::NOT_IMPLEMENTED
+        );
+        assert_eq!(
+            query_error(Status::internal("Connection lost")).status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
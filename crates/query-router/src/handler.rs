@@ -1,17 +1,55 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use std::{collections::HashMap, sync::Arc};
+use serde_json::value::RawValue;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use uuid::Uuid;
-use warp::hyper::header::CONTENT_TYPE;
+use warp::hyper::header::{HeaderValue, CONTENT_TYPE};
+use warp::hyper::{Body as HttpBody, Response};
 
 use crate::error::Error;
 use crate::schema::{SchemaCache, SchemaMetadata};
-use futures_util::TryStreamExt;
+use cdl_dto::query::{ObjectPage, SchemaQuery};
+use futures_util::stream::{self, BoxStream};
+use futures_util::{future, StreamExt, TryStreamExt};
+use rpc::query_service::ObjectStream;
 use rpc::schema_registry::types::SchemaType;
 use rpc::{query_service, query_service_ts};
 use settings_utils::RepositoryStaticRouting;
 
 const APPLICATION_JSON: &str = "application/json";
+const APPLICATION_NDJSON: &str = "application/x-ndjson";
+
+/// Format of responses containing many objects, chosen by `Accept` header of the request
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamFormat {
+    /// JSON object keyed by object ids
+    Json,
+    /// Every object in separate line, as `{"objectId": ..., "payload": ...}`
+    NdJson,
+}
+
+impl StreamFormat {
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        match accept {
+            Some(accept) if accept.contains(APPLICATION_NDJSON) => StreamFormat::NdJson,
+            _ => StreamFormat::Json,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            StreamFormat::Json => APPLICATION_JSON,
+            StreamFormat::NdJson => APPLICATION_NDJSON,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectLine<'a> {
+    object_id: &'a str,
+    payload: &'a RawValue,
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
@@ -90,6 +128,7 @@ pub async fn query_multiple(
     object_ids: String,
     schema_id: Uuid,
     repository_id: Option<String>,
+    accept: Option<String>,
     cache: Arc<SchemaCache>,
     routing: Arc<HashMap<String, RepositoryStaticRouting>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
@@ -98,39 +137,30 @@ pub async fn query_multiple(
         schema_type,
     } = get_routing_info(schema_id, repository_id, cache, routing).await?;
 
-    let object_ids = object_ids.split(',').map(str::to_owned).collect();
+    let object_ids = unique_object_ids(&object_ids);
 
-    let values = match schema_type {
+    match schema_type {
         SchemaType::DocumentStorage => {
-            let values: HashMap<_, _> =
-                rpc::query_service::query_multiple(object_ids, query_address.clone())
-                    .await
-                    .map_err(Error::ClientError)?
-                    .map_ok(|o| (o.object_id, o.payload))
-                    .try_collect()
-                    .await
-                    .map_err(Error::ClientError)?;
+            let objects = rpc::query_service::query_multiple(object_ids, query_address.clone())
+                .await
+                .map_err(Error::ClientError)?;
 
-            values
-        }
-        _ => {
-            return Err(warp::Rejection::from(Error::ExpectedSchemaType(
-                SchemaType::DocumentStorage,
-            )));
+            Ok(stream_objects(
+                objects,
+                StreamFormat::from_accept(accept.as_deref()),
+            ))
         }
-    };
-
-    Ok(warp::reply::with_header(
-        serde_json::to_vec(&byte_map_to_json_map(values)?).map_err(Error::JsonError)?,
-        CONTENT_TYPE,
-        APPLICATION_JSON,
-    ))
+        _ => Err(warp::Rejection::from(Error::ExpectedSchemaType(
+            SchemaType::DocumentStorage,
+        ))),
+    }
 }
 
 #[tracing::instrument(skip(cache))]
 pub async fn query_by_schema(
     schema_id: Uuid,
     repository_id: Option<String>,
+    accept: Option<String>,
     cache: Arc<SchemaCache>,
     routing: Arc<HashMap<String, RepositoryStaticRouting>>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
@@ -141,19 +171,14 @@ pub async fn query_by_schema(
 
     match &schema_type {
         SchemaType::DocumentStorage => {
-            let values: HashMap<_, _> =
+            let objects =
                 rpc::query_service::query_by_schema(schema_id.to_string(), query_address.clone())
-                    .await
-                    .map_err(Error::ClientError)?
-                    .map_ok(|o| (o.object_id, o.payload))
-                    .try_collect()
                     .await
                     .map_err(Error::ClientError)?;
 
-            Ok(warp::reply::with_header(
-                serde_json::to_vec(&byte_map_to_json_map(values)?).map_err(Error::JsonError)?,
-                CONTENT_TYPE,
-                APPLICATION_JSON,
+            Ok(stream_objects(
+                objects,
+                StreamFormat::from_accept(accept.as_deref()),
             ))
         }
         SchemaType::Timeseries => {
@@ -163,12 +188,43 @@ pub async fn query_by_schema(
             )
             .await
             .map_err(Error::ClientError)?;
-            Ok(warp::reply::with_header(
-                timeseries.into_bytes(),
-                CONTENT_TYPE,
-                APPLICATION_JSON,
+
+            let mut response = Response::new(HttpBody::from(timeseries));
+            response
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(APPLICATION_JSON));
+            Ok(response)
+        }
+    }
+}
+
+#[tracing::instrument(skip(cache))]
+pub async fn query_by_schema_paged(
+    schema_id: Uuid,
+    repository_id: Option<String>,
+    cache: Arc<SchemaCache>,
+    routing: Arc<HashMap<String, RepositoryStaticRouting>>,
+    query: SchemaQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let SchemaMetadata {
+        query_address,
+        schema_type,
+    } = get_routing_info(schema_id, repository_id, cache, routing).await?;
+
+    match schema_type {
+        SchemaType::DocumentStorage => {
+            let query = query.into_rpc(schema_id).map_err(Error::InvalidPageQuery)?;
+            let page = rpc::query_service::query_by_schema_paged(query, query_address)
+                .await
+                .map_err(Error::ClientError)?;
+
+            Ok(warp::reply::json(
+                &ObjectPage::from_rpc(page).map_err(Error::InvalidObjectPage)?,
             ))
         }
+        _ => Err(warp::Rejection::from(Error::ExpectedSchemaType(
+            SchemaType::DocumentStorage,
+        ))),
     }
 }
 
@@ -210,17 +266,64 @@ pub async fn query_raw(
     ))
 }
 
-fn byte_map_to_json_map(map: HashMap<String, Vec<u8>>) -> Result<HashMap<String, Value>, Error> {
-    map.into_iter()
-        .map(|(object_id, value)| {
-            Ok((
-                object_id,
-                serde_json::from_slice(&value).map_err(Error::JsonError)?,
-            ))
-        })
+/// Objects are streamed as keys of JSON object, so every id is requested only once
+fn unique_object_ids(object_ids: &str) -> Vec<String> {
+    let mut requested = HashSet::new();
+    object_ids
+        .split(',')
+        .filter(|object_id| requested.insert(*object_id))
+        .map(str::to_owned)
         .collect()
 }
 
+/// Writes objects to the response as they're received from query service, without collecting them first.
+/// Next object is requested only when the previous one was sent, so slow clients don't cause objects to pile up in memory.
+/// Once response has started, failure can only be reported by aborting it.
+fn stream_objects(objects: ObjectStream, format: StreamFormat) -> Response<HttpBody> {
+    let body: BoxStream<'static, anyhow::Result<Vec<u8>>> = match format {
+        StreamFormat::Json => {
+            let entries = objects
+                .enumerate()
+                .map(|(index, object)| -> anyhow::Result<Vec<u8>> {
+                    let object = object?;
+                    let payload: &RawValue = serde_json::from_slice(&object.payload)?;
+
+                    let mut entry = if index == 0 { vec![] } else { vec![b','] };
+                    serde_json::to_writer(&mut entry, &object.object_id)?;
+                    entry.push(b':');
+                    entry.extend_from_slice(payload.get().as_bytes());
+                    Ok(entry)
+                });
+
+            stream::once(future::ok(b"{".to_vec()))
+                .chain(entries)
+                .chain(stream::once(future::ok(b"}".to_vec())))
+                .boxed()
+        }
+        StreamFormat::NdJson => objects
+            .map(|object| -> anyhow::Result<Vec<u8>> {
+                let object = object?;
+
+                let mut line = serde_json::to_vec(&ObjectLine {
+                    object_id: &object.object_id,
+                    payload: serde_json::from_slice(&object.payload)?,
+                })?;
+                line.push(b'\n');
+                Ok(line)
+            })
+            .boxed(),
+    };
+
+    let body = body.inspect_err(|err| tracing::error!("Failed to stream objects: {:#}", err));
+
+    let mut response = Response::new(HttpBody::wrap_stream(body));
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(format.content_type()),
+    );
+    response
+}
+
 async fn get_routing_info(
     schema_id: Uuid,
     repository_id: Option<String>,
@@ -246,3 +349,67 @@ async fn get_routing_info(
 
     Ok(metadata)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rpc::error::ClientError;
+    use rpc::query_service::Object;
+
+    fn objects(objects: &[(&str, &str)]) -> ObjectStream {
+        let objects: Vec<Result<Object, ClientError>> = objects
+            .iter()
+            .map(|(object_id, payload)| {
+                Ok(Object {
+                    object_id: object_id.to_string(),
+                    payload: payload.as_bytes().to_vec(),
+                })
+            })
+            .collect();
+        Box::pin(stream::iter(objects))
+    }
+
+    async fn body(response: Response<HttpBody>) -> String {
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn requests_every_object_once() {
+        assert_eq!(unique_object_ids("a,b,a,c,b"), vec!["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn streams_json_object_keyed_by_object_ids() {
+        let response = stream_objects(
+            objects(&[("a", r#"{"x": 1}"#), ("b", "[2]")]),
+            StreamFormat::Json,
+        );
+
+        assert_eq!(response.headers()[CONTENT_TYPE], APPLICATION_JSON);
+        assert_eq!(body(response).await, r#"{"a":{"x": 1},"b":[2]}"#);
+    }
+
+    #[tokio::test]
+    async fn streams_empty_json_object() {
+        let response = stream_objects(objects(&[]), StreamFormat::Json);
+
+        assert_eq!(body(response).await, "{}");
+    }
+
+    #[tokio::test]
+    async fn streams_object_per_line() {
+        let response = stream_objects(
+            objects(&[("a", r#"{"x": 1}"#), ("b", "[2]")]),
+            StreamFormat::NdJson,
+        );
+
+        assert_eq!(response.headers()[CONTENT_TYPE], APPLICATION_NDJSON);
+        assert_eq!(
+            body(response).await,
+            "{\"objectId\":\"a\",\"payload\":{\"x\": 1}}\n{\"objectId\":\"b\",\"payload\":[2]}\n"
+        );
+    }
+}
@@ -60,6 +60,7 @@ async fn main() -> anyhow::Result<()> {
 
     let schema_id_filter = warp::header::header::<Uuid>("SCHEMA_ID");
     let repository_id_filter = warp::header::optional::<String>("REPOSITORY_ID");
+    let accept_filter = warp::header::optional::<String>("accept");
     let body_filter = warp::body::content_length_limit(1024 * 32).and(warp::body::json());
 
     let single_route = warp::path!("single" / Uuid)
@@ -73,6 +74,7 @@ async fn main() -> anyhow::Result<()> {
     let multiple_route = warp::path!("multiple" / String)
         .and(schema_id_filter)
         .and(repository_id_filter)
+        .and(accept_filter)
         .and(cache_filter.clone())
         .and(routing_filter.clone())
         .and_then(handler::query_multiple);
@@ -80,10 +82,19 @@ async fn main() -> anyhow::Result<()> {
     let schema_route = warp::path!("schema")
         .and(schema_id_filter)
         .and(repository_id_filter)
+        .and(accept_filter)
         .and(cache_filter.clone())
         .and(routing_filter.clone())
         .and_then(handler::query_by_schema);
 
+    let schema_page_route = warp::path!("schema" / "page")
+        .and(schema_id_filter)
+        .and(repository_id_filter)
+        .and(cache_filter.clone())
+        .and(routing_filter.clone())
+        .and(warp::body::content_length_limit(1024 * 32).and(warp::body::json()))
+        .and_then(handler::query_by_schema_paged);
+
     let raw_route = warp::path!("raw")
         .and(schema_id_filter)
         .and(repository_id_filter)
@@ -93,7 +104,7 @@ async fn main() -> anyhow::Result<()> {
         .and_then(handler::query_raw);
 
     let routes = warp::post()
-        .and(single_route.or(raw_route))
+        .and(single_route.or(raw_route).or(schema_page_route))
         .or(warp::get().and(multiple_route.or(schema_route)));
 
     tracing_utils::http::serve(routes, ([0, 0, 0, 0], settings.input_port)).await;
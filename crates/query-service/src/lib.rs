@@ -1 +1,4 @@
 pub mod psql;
+pub mod rocksdb;
+
+mod schema_query;
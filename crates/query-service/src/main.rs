@@ -8,7 +8,11 @@ use tonic::transport::Server;
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
-    postgres: PostgresSettings,
+    #[serde(default)]
+    repository_kind: RepositoryKind,
+
+    postgres: Option<PostgresSettings>,
+    rocksdb: Option<RocksDbSettings>,
     input_port: u16,
 
     monitoring: MonitoringSettings,
@@ -17,6 +21,21 @@ pub struct Settings {
     log: LogSettings,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RepositoryKind {
+    Postgres,
+    /// Embedded document storage, written by command service
+    #[serde(rename = "rocksdb")]
+    RocksDb,
+}
+
+impl Default for RepositoryKind {
+    fn default() -> Self {
+        RepositoryKind::Postgres
+    }
+}
+
 async fn spawn_server<Q: QueryService>(service: Q, port: u16) -> anyhow::Result<()> {
     let addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port);
 
@@ -42,9 +61,25 @@ async fn main() -> anyhow::Result<()> {
 
     metrics::serve(&settings.monitoring);
 
-    spawn_server(
-        query_service::psql::PsqlQuery::load(settings.postgres).await?,
-        settings.input_port,
-    )
-    .await
+    match (
+        settings.postgres,
+        settings.rocksdb,
+        settings.repository_kind,
+    ) {
+        (Some(postgres), _, RepositoryKind::Postgres) => {
+            spawn_server(
+                query_service::psql::PsqlQuery::load(postgres).await?,
+                settings.input_port,
+            )
+            .await
+        }
+        (_, Some(rocksdb), RepositoryKind::RocksDb) => {
+            spawn_server(
+                query_service::rocksdb::RocksDbQuery::load(rocksdb)?,
+                settings.input_port,
+            )
+            .await
+        }
+        _ => anyhow::bail!("Unsupported repository specification"),
+    }
 }
@@ -1,3 +1,4 @@
+use crate::schema_query;
 use anyhow::Context;
 use bb8_postgres::bb8::{Pool, PooledConnection};
 use bb8_postgres::tokio_postgres::config::Config as PgConfig;
@@ -6,7 +7,10 @@ use bb8_postgres::PostgresConnectionManager;
 use futures_util::TryStreamExt;
 use metrics_utils::{self as metrics, counter};
 use rpc::query_service::query_service_server::QueryService;
-use rpc::query_service::{Object, ObjectIds, ObjectStream, RawStatement, SchemaId, ValueBytes};
+use rpc::query_service::{
+    Cursor, Object, ObjectIds, ObjectPage, ObjectStream, RawStatement, SchemaId, SchemaQuery,
+    ValueBytes,
+};
 use serde_json::Value;
 use settings_utils::PostgresSettings;
 use tonic::{Request, Response, Status};
@@ -183,6 +187,54 @@ impl QueryService for PsqlQuery {
         Ok(tonic::Response::new(stream))
     }
 
+    #[tracing::instrument(skip(self))]
+    async fn query_by_schema_paged(
+        &self,
+        request: Request<SchemaQuery>,
+    ) -> Result<Response<ObjectPage>, Status> {
+        let request = request.into_inner();
+
+        counter!("cdl.query-service.query-by-schema-paged.psql", 1);
+
+        let statement = schema_query::build(&request, &self.schema)?;
+        let params: Vec<&(dyn ToSql + Sync)> = statement
+            .params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+            .collect();
+
+        let rows: Vec<_> = self
+            .make_query(&statement.text, &params)
+            .await?
+            .try_collect()
+            .await
+            .map_err(|err| Status::internal(format!("Unable to query data: {}", err)))?;
+
+        // Full page means there may be more objects, client continues after the last one
+        let next = match rows.last() {
+            Some(row) if rows.len() as u64 == schema_query::page_size(request.limit) => {
+                Some(Cursor {
+                    object_id: row.get::<usize, Uuid>(0).to_string(),
+                    value: if request.order_by.is_some() {
+                        row.get::<usize, Value>(2).to_string()
+                    } else {
+                        String::new()
+                    },
+                })
+            }
+            _ => None,
+        };
+        let objects = rows
+            .iter()
+            .map(|row| Object {
+                object_id: row.get::<usize, Uuid>(0).to_string(),
+                payload: row.get::<usize, Value>(1).to_string().into_bytes(),
+            })
+            .collect();
+
+        Ok(tonic::Response::new(ObjectPage { objects, next }))
+    }
+
     #[tracing::instrument(skip(self))]
     async fn query_raw(
         &self,
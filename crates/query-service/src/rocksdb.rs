@@ -0,0 +1,169 @@
+use kv_utils::DocumentStore;
+use metrics_utils::{self as metrics, counter};
+use rpc::query_service::query_service_server::QueryService;
+use rpc::query_service::{
+    Object, ObjectIds, ObjectPage, ObjectStream, RawStatement, SchemaId, SchemaQuery, ValueBytes,
+};
+use settings_utils::RocksDbSettings;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+/// Queries embedded document storage, written by command service
+pub struct RocksDbQuery {
+    replica: Arc<Replica>,
+}
+
+struct Replica {
+    store: DocumentStore,
+    catch_up_interval: Duration,
+    last_catch_up: Mutex<Option<Instant>>,
+}
+
+impl RocksDbQuery {
+    pub fn load(settings: RocksDbSettings) -> anyhow::Result<Self> {
+        let secondary_path = settings.secondary_path.ok_or_else(|| {
+            anyhow::anyhow!("Query service requires `secondary_path` of the document store")
+        })?;
+
+        Ok(Self {
+            replica: Arc::new(Replica {
+                store: DocumentStore::open_secondary(settings.path, secondary_path)?,
+                catch_up_interval: Duration::from_millis(settings.catch_up_interval_ms),
+                last_catch_up: Mutex::new(None),
+            }),
+        })
+    }
+
+    /// Runs blocking RocksDB reads outside of async workers
+    async fn read<T: Send + 'static>(
+        &self,
+        read: impl FnOnce(&Replica) -> Result<T, Status> + Send + 'static,
+    ) -> Result<T, Status> {
+        let replica = self.replica.clone();
+        tokio::task::spawn_blocking(move || {
+            replica.catch_up()?;
+            read(&*replica)
+        })
+        .await
+        .map_err(|err| Status::internal(format!("Query task failed: {}", err)))?
+    }
+}
+
+impl Replica {
+    /// Replica sees writes of command service only after catching up with it,
+    /// which is done at most once per `catch_up_interval_ms`
+    fn catch_up(&self) -> Result<(), Status> {
+        let mut last_catch_up = self
+            .last_catch_up
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if matches!(*last_catch_up, Some(last) if last.elapsed() < self.catch_up_interval) {
+            return Ok(());
+        }
+
+        self.store
+            .catch_up()
+            .map_err(|err| Status::internal(format!("{:?}", err)))?;
+        *last_catch_up = Some(Instant::now());
+
+        Ok(())
+    }
+
+    fn latest_objects(
+        &self,
+        object_ids: impl IntoIterator<Item = Uuid>,
+    ) -> Result<Vec<Result<Object, Status>>, Status> {
+        let mut objects = vec![];
+        for object_id in object_ids {
+            let stored = self
+                .store
+                .latest(object_id)
+                .map_err(|err| Status::internal(format!("Unable to query data: {}", err)))?;
+            if let Some(stored) = stored {
+                objects.push(Ok(Object {
+                    object_id: object_id.to_string(),
+                    payload: stored.payload.to_string().into_bytes(),
+                }));
+            }
+        }
+
+        Ok(objects)
+    }
+}
+
+#[tonic::async_trait]
+impl QueryService for RocksDbQuery {
+    type QueryMultipleStream = ObjectStream<tonic::Status>;
+
+    #[tracing::instrument(skip(self))]
+    async fn query_multiple(
+        &self,
+        request: Request<ObjectIds>,
+    ) -> Result<Response<Self::QueryMultipleStream>, Status> {
+        let request = request.into_inner();
+
+        counter!("cdl.query-service.query-multiple.rocksdb", 1);
+
+        let object_ids: Vec<Uuid> = request
+            .object_ids
+            .into_iter()
+            .map(|id| id.parse::<Uuid>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        let objects = self
+            .read(move |replica| replica.latest_objects(object_ids))
+            .await?;
+
+        Ok(tonic::Response::new(Box::pin(futures_util::stream::iter(
+            objects,
+        ))))
+    }
+
+    type QueryBySchemaStream = ObjectStream<tonic::Status>;
+
+    #[tracing::instrument(skip(self))]
+    async fn query_by_schema(
+        &self,
+        request: Request<SchemaId>,
+    ) -> Result<Response<Self::QueryBySchemaStream>, Status> {
+        let request = request.into_inner();
+
+        counter!("cdl.query-service.query-by-schema.rocksdb", 1);
+
+        let schema_id = request
+            .schema_id
+            .parse::<Uuid>()
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        let objects = self
+            .read(move |replica| replica.latest_objects(replica.store.objects_of_schema(schema_id)))
+            .await?;
+
+        Ok(tonic::Response::new(Box::pin(futures_util::stream::iter(
+            objects,
+        ))))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn query_by_schema_paged(
+        &self,
+        _request: Request<SchemaQuery>,
+    ) -> Result<Response<ObjectPage>, Status> {
+        Err(Status::unimplemented(
+            "Paged queries are not supported by embedded document storage",
+        ))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn query_raw(
+        &self,
+        _request: Request<RawStatement>,
+    ) -> Result<Response<ValueBytes>, Status> {
+        Err(Status::unimplemented(
+            "Raw statements are not supported by embedded document storage",
+        ))
+    }
+}
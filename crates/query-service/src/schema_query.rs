@@ -0,0 +1,427 @@
+//! Translation of paged schema queries into SQL over JSONB payloads.
+//! Paths and values are always passed as parameters, never formatted into the statement.
+
+use bb8_postgres::tokio_postgres::types::ToSql;
+use rpc::query_service::{predicate::Predicate as Kind, Predicate, SchemaQuery};
+use serde_json::Value;
+use tonic::Status;
+use uuid::Uuid;
+
+pub type Param = Box<dyn ToSql + Sync + Send>;
+
+/// Page size used when the query has no limit
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+/// Larger limits are reduced to this size, so a single response stays bounded
+pub const MAX_PAGE_SIZE: u64 = 1000;
+
+/// Number of objects returned in the page for the requested limit
+pub fn page_size(limit: u64) -> u64 {
+    match limit {
+        0 => DEFAULT_PAGE_SIZE,
+        limit => limit.min(MAX_PAGE_SIZE),
+    }
+}
+
+/// Statement selecting `object_id`, `payload` and value of the ordering field of every object in the page
+pub struct Statement {
+    pub text: String,
+    pub params: Vec<Param>,
+}
+
+pub fn build(query: &SchemaQuery, schema: &str) -> Result<Statement, Status> {
+    let mut builder = Builder::default();
+    let schema_id = builder.param(parse_uuid(&query.schema_id)?);
+
+    // Missing fields are ordered as `null`, which precedes every other JSON value
+    let key = query
+        .order_by
+        .as_ref()
+        .map(|order_by| {
+            Ok::<_, Status>(format!(
+                "COALESCE(payload #> {}, 'null'::jsonb)",
+                builder.path(&order_by.path)?
+            ))
+        })
+        .transpose()?;
+    let descending = query
+        .order_by
+        .as_ref()
+        .map(|order_by| order_by.descending)
+        .unwrap_or_default();
+
+    let mut conditions = vec![];
+    if let Some(filter) = &query.filter {
+        conditions.push(builder.predicate(filter)?);
+    }
+    if let Some(after) = &query.after {
+        let object_id = builder.param(parse_uuid(&after.object_id)?);
+        let operator = if descending { "<" } else { ">" };
+        conditions.push(match &key {
+            Some(key) => {
+                if after.value.is_empty() {
+                    return Err(Status::invalid_argument(
+                        "cursor value is required when ordering by field",
+                    ));
+                }
+                let value = builder.value(&after.value)?;
+                format!(
+                    "({}, object_id) {} ({}, {}::uuid)",
+                    key, operator, value, object_id
+                )
+            }
+            None => format!("object_id {} {}::uuid", operator, object_id),
+        });
+    }
+
+    let mut text = format!(
+        "SELECT object_id, payload, {} \
+         FROM (\
+             SELECT DISTINCT ON (object_id) object_id, payload::jsonb AS payload \
+             FROM {}.data \
+             WHERE schema_id = {} \
+             ORDER BY object_id, version DESC\
+         ) latest",
+        key.as_deref().unwrap_or("'null'::jsonb"),
+        schema,
+        schema_id
+    );
+    if !conditions.is_empty() {
+        text.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+    }
+    let direction = if descending { " DESC" } else { "" };
+    match &key {
+        Some(key) => text.push_str(&format!(
+            " ORDER BY {}{}, object_id{}",
+            key, direction, direction
+        )),
+        None => text.push_str(&format!(" ORDER BY object_id{}", direction)),
+    }
+    let limit = builder.param(page_size(query.limit) as i64);
+    text.push_str(&format!(" LIMIT {}", limit));
+
+    Ok(Statement {
+        text,
+        params: builder.params,
+    })
+}
+
+#[derive(Default)]
+struct Builder {
+    params: Vec<Param>,
+}
+
+impl Builder {
+    fn param(&mut self, value: impl ToSql + Sync + Send + 'static) -> String {
+        self.params.push(Box::new(value));
+        format!("${}", self.params.len())
+    }
+
+    fn path(&mut self, pointer: &str) -> Result<String, Status> {
+        Ok(format!("{}::text[]", self.param(parse_pointer(pointer)?)))
+    }
+
+    fn field(&mut self, pointer: &str) -> Result<String, Status> {
+        Ok(format!("(payload #> {})", self.path(pointer)?))
+    }
+
+    fn value(&mut self, value: &str) -> Result<String, Status> {
+        Ok(format!("{}::jsonb", self.param(parse_json(value)?)))
+    }
+
+    /// Every comparison is wrapped in `COALESCE`, so missing fields make it false instead of `NULL`,
+    /// which would be kept by negation
+    fn predicate(&mut self, predicate: &Predicate) -> Result<String, Status> {
+        let predicate = predicate
+            .predicate
+            .as_ref()
+            .ok_or_else(|| Status::invalid_argument("Empty predicate"))?;
+
+        Ok(match predicate {
+            Kind::And(list) => self.join(&list.predicates, "AND", "TRUE")?,
+            Kind::Or(list) => self.join(&list.predicates, "OR", "FALSE")?,
+            Kind::Not(predicate) => format!("(NOT {})", self.predicate(predicate)?),
+            Kind::Equals(equals) => {
+                let field = self.field(&equals.path)?;
+                format!(
+                    "COALESCE({} = {}, FALSE)",
+                    field,
+                    self.value(&equals.value)?
+                )
+            }
+            Kind::Range(range) => {
+                if range.lower.is_none() && range.upper.is_none() {
+                    return Err(Status::invalid_argument(
+                        "Range predicate requires at least one bound",
+                    ));
+                }
+                let field = self.field(&range.path)?;
+                let mut conditions = vec![];
+                for (bound, operator, inclusive_operator) in
+                    [(&range.lower, ">", ">="), (&range.upper, "<", "<=")].iter()
+                {
+                    if let Some(bound) = bound {
+                        let value = self.value(&bound.value)?;
+                        let operator = if bound.inclusive {
+                            inclusive_operator
+                        } else {
+                            operator
+                        };
+                        // JSONB values of different types are ordered by type, not compared
+                        conditions
+                            .push(format!("jsonb_typeof({}) = jsonb_typeof({})", field, value));
+                        conditions.push(format!("{} {} {}", field, operator, value));
+                    }
+                }
+                format!("COALESCE({}, FALSE)", conditions.join(" AND "))
+            }
+            Kind::InList(in_list) => {
+                let field = self.field(&in_list.path)?;
+                let values = in_list
+                    .values
+                    .iter()
+                    .map(|value| parse_json(value))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let values = self.param(Value::Array(values));
+                format!(
+                    "COALESCE({} IN (SELECT jsonb_array_elements({}::jsonb)), FALSE)",
+                    field, values
+                )
+            }
+            Kind::Exists(exists) => format!(
+                "COALESCE(jsonb_typeof({}) <> 'null', FALSE)",
+                self.field(&exists.path)?
+            ),
+        })
+    }
+
+    fn join(
+        &mut self,
+        predicates: &[Predicate],
+        operator: &str,
+        empty: &str,
+    ) -> Result<String, Status> {
+        if predicates.is_empty() {
+            return Ok(empty.to_owned());
+        }
+
+        let predicates = predicates
+            .iter()
+            .map(|predicate| self.predicate(predicate))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(format!(
+            "({})",
+            predicates.join(format!(" {} ", operator).as_str())
+        ))
+    }
+}
+
+/// Splits JSON pointer into keys, as expected by `#>` operator
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, Status> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+    if !pointer.starts_with('/') {
+        return Err(Status::invalid_argument(format!(
+            "Path `{}` is not a JSON pointer",
+            pointer
+        )));
+    }
+
+    Ok(pointer[1..]
+        .split('/')
+        .map(|key| key.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn parse_json(value: &str) -> Result<Value, Status> {
+    serde_json::from_str(value)
+        .map_err(|err| Status::invalid_argument(format!("Invalid JSON value: {}", err)))
+}
+
+fn parse_uuid(id: &str) -> Result<Uuid, Status> {
+    id.parse()
+        .map_err(|err: uuid::Error| Status::invalid_argument(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rpc::query_service::{
+        Bound, Cursor, EqualsPredicate, ExistsPredicate, InPredicate, Ordering, PredicateList,
+        RangePredicate,
+    };
+
+    const SCHEMA_ID: &str = "00000000-0000-0000-0000-000000000001";
+
+    fn predicate(kind: Kind) -> Predicate {
+        Predicate {
+            predicate: Some(kind),
+        }
+    }
+
+    fn query(filter: Option<Predicate>) -> SchemaQuery {
+        SchemaQuery {
+            schema_id: SCHEMA_ID.to_owned(),
+            limit: 0,
+            after: None,
+            order_by: None,
+            filter,
+        }
+    }
+
+    fn condition(filter: Predicate) -> (String, usize) {
+        let statement = build(&query(Some(filter)), "cdl").unwrap();
+        let condition = statement
+            .text
+            .split(" latest WHERE ")
+            .nth(1)
+            .unwrap()
+            .split(" ORDER BY ")
+            .next()
+            .unwrap()
+            .to_owned();
+
+        (condition, statement.params.len())
+    }
+
+    #[test]
+    fn splits_json_pointers() {
+        assert_eq!(parse_pointer("").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            parse_pointer("/a/0/b~1c~0").unwrap(),
+            vec!["a", "0", "b/c~"]
+        );
+        assert!(parse_pointer("a/b").is_err());
+    }
+
+    #[test]
+    fn selects_latest_objects_of_schema() {
+        let statement = build(&query(None), "cdl").unwrap();
+
+        assert_eq!(
+            statement.text,
+            "SELECT object_id, payload, 'null'::jsonb \
+             FROM (SELECT DISTINCT ON (object_id) object_id, payload::jsonb AS payload \
+             FROM cdl.data WHERE schema_id = $1 ORDER BY object_id, version DESC) latest \
+             ORDER BY object_id LIMIT $2"
+        );
+        assert_eq!(statement.params.len(), 2);
+    }
+
+    #[test]
+    fn bounds_page_size() {
+        assert_eq!(page_size(0), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_size(10), 10);
+        assert_eq!(page_size(MAX_PAGE_SIZE + 1), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn translates_predicate_tree() {
+        let filter = predicate(Kind::And(PredicateList {
+            predicates: vec![
+                predicate(Kind::Equals(EqualsPredicate {
+                    path: "/a".to_owned(),
+                    value: "\"x\"".to_owned(),
+                })),
+                predicate(Kind::Not(Box::new(predicate(Kind::Or(PredicateList {
+                    predicates: vec![
+                        predicate(Kind::InList(InPredicate {
+                            path: "/b".to_owned(),
+                            values: vec!["1".to_owned(), "2".to_owned()],
+                        })),
+                        predicate(Kind::Exists(ExistsPredicate {
+                            path: "/c".to_owned(),
+                        })),
+                    ],
+                }))))),
+            ],
+        }));
+
+        assert_eq!(
+            condition(filter),
+            (
+                "(COALESCE((payload #> $2::text[]) = $3::jsonb, FALSE) AND \
+                 (NOT (COALESCE((payload #> $4::text[]) IN (SELECT jsonb_array_elements($5::jsonb)), FALSE) OR \
+                 COALESCE(jsonb_typeof((payload #> $6::text[])) <> 'null', FALSE))))"
+                    .to_owned(),
+                7
+            )
+        );
+    }
+
+    #[test]
+    fn translates_range() {
+        let filter = predicate(Kind::Range(RangePredicate {
+            path: "/a".to_owned(),
+            lower: Some(Bound {
+                value: "1".to_owned(),
+                inclusive: true,
+            }),
+            upper: None,
+        }));
+
+        assert_eq!(
+            condition(filter),
+            (
+                "COALESCE(jsonb_typeof((payload #> $2::text[])) = jsonb_typeof($3::jsonb) AND \
+                 (payload #> $2::text[]) >= $3::jsonb, FALSE)"
+                    .to_owned(),
+                4
+            )
+        );
+    }
+
+    #[test]
+    fn continues_after_cursor_in_order_of_field() {
+        let mut query = query(None);
+        query.limit = 10;
+        query.order_by = Some(Ordering {
+            path: "/a".to_owned(),
+            descending: true,
+        });
+        query.after = Some(Cursor {
+            object_id: SCHEMA_ID.to_owned(),
+            value: "5".to_owned(),
+        });
+
+        let statement = build(&query, "cdl").unwrap();
+
+        assert!(statement.text.ends_with(
+            " latest WHERE (COALESCE(payload #> $2::text[], 'null'::jsonb), object_id) < ($4::jsonb, $3::uuid) \
+             ORDER BY COALESCE(payload #> $2::text[], 'null'::jsonb) DESC, object_id DESC LIMIT $5"
+        ));
+        assert_eq!(statement.params.len(), 5);
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        let range = predicate(Kind::Range(RangePredicate {
+            path: "/a".to_owned(),
+            lower: None,
+            upper: None,
+        }));
+        let invalid_value = predicate(Kind::Equals(EqualsPredicate {
+            path: "/a".to_owned(),
+            value: "x".to_owned(),
+        }));
+        let mut missing_cursor_value = query(None);
+        missing_cursor_value.order_by = Some(Ordering {
+            path: "/a".to_owned(),
+            descending: false,
+        });
+        missing_cursor_value.after = Some(Cursor {
+            object_id: SCHEMA_ID.to_owned(),
+            value: String::new(),
+        });
+
+        assert!(build(&query(Some(range)), "cdl").is_err());
+        assert!(build(&query(Some(invalid_value)), "cdl").is_err());
+        assert!(build(
+            &query(Some(predicate(Kind::And(PredicateList::default())))),
+            "cdl"
+        )
+        .is_ok());
+        assert!(build(&missing_cursor_value, "cdl").is_err());
+    }
+}
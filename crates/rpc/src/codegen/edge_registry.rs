@@ -109,6 +109,18 @@ pub struct ObjectIdQuery {
     pub object_id: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ChildObjectsQuery {
+    #[prost(string, required, tag = "1")]
+    pub relation_id: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub child_object_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ObjectIds {
+    #[prost(string, repeated, tag = "1")]
+    pub object_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Empty {}
 #[doc = r" Generated client implementations."]
 pub mod edge_registry_client {
@@ -278,6 +290,21 @@ pub mod edge_registry_client {
             let path = http::uri::PathAndQuery::from_static("/edge_registry.EdgeRegistry/GetEdges");
             self.inner.unary(request.into_request(), path, codec).await
         }
+        pub async fn get_parents(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ChildObjectsQuery>,
+        ) -> Result<tonic::Response<super::ObjectIds>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/edge_registry.EdgeRegistry/GetParents");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
         pub async fn heartbeat(
             &mut self,
             request: impl tonic::IntoRequest<super::Empty>,
@@ -365,6 +392,10 @@ pub mod edge_registry_server {
             &self,
             request: tonic::Request<super::ObjectIdQuery>,
         ) -> Result<tonic::Response<super::ObjectRelations>, tonic::Status>;
+        async fn get_parents(
+            &self,
+            request: tonic::Request<super::ChildObjectsQuery>,
+        ) -> Result<tonic::Response<super::ObjectIds>, tonic::Status>;
         async fn heartbeat(
             &self,
             request: tonic::Request<super::Empty>,
@@ -684,6 +715,37 @@ pub mod edge_registry_server {
                     };
                     Box::pin(fut)
                 }
+                "/edge_registry.EdgeRegistry/GetParents" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetParentsSvc<T: EdgeRegistry>(pub Arc<T>);
+                    impl<T: EdgeRegistry> tonic::server::UnaryService<super::ChildObjectsQuery> for GetParentsSvc<T> {
+                        type Response = super::ObjectIds;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ChildObjectsQuery>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).get_parents(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let interceptor = inner.1.clone();
+                        let inner = inner.0;
+                        let method = GetParentsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = if let Some(interceptor) = interceptor {
+                            tonic::server::Grpc::with_interceptor(codec, interceptor)
+                        } else {
+                            tonic::server::Grpc::new(codec)
+                        };
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/edge_registry.EdgeRegistry/Heartbeat" => {
                     #[allow(non_camel_case_types)]
                     struct HeartbeatSvc<T: EdgeRegistry>(pub Arc<T>);
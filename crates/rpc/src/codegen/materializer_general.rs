@@ -8,6 +8,15 @@ pub struct MaterializedView {
     pub rows: ::prost::alloc::vec::Vec<super::common::RowDefinition>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeletedObjects {
+    #[prost(string, required, tag = "1")]
+    pub view_id: ::prost::alloc::string::String,
+    #[prost(message, required, tag = "2")]
+    pub options: Options,
+    #[prost(string, repeated, tag = "3")]
+    pub object_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Options {
     #[prost(string, required, tag = "1")]
     pub options: ::prost::alloc::string::String,
@@ -79,6 +88,22 @@ pub mod general_materializer_client {
             );
             self.inner.unary(request.into_request(), path, codec).await
         }
+        pub async fn delete_objects(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeletedObjects>,
+        ) -> Result<tonic::Response<super::Empty>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/materializer_general.GeneralMaterializer/DeleteObjects",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
     }
     impl<T: Clone> Clone for GeneralMaterializerClient<T> {
         fn clone(&self) -> Self {
@@ -108,6 +133,10 @@ pub mod general_materializer_server {
             &self,
             request: tonic::Request<super::MaterializedView>,
         ) -> Result<tonic::Response<super::Empty>, tonic::Status>;
+        async fn delete_objects(
+            &self,
+            request: tonic::Request<super::DeletedObjects>,
+        ) -> Result<tonic::Response<super::Empty>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct GeneralMaterializerServer<T: GeneralMaterializer> {
@@ -205,6 +234,39 @@ pub mod general_materializer_server {
                     };
                     Box::pin(fut)
                 }
+                "/materializer_general.GeneralMaterializer/DeleteObjects" => {
+                    #[allow(non_camel_case_types)]
+                    struct DeleteObjectsSvc<T: GeneralMaterializer>(pub Arc<T>);
+                    impl<T: GeneralMaterializer> tonic::server::UnaryService<super::DeletedObjects>
+                        for DeleteObjectsSvc<T>
+                    {
+                        type Response = super::Empty;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DeletedObjects>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).delete_objects(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let interceptor = inner.1.clone();
+                        let inner = inner.0;
+                        let method = DeleteObjectsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = if let Some(interceptor) = interceptor {
+                            tonic::server::Grpc::with_interceptor(codec, interceptor)
+                        } else {
+                            tonic::server::Grpc::new(codec)
+                        };
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
@@ -4,6 +4,44 @@ pub struct OnDemandRequest {
     pub view_id: ::prost::alloc::string::String,
     #[prost(map = "string, message", tag = "2")]
     pub schemas: ::std::collections::HashMap<::prost::alloc::string::String, Schema>,
+    /// Additional filter, applied together with the filter of the view
+    #[prost(message, optional, tag = "3")]
+    pub filter: ::core::option::Option<super::schema_registry::Filter>,
+    /// Names of the fields to return; all fields when empty
+    #[prost(string, repeated, tag = "4")]
+    pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "5")]
+    pub order_by: ::core::option::Option<Ordering>,
+    #[prost(uint64, optional, tag = "6")]
+    pub limit: ::core::option::Option<u64>,
+    #[prost(oneof = "on_demand_request::Page", tags = "7, 8")]
+    pub page: ::core::option::Option<on_demand_request::Page>,
+}
+/// Nested message and enum types in `OnDemandRequest`.
+pub mod on_demand_request {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Page {
+        #[prost(uint64, tag = "7")]
+        Offset(u64),
+        #[prost(message, tag = "8")]
+        After(super::Cursor),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Ordering {
+    #[prost(string, required, tag = "1")]
+    pub field: ::prost::alloc::string::String,
+    #[prost(bool, optional, tag = "2")]
+    pub descending: ::core::option::Option<bool>,
+}
+/// Position of the last row of previous page
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Cursor {
+    #[prost(string, repeated, tag = "1")]
+    pub object_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// JSON encoded value of the ordering field, required when `order_by` is set
+    #[prost(string, optional, tag = "2")]
+    pub value: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Schema {
@@ -4,6 +4,13 @@ pub struct View {
     pub view_id: ::prost::alloc::string::String,
     #[prost(map = "string, message", tag = "2")]
     pub schemas: ::std::collections::HashMap<::prost::alloc::string::String, Schema>,
+    #[prost(message, optional, tag = "3")]
+    pub filter: ::core::option::Option<super::schema_registry::Filter>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ViewId {
+    #[prost(string, required, tag = "1")]
+    pub view_id: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Schema {
@@ -65,6 +72,21 @@ pub mod object_builder_client {
                 .server_streaming(request.into_request(), path, codec)
                 .await
         }
+        pub async fn refresh_view(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ViewId>,
+        ) -> Result<tonic::Response<super::Empty>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path =
+                http::uri::PathAndQuery::from_static("/object_builder.ObjectBuilder/RefreshView");
+            self.inner.unary(request.into_request(), path, codec).await
+        }
         pub async fn heartbeat(
             &mut self,
             request: impl tonic::IntoRequest<super::Empty>,
@@ -110,6 +132,10 @@ pub mod object_builder_server {
             &self,
             request: tonic::Request<super::View>,
         ) -> Result<tonic::Response<Self::MaterializeStream>, tonic::Status>;
+        async fn refresh_view(
+            &self,
+            request: tonic::Request<super::ViewId>,
+        ) -> Result<tonic::Response<super::Empty>, tonic::Status>;
         async fn heartbeat(
             &self,
             request: tonic::Request<super::Empty>,
@@ -177,6 +203,34 @@ pub mod object_builder_server {
                     };
                     Box::pin(fut)
                 }
+                "/object_builder.ObjectBuilder/RefreshView" => {
+                    #[allow(non_camel_case_types)]
+                    struct RefreshViewSvc<T: ObjectBuilder>(pub Arc<T>);
+                    impl<T: ObjectBuilder> tonic::server::UnaryService<super::ViewId> for RefreshViewSvc<T> {
+                        type Response = super::Empty;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(&mut self, request: tonic::Request<super::ViewId>) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).refresh_view(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let interceptor = inner.1.clone();
+                        let inner = inner.0;
+                        let method = RefreshViewSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = if let Some(interceptor) = interceptor {
+                            tonic::server::Grpc::with_interceptor(codec, interceptor)
+                        } else {
+                            tonic::server::Grpc::new(codec)
+                        };
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/object_builder.ObjectBuilder/Heartbeat" => {
                     #[allow(non_camel_case_types)]
                     struct HeartbeatSvc<T: ObjectBuilder>(pub Arc<T>);
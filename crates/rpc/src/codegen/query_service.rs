@@ -15,6 +15,116 @@ pub struct Object {
     #[prost(bytes = "vec", tag = "2")]
     pub payload: ::prost::alloc::vec::Vec<u8>,
 }
+/// Latest versions of objects of the schema, filtered and ordered by fields of their payloads
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SchemaQuery {
+    #[prost(string, tag = "1")]
+    pub schema_id: ::prost::alloc::string::String,
+    /// Maximal number of objects in the page, 100 when 0, at most 1000
+    #[prost(uint64, tag = "2")]
+    pub limit: u64,
+    /// Last object of the previous page, first page is returned when missing
+    #[prost(message, optional, tag = "3")]
+    pub after: ::core::option::Option<Cursor>,
+    /// Objects are ordered by their ids when missing, and by ids within equal values otherwise
+    #[prost(message, optional, tag = "4")]
+    pub order_by: ::core::option::Option<Ordering>,
+    #[prost(message, optional, tag = "5")]
+    pub filter: ::core::option::Option<Predicate>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Ordering {
+    /// JSON pointer to the field of the payload
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub descending: bool,
+}
+/// Position of the last object of the previous page
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Cursor {
+    #[prost(string, tag = "1")]
+    pub object_id: ::prost::alloc::string::String,
+    /// JSON encoded value of the ordering field, required when `order_by` is set
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Predicate {
+    #[prost(oneof = "predicate::Predicate", tags = "1, 2, 3, 4, 5, 6, 7")]
+    pub predicate: ::core::option::Option<predicate::Predicate>,
+}
+/// Nested message and enum types in `Predicate`.
+pub mod predicate {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Predicate {
+        #[prost(message, tag = "1")]
+        And(super::PredicateList),
+        #[prost(message, tag = "2")]
+        Or(super::PredicateList),
+        #[prost(message, tag = "3")]
+        Not(::prost::alloc::boxed::Box<super::Predicate>),
+        #[prost(message, tag = "4")]
+        Equals(super::EqualsPredicate),
+        #[prost(message, tag = "5")]
+        Range(super::RangePredicate),
+        #[prost(message, tag = "6")]
+        InList(super::InPredicate),
+        #[prost(message, tag = "7")]
+        Exists(super::ExistsPredicate),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PredicateList {
+    #[prost(message, repeated, tag = "1")]
+    pub predicates: ::prost::alloc::vec::Vec<Predicate>,
+}
+/// Values are JSON encoded, paths are JSON pointers to the fields of the payload
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EqualsPredicate {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+/// Field has to be of the same JSON type as its bounds
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RangePredicate {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub lower: ::core::option::Option<Bound>,
+    #[prost(message, optional, tag = "3")]
+    pub upper: ::core::option::Option<Bound>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Bound {
+    #[prost(string, tag = "1")]
+    pub value: ::prost::alloc::string::String,
+    #[prost(bool, tag = "2")]
+    pub inclusive: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InPredicate {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    #[prost(string, repeated, tag = "2")]
+    pub values: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Field is present and isn't `null`
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExistsPredicate {
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ObjectPage {
+    #[prost(message, repeated, tag = "1")]
+    pub objects: ::prost::alloc::vec::Vec<Object>,
+    /// Cursor of the last object, missing when there are no more objects
+    #[prost(message, optional, tag = "2")]
+    pub next: ::core::option::Option<Cursor>,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RawStatement {
     #[prost(string, tag = "1")]
@@ -108,6 +218,22 @@ pub mod query_service_client {
             let path = http::uri::PathAndQuery::from_static("/query_service.QueryService/QueryRaw");
             self.inner.unary(request.into_request(), path, codec).await
         }
+        pub async fn query_by_schema_paged(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SchemaQuery>,
+        ) -> Result<tonic::Response<super::ObjectPage>, tonic::Status> {
+            self.inner.ready().await.map_err(|e| {
+                tonic::Status::new(
+                    tonic::Code::Unknown,
+                    format!("Service was not ready: {}", e.into()),
+                )
+            })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/query_service.QueryService/QueryBySchemaPaged",
+            );
+            self.inner.unary(request.into_request(), path, codec).await
+        }
     }
     impl<T: Clone> Clone for QueryServiceClient<T> {
         fn clone(&self) -> Self {
@@ -151,6 +277,10 @@ pub mod query_service_server {
             &self,
             request: tonic::Request<super::RawStatement>,
         ) -> Result<tonic::Response<super::ValueBytes>, tonic::Status>;
+        async fn query_by_schema_paged(
+            &self,
+            request: tonic::Request<super::SchemaQuery>,
+        ) -> Result<tonic::Response<super::ObjectPage>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct QueryServiceServer<T: QueryService> {
@@ -285,6 +415,37 @@ pub mod query_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/query_service.QueryService/QueryBySchemaPaged" => {
+                    #[allow(non_camel_case_types)]
+                    struct QueryBySchemaPagedSvc<T: QueryService>(pub Arc<T>);
+                    impl<T: QueryService> tonic::server::UnaryService<super::SchemaQuery> for QueryBySchemaPagedSvc<T> {
+                        type Response = super::ObjectPage;
+                        type Future = BoxFuture<tonic::Response<Self::Response>, tonic::Status>;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SchemaQuery>,
+                        ) -> Self::Future {
+                            let inner = self.0.clone();
+                            let fut = async move { (*inner).query_by_schema_paged(request).await };
+                            Box::pin(fut)
+                        }
+                    }
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let interceptor = inner.1.clone();
+                        let inner = inner.0;
+                        let method = QueryBySchemaPagedSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = if let Some(interceptor) = interceptor {
+                            tonic::server::Grpc::with_interceptor(codec, interceptor)
+                        } else {
+                            tonic::server::Grpc::new(codec)
+                        };
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => Box::pin(async move {
                     Ok(http::Response::builder()
                         .status(200)
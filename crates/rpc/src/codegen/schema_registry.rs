@@ -15,6 +15,9 @@ pub struct SchemaMetadata {
     pub query_address: ::prost::alloc::string::String,
     #[prost(message, required, tag = "4")]
     pub schema_type: SchemaType,
+    /// JSON pointers to fields of the payload, from which data router derives ids of objects sent without them
+    #[prost(string, repeated, tag = "5")]
+    pub natural_key: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SchemaMetadataPatch {
@@ -26,6 +29,13 @@ pub struct SchemaMetadataPatch {
     pub insert_destination: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(message, optional, tag = "4")]
     pub schema_type: ::core::option::Option<SchemaType>,
+    #[prost(message, optional, tag = "5")]
+    pub natural_key: ::core::option::Option<NaturalKey>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NaturalKey {
+    #[prost(string, repeated, tag = "1")]
+    pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Schema {
@@ -3,6 +3,14 @@ use object_builder_client::ObjectBuilderClient;
 use tonic::transport::Channel;
 
 pub use crate::codegen::object_builder::*;
+use bb8::{Pool, PooledConnection};
+
+pub type ObjectBuilderConn = ObjectBuilderClient<Channel>;
+pub type ObjectBuilderPool = Pool<ObjectBuilderConnectionManager>;
+
+pub struct ObjectBuilderConnectionManager {
+    pub address: String,
+}
 
 pub async fn connect(addr: impl Into<String>) -> Result<ObjectBuilderClient<Channel>, ClientError> {
     connect_inner(addr.into())
@@ -20,3 +28,27 @@ async fn connect_inner(
         tracing_utils::grpc::interceptor(),
     ))
 }
+
+#[async_trait::async_trait]
+impl bb8::ManageConnection for ObjectBuilderConnectionManager {
+    type Connection = ObjectBuilderConn;
+    type Error = ClientError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        tracing::debug!("Connecting to object builder");
+
+        connect(self.address.clone()).await
+    }
+
+    async fn is_valid(&self, conn: &mut PooledConnection<'_, Self>) -> Result<(), Self::Error> {
+        conn.heartbeat(Empty {})
+            .await
+            .map_err(|source| ClientError::QueryError { source })?;
+
+        Ok(())
+    }
+
+    fn has_broken(&self, _conn: &mut Self::Connection) -> bool {
+        false
+    }
+}
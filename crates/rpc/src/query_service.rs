@@ -70,3 +70,16 @@ pub async fn query_raw(raw_statement: String, addr: String) -> Result<Vec<u8>, C
 
     Ok(response.into_inner().value_bytes)
 }
+
+pub async fn query_by_schema_paged(
+    query: SchemaQuery,
+    addr: String,
+) -> Result<ObjectPage, ClientError> {
+    let mut conn = connect(addr).await?;
+    let response = conn
+        .query_by_schema_paged(query)
+        .await
+        .map_err(|err| ClientError::QueryError { source: err })?;
+
+    Ok(response.into_inner())
+}
@@ -0,0 +1,2 @@
+-- JSON pointers to fields, from which data router derives ids of objects sent without them
+ALTER TABLE schemas ADD COLUMN IF NOT EXISTS natural_key varchar[] not null default '{}';
@@ -72,7 +72,7 @@ impl SchemaRegistryDb {
 
         sqlx::query_as!(
             Schema,
-            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\"
+            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\", natural_key
              FROM schemas WHERE id = $1",
             id
         )
@@ -86,7 +86,7 @@ impl SchemaRegistryDb {
 
         sqlx::query_as!(
             Schema,
-            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\"
+            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\", natural_key
              FROM schemas WHERE id = (SELECT base_schema FROM views WHERE id = $1)",
             id
         )
@@ -131,6 +131,7 @@ impl SchemaRegistryDb {
             schema_type: schema.schema_type,
             insert_destination: schema.insert_destination,
             query_address: schema.query_address,
+            natural_key: schema.natural_key,
             definitions,
             views,
         })
@@ -261,7 +262,7 @@ impl SchemaRegistryDb {
 
         sqlx::query_as!(
             Schema,
-            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\" \
+            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\", natural_key \
              FROM schemas ORDER BY name"
         )
         .fetch_all(&mut conn)
@@ -274,7 +275,7 @@ impl SchemaRegistryDb {
 
         let all_schemas = sqlx::query_as!(
             Schema,
-            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\" FROM schemas"
+            "SELECT id, name, insert_destination, query_address, schema_type as \"schema_type: _\", natural_key FROM schemas"
         )
         .fetch_all(&mut conn)
         .await?;
@@ -327,6 +328,7 @@ impl SchemaRegistryDb {
                     schema_type: schema.schema_type,
                     insert_destination: schema.insert_destination,
                     query_address: schema.query_address,
+                    natural_key: schema.natural_key,
                     definitions,
                     views,
                 })
@@ -344,13 +346,14 @@ impl SchemaRegistryDb {
             .transaction::<_, _, RegistryError>(move |c| {
                 Box::pin(async move {
                     sqlx::query!(
-                        "INSERT INTO schemas(id, name, schema_type, insert_destination, query_address) \
-                         VALUES($1, $2, $3, $4, $5)",
+                        "INSERT INTO schemas(id, name, schema_type, insert_destination, query_address, natural_key) \
+                         VALUES($1, $2, $3, $4, $5, $6)",
                         &new_id,
                         &schema.name,
                         &schema.schema_type as &rpc::schema_registry::types::SchemaType,
                         &schema.insert_destination,
                         &schema.query_address,
+                        &schema.natural_key,
                     )
                     .execute(c.acquire().await?)
                     .await?;
@@ -402,11 +405,12 @@ impl SchemaRegistryDb {
         let old_schema = self.get_schema(id).await?;
 
         sqlx::query!(
-            "UPDATE schemas SET name = $1, schema_type = $2, insert_destination = $3, query_address = $4 WHERE id = $5",
+            "UPDATE schemas SET name = $1, schema_type = $2, insert_destination = $3, query_address = $4, natural_key = $5 WHERE id = $6",
             update.name.unwrap_or(old_schema.name),
             update.schema_type.unwrap_or(old_schema.schema_type) as _,
             update.insert_destination.unwrap_or(old_schema.insert_destination),
             update.query_address.unwrap_or(old_schema.query_address),
+            &update.natural_key.unwrap_or(old_schema.natural_key),
             id
         )
         .execute(&mut conn)
@@ -558,13 +562,14 @@ impl SchemaRegistryDb {
                 Box::pin(async move {
                     for schema in imported.schemas {
                         sqlx::query!(
-                            "INSERT INTO schemas(id, name, schema_type, insert_destination, query_address) \
-                             VALUES($1, $2, $3, $4, $5)",
+                            "INSERT INTO schemas(id, name, schema_type, insert_destination, query_address, natural_key) \
+                             VALUES($1, $2, $3, $4, $5, $6)",
                             schema.id,
                             schema.name,
                             schema.schema_type as _,
                             schema.insert_destination,
-                            schema.query_address
+                            schema.query_address,
+                            &schema.natural_key
                         )
                         .execute(c.acquire().await?)
                         .await?;
@@ -35,6 +35,8 @@ pub enum RegistryError {
     InvalidData(Vec<String>),
     #[error("Invalid JSON schema: {0}")]
     InvalidJsonSchema(jsonschema::CompilationError),
+    #[error("Natural key field \"{0}\" is not a JSON pointer")]
+    InvalidNaturalKey(String),
     #[error("Error receiving notification from database: {0}")]
     NotificationError(sqlx::Error),
     #[error("Malformed notification payload: {0}")]
@@ -74,7 +76,8 @@ impl From<RegistryError> for Status {
             | RegistryError::InvalidVersion(_)
             | RegistryError::NoVersionMatchesRequirement(_)
             | RegistryError::InvalidData(_)
-            | RegistryError::InvalidJsonSchema(_) => Status::invalid_argument(error.to_string()),
+            | RegistryError::InvalidJsonSchema(_)
+            | RegistryError::InvalidNaturalKey(_) => Status::invalid_argument(error.to_string()),
             RegistryError::ConnectionError(_)
             | RegistryError::DbError(_)
             | RegistryError::MQError(_)
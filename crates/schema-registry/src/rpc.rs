@@ -77,6 +77,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                 .schema_type
                 .try_into()
                 .map_err(|e| tonic::Status::invalid_argument(format!("{:?}", e)))?,
+            natural_key: validate_natural_key(request.metadata.natural_key)?,
         };
 
         if !new_schema.insert_destination.is_empty()
@@ -134,6 +135,12 @@ impl SchemaRegistry for SchemaRegistryImpl {
             None
         };
 
+        let natural_key = request
+            .patch
+            .natural_key
+            .map(|key| validate_natural_key(key.fields))
+            .transpose()?;
+
         if let Some(destination) = request.patch.insert_destination.as_ref() {
             if !self
                 .mq_metadata
@@ -153,6 +160,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                     query_address: request.patch.query_address,
                     insert_destination: request.patch.insert_destination,
                     schema_type,
+                    natural_key,
                 },
             )
             .await?;
@@ -299,6 +307,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
             insert_destination: schema.insert_destination,
             query_address: schema.query_address,
             schema_type: schema.schema_type.into(),
+            natural_key: schema.natural_key,
         }))
     }
 
@@ -354,6 +363,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                 insert_destination: schema.insert_destination,
                 query_address: schema.query_address,
                 schema_type: schema.schema_type.into(),
+                natural_key: schema.natural_key,
             },
             definitions: schema
                 .definitions
@@ -451,6 +461,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                         insert_destination: schema.insert_destination,
                         query_address: schema.query_address,
                         schema_type: schema.schema_type.into(),
+                        natural_key: schema.natural_key,
                     },
                 })
                 .collect(),
@@ -475,6 +486,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                             insert_destination: schema.insert_destination,
                             query_address: schema.query_address,
                             schema_type: schema.schema_type.into(),
+                            natural_key: schema.natural_key,
                         },
                         definitions: schema
                             .definitions
@@ -576,6 +588,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                 insert_destination: schema.insert_destination,
                 query_address: schema.query_address,
                 schema_type: schema.schema_type.into(),
+                natural_key: schema.natural_key,
             },
         }))
     }
@@ -626,6 +639,7 @@ impl SchemaRegistry for SchemaRegistryImpl {
                         insert_destination: schema.insert_destination,
                         query_address: schema.query_address,
                         schema_type: schema.schema_type.into(),
+                        natural_key: schema.natural_key,
                     },
                 })
             }),
@@ -687,6 +701,13 @@ fn parse_uuid(id: &str) -> Result<Uuid, Status> {
         .map_err(|err| Status::invalid_argument(format!("Failed to parse UUID: {}", err)))
 }
 
+fn validate_natural_key(fields: Vec<String>) -> Result<Vec<String>, Status> {
+    match fields.iter().find(|field| !field.starts_with('/')) {
+        Some(field) => Err(RegistryError::InvalidNaturalKey(field.clone()).into()),
+        None => Ok(fields),
+    }
+}
+
 fn serialize_json<T: serde::Serialize>(json: &T) -> Result<Vec<u8>, Status> {
     serde_json::to_vec(json)
         .map_err(|err| Status::internal(format!("Unable to serialize JSON: {}", err)))
@@ -14,6 +14,8 @@ pub struct Schema {
     pub query_address: String,
     #[serde(rename = "type")]
     pub schema_type: SchemaType,
+    #[serde(default)]
+    pub natural_key: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -24,6 +26,8 @@ pub struct NewSchema {
     #[serde(rename = "type")]
     pub schema_type: SchemaType,
     pub definition: Value,
+    #[serde(default)]
+    pub natural_key: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -33,6 +37,7 @@ pub struct SchemaUpdate {
     pub query_address: Option<String>,
     #[serde(rename = "type")]
     pub schema_type: Option<SchemaType>,
+    pub natural_key: Option<Vec<String>>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -43,6 +48,8 @@ pub struct FullSchema {
     pub query_address: String,
     #[serde(rename = "type")]
     pub schema_type: SchemaType,
+    #[serde(default)]
+    pub natural_key: Vec<String>,
     pub definitions: Vec<SchemaDefinition>,
     pub views: Vec<View>,
 }
@@ -8,9 +8,11 @@ pub use lapin::options::BasicConsumeOptions;
 use lapin::types::FieldTable;
 #[cfg(feature = "kafka")]
 use rdkafka::{
-    consumer::{DefaultConsumerContext, StreamConsumer},
-    ClientConfig,
+    consumer::{ConsumerContext, Rebalance, StreamConsumer},
+    ClientConfig, ClientContext,
 };
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 #[cfg(feature = "amqp")]
 use tokio_amqp::LapinTokioExt;
 use tracing_futures::Instrument;
@@ -47,10 +49,29 @@ pub enum CommonConsumerConfig<'a> {
     },
 }
 
+/// Marks Kafka consumer as assigned, once consumer group gave it partitions
+#[cfg(feature = "kafka")]
+#[derive(Default)]
+pub struct AssignmentContext {
+    assigned: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "kafka")]
+impl ClientContext for AssignmentContext {}
+
+#[cfg(feature = "kafka")]
+impl ConsumerContext for AssignmentContext {
+    fn post_rebalance(&self, rebalance: &Rebalance<'_>) {
+        if let Rebalance::Assign(_) = rebalance {
+            self.assigned.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
 pub enum CommonConsumer {
     #[cfg(feature = "kafka")]
     Kafka {
-        consumer: StreamConsumer<DefaultConsumerContext>,
+        consumer: StreamConsumer<AssignmentContext>,
         ack_queue: KafkaAckQueue,
     },
     #[cfg(feature = "amqp")]
@@ -77,7 +98,7 @@ impl CommonConsumer {
 
     #[cfg(feature = "kafka")]
     async fn new_kafka(group_id: &str, brokers: &str, topics: &[&str]) -> Result<Self> {
-        let consumer: StreamConsumer<DefaultConsumerContext> = ClientConfig::new()
+        let consumer: StreamConsumer<AssignmentContext> = ClientConfig::new()
             .set("group.id", group_id)
             .set("bootstrap.servers", brokers)
             .set("enable.partition.eof", "false")
@@ -86,7 +107,7 @@ impl CommonConsumer {
             .set("enable.auto.offset.store", "false")
             .set("auto.offset.reset", "earliest")
             .set("allow.auto.create.topics", "true")
-            .create()
+            .create_with_context(AssignmentContext::default())
             .context("Consumer creation failed")?;
 
         rdkafka::consumer::Consumer::subscribe(&consumer, topics)
@@ -123,6 +144,17 @@ impl CommonConsumer {
         Ok(CommonConsumer::Amqp { consumer })
     }
 
+    /// Flag set once consumer can receive messages.
+    /// Kafka consumer has to join its consumer group first, which happens only while it's being run.
+    pub fn assigned(&self) -> Arc<AtomicBool> {
+        match self {
+            #[cfg(feature = "kafka")]
+            CommonConsumer::Kafka { consumer, .. } => consumer.context().assigned.clone(),
+            #[cfg(feature = "amqp")]
+            CommonConsumer::Amqp { .. } => Arc::new(AtomicBool::new(true)),
+        }
+    }
+
     /// Process messages in order. Cannot be used with Grpc.
     /// # Error handling
     /// Function returns and error on first unhandled message.
@@ -5,7 +5,7 @@ use std::{
 };
 
 use rdkafka::{
-    consumer::{DefaultConsumerContext, StreamConsumer},
+    consumer::{ConsumerContext, StreamConsumer},
     message::BorrowedMessage,
     Message, Offset, TopicPartitionList,
 };
@@ -27,10 +27,10 @@ impl KafkaAckQueue {
 
         partition_queue.add(message);
     }
-    pub fn ack(
+    pub fn ack<C: ConsumerContext + 'static>(
         &self,
         message: &BorrowedMessage<'_>,
-        consumer: &StreamConsumer<DefaultConsumerContext>,
+        consumer: &StreamConsumer<C>,
     ) {
         let partition = message.partition();
         let mut queue = self.queue.lock().unwrap_or_else(abort_on_poison);
@@ -64,10 +64,10 @@ impl KafkaPartitionAckQueue {
         trace!("Adding offset {} Partition: {}", offset, self.partition);
     }
 
-    pub fn ack(
+    pub fn ack<C: ConsumerContext + 'static>(
         &mut self,
         message: &BorrowedMessage<'_>,
-        consumer: &StreamConsumer<DefaultConsumerContext>,
+        consumer: &StreamConsumer<C>,
     ) {
         let mut offset = message.offset();
         if *self.waiting_list.front().unwrap() == offset {
@@ -3,13 +3,24 @@ use anyhow::Context;
 #[cfg(feature = "amqp")]
 use lapin::message::Delivery;
 #[cfg(feature = "kafka")]
-use rdkafka::{message::BorrowedMessage, Message};
+use rdkafka::{
+    message::{BorrowedMessage, Headers},
+    Message,
+};
 
 use super::Result;
 
 pub trait CommunicationMessage: Send + Sync {
     fn payload(&self) -> Result<&str>;
     fn key(&self) -> Result<&str>;
+    /// Payload without UTF-8 validation, for binary encodings
+    fn payload_bytes(&self) -> Result<&[u8]>;
+    /// Content type of the payload, when message carries one
+    fn content_type(&self) -> Option<&str>;
+    /// Creation time of the message in milliseconds since Unix epoch, when message carries one
+    fn timestamp(&self) -> Option<i64> {
+        None
+    }
 }
 
 #[cfg(feature = "kafka")]
@@ -31,6 +42,21 @@ impl<'a> CommunicationMessage for KafkaCommunicationMessage<'a> {
             .payload_view::<str>()
             .ok_or_else(|| anyhow::anyhow!("Message has no payload"))??)
     }
+    fn payload_bytes(&self) -> Result<&[u8]> {
+        self.message
+            .payload()
+            .ok_or_else(|| anyhow::anyhow!("Message has no payload"))
+    }
+    fn content_type(&self) -> Option<&str> {
+        let headers = self.message.headers()?;
+        (0..headers.count())
+            .filter_map(|idx| headers.get(idx))
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .and_then(|(_, value)| std::str::from_utf8(value).ok())
+    }
+    fn timestamp(&self) -> Option<i64> {
+        self.message.timestamp().to_millis()
+    }
 }
 
 #[cfg(feature = "amqp")]
@@ -46,6 +72,23 @@ impl CommunicationMessage for AmqpCommunicationMessage {
     fn payload(&self) -> Result<&str> {
         Ok(std::str::from_utf8(&self.delivery.data).context("Payload was not valid UTF-8")?)
     }
+    fn payload_bytes(&self) -> Result<&[u8]> {
+        Ok(&self.delivery.data)
+    }
+    fn content_type(&self) -> Option<&str> {
+        self.delivery
+            .properties
+            .content_type()
+            .as_ref()
+            .map(|content_type| content_type.as_str())
+    }
+    fn timestamp(&self) -> Option<i64> {
+        // AMQP timestamps have a precision of seconds
+        self.delivery
+            .properties
+            .timestamp()
+            .map(|timestamp| timestamp as i64 * 1000)
+    }
 }
 
 #[cfg(feature = "grpc")]
@@ -57,4 +100,12 @@ impl CommunicationMessage for rpc::generic::Message {
     fn key(&self) -> Result<&str> {
         Ok(&self.key)
     }
+
+    fn payload_bytes(&self) -> Result<&[u8]> {
+        Ok(&self.payload)
+    }
+
+    fn content_type(&self) -> Option<&str> {
+        None
+    }
 }
@@ -0,0 +1,416 @@
+//! Embedded document storage, backed by RocksDB.
+//!
+//! Every version of an object is kept, same as in `data` table of Postgres repository.
+//! Command service opens the database as primary, query service follows it as a read-only secondary.
+//!
+//! Keys are prefixed by their kind:
+//! - `d` + object id + version - stored version of the object,
+//! - `s` + schema id + object id - objects of the schema,
+//! - `i` + object id + idempotency key - keys of stored messages.
+//!
+//! Writes of an object are serialized by a lock, so concurrent copies of the same message can't both pass
+//! the duplicate check.
+
+use anyhow::Context;
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+const DATA_PREFIX: u8 = b'd';
+const SCHEMA_PREFIX: u8 = b's';
+const IDEMPOTENCY_PREFIX: u8 = b'i';
+/// Number of locks objects are spread across
+const LOCK_SHARDS: usize = 64;
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredVersion {
+    pub schema_id: Uuid,
+    pub payload: Value,
+}
+
+pub struct DocumentStore {
+    db: DB,
+    locks: Vec<Mutex<()>>,
+}
+
+impl DocumentStore {
+    /// Opens database for writing, creating it when missing
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+
+        let db = DB::open(&options, path).context("Could not open document store")?;
+
+        Ok(Self::new(db))
+    }
+
+    /// Opens read-only replica of database written by other process.
+    /// Replica doesn't see new writes until [`catch_up`](Self::catch_up) is called.
+    pub fn open_secondary(
+        path: impl AsRef<Path>,
+        secondary_path: impl AsRef<Path>,
+    ) -> anyhow::Result<Self> {
+        let mut options = Options::default();
+        // Secondary instance has to keep every file open, as primary can delete them anytime
+        options.set_max_open_files(-1);
+
+        let db = DB::open_as_secondary(&options, path.as_ref(), secondary_path.as_ref())
+            .context("Could not open document store replica")?;
+
+        Ok(Self::new(db))
+    }
+
+    fn new(db: DB) -> Self {
+        Self {
+            db,
+            locks: (0..LOCK_SHARDS).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    fn lock(&self, object_id: Uuid) -> MutexGuard<'_, ()> {
+        let shard = (object_id.as_u128() % self.locks.len() as u128) as usize;
+        self.locks[shard]
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn catch_up(&self) -> anyhow::Result<()> {
+        self.db
+            .try_catch_up_with_primary()
+            .context("Could not catch up with primary document store")
+    }
+
+    /// Stores new version of the object.
+    /// Returns `false` when the version or the idempotency key was already stored.
+    pub fn insert(
+        &self,
+        object_id: Uuid,
+        schema_id: Uuid,
+        version: i64,
+        payload: &Value,
+        idempotency_key: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        let _guard = self.lock(object_id);
+        self.insert_locked(object_id, schema_id, version, payload, idempotency_key)
+    }
+
+    fn insert_locked(
+        &self,
+        object_id: Uuid,
+        schema_id: Uuid,
+        version: i64,
+        payload: &Value,
+        idempotency_key: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        let data_key = data_key(object_id, version);
+        if self.db.get_pinned(&data_key)?.is_some() {
+            return Ok(false);
+        }
+
+        let mut batch = WriteBatch::default();
+        if let Some(idempotency_key) = idempotency_key {
+            let key = idempotency_key_of(object_id, idempotency_key);
+            if self.db.get_pinned(&key)?.is_some() {
+                return Ok(false);
+            }
+            batch.put(key, b"");
+        }
+
+        batch.put(
+            data_key,
+            serde_json::to_vec(&StoredVersion {
+                schema_id,
+                payload: payload.clone(),
+            })?,
+        );
+        batch.put(schema_key(schema_id, object_id), b"");
+
+        self.db.write(batch)?;
+
+        Ok(true)
+    }
+
+    /// Stores new version of the object, with `patch` applied as JSON merge patch (RFC 7396)
+    /// to the latest version preceding it.
+    /// Returns `false` when the version or the idempotency key was already stored.
+    pub fn patch(
+        &self,
+        object_id: Uuid,
+        schema_id: Uuid,
+        version: i64,
+        patch: Value,
+        idempotency_key: Option<&str>,
+    ) -> anyhow::Result<bool> {
+        let _guard = self.lock(object_id);
+        let mut payload = self
+            .latest_before(object_id, version)?
+            .map(|stored| stored.payload)
+            .unwrap_or(Value::Null);
+        merge_patch(&mut payload, patch);
+
+        self.insert_locked(object_id, schema_id, version, &payload, idempotency_key)
+    }
+
+    /// Removes every version of the object stored up to `version`
+    pub fn delete(&self, object_id: Uuid, version: i64) -> anyhow::Result<()> {
+        let _guard = self.lock(object_id);
+        let mut batch = WriteBatch::default();
+        let mut schema_id = None;
+        let mut remaining = false;
+
+        for (key, value) in self.scan(&object_prefix(DATA_PREFIX, object_id)) {
+            if decode_version(&key[17..]) <= version {
+                let stored: StoredVersion = serde_json::from_slice(&value)?;
+                schema_id = Some(stored.schema_id);
+                batch.delete(key);
+            } else {
+                remaining = true;
+            }
+        }
+
+        if let (Some(schema_id), false) = (schema_id, remaining) {
+            batch.delete(schema_key(schema_id, object_id));
+        }
+
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Latest version of the object
+    pub fn latest(&self, object_id: Uuid) -> anyhow::Result<Option<StoredVersion>> {
+        self.latest_before(object_id, i64::MAX)
+    }
+
+    fn latest_before(
+        &self,
+        object_id: Uuid,
+        version: i64,
+    ) -> anyhow::Result<Option<StoredVersion>> {
+        let prefix = object_prefix(DATA_PREFIX, object_id);
+        let start = data_key(object_id, version);
+
+        let mut iter = self
+            .db
+            .iterator(IteratorMode::From(&start, Direction::Reverse));
+        match iter.next() {
+            Some((key, value)) if key.starts_with(&prefix) => {
+                Ok(Some(serde_json::from_slice(&value)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Ids of all objects of the schema
+    pub fn objects_of_schema(&self, schema_id: Uuid) -> Vec<Uuid> {
+        self.scan(&object_prefix(SCHEMA_PREFIX, schema_id))
+            .map(|(key, _)| Uuid::from_slice(&key[17..]).expect("Invalid key in document store"))
+            .collect()
+    }
+
+    fn scan<'a>(&'a self, prefix: &'a [u8]) -> impl Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a {
+        self.db
+            .iterator(IteratorMode::From(prefix, Direction::Forward))
+            .take_while(move |(key, _)| key.starts_with(prefix))
+    }
+}
+
+/// Applies JSON merge patch (RFC 7396): objects are merged recursively,
+/// `null` removes the field and any other value replaces the previous one.
+pub fn merge_patch(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+            let target = target.as_object_mut().unwrap();
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else {
+                    merge_patch(target.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+fn object_prefix(prefix: u8, id: Uuid) -> Vec<u8> {
+    let mut key = Vec::with_capacity(33);
+    key.push(prefix);
+    key.extend_from_slice(id.as_bytes());
+    key
+}
+
+fn data_key(object_id: Uuid, version: i64) -> Vec<u8> {
+    let mut key = object_prefix(DATA_PREFIX, object_id);
+    key.extend_from_slice(&encode_version(version));
+    key
+}
+
+fn schema_key(schema_id: Uuid, object_id: Uuid) -> Vec<u8> {
+    let mut key = object_prefix(SCHEMA_PREFIX, schema_id);
+    key.extend_from_slice(object_id.as_bytes());
+    key
+}
+
+fn idempotency_key_of(object_id: Uuid, idempotency_key: &str) -> Vec<u8> {
+    let mut key = object_prefix(IDEMPOTENCY_PREFIX, object_id);
+    key.extend_from_slice(idempotency_key.as_bytes());
+    key
+}
+
+/// Big endian with flipped sign bit, so byte order of keys matches order of versions
+fn encode_version(version: i64) -> [u8; 8] {
+    ((version as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+fn decode_version(bytes: &[u8]) -> i64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(bytes);
+    (u64::from_be_bytes(buf) ^ (1 << 63)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use test_case::test_case;
+
+    fn store() -> (tempfile::TempDir, DocumentStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DocumentStore::open(dir.path()).unwrap();
+        (dir, store)
+    }
+
+    #[test_case(r#"{"a": 1}"#, r#"{"b": 2}"# => json!({"a": 1, "b": 2}))]
+    #[test_case(r#"{"a": {"b": 1, "c": 2}}"#, r#"{"a": {"c": null}}"# => json!({"a": {"b": 1}}))]
+    #[test_case(r#"{"a": [1, 2]}"#, r#"{"a": [3]}"# => json!({"a": [3]}))]
+    #[test_case(r#"{"a": 1}"#, r#"[1]"# => json!([1]))]
+    #[test_case("null", r#"{"a": {"b": null}}"# => json!({"a": {}}))]
+    fn merges_patch(target: &str, patch: &str) -> Value {
+        let mut target = serde_json::from_str(target).unwrap();
+        merge_patch(&mut target, serde_json::from_str(patch).unwrap());
+        target
+    }
+
+    #[test_case(i64::MIN, -1)]
+    #[test_case(-1, 0)]
+    #[test_case(0, 1603285776)]
+    #[test_case(1603285776, i64::MAX)]
+    fn encoded_versions_keep_order(lower: i64, higher: i64) {
+        assert!(encode_version(lower) < encode_version(higher));
+        assert_eq!(decode_version(&encode_version(lower)), lower);
+    }
+
+    #[test]
+    fn keeps_versions_of_object() {
+        let (_dir, store) = store();
+        let (object_id, schema_id) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        assert!(store
+            .insert(object_id, schema_id, 2, &json!({"v": 2}), None)
+            .unwrap());
+        assert!(store
+            .insert(object_id, schema_id, 1, &json!({"v": 1}), None)
+            .unwrap());
+        assert!(!store
+            .insert(object_id, schema_id, 2, &json!({"v": 3}), None)
+            .unwrap());
+
+        let latest = store.latest(object_id).unwrap().unwrap();
+        assert_eq!(latest.payload, json!({"v": 2}));
+        assert_eq!(latest.schema_id, schema_id);
+        assert_eq!(store.objects_of_schema(schema_id), vec![object_id]);
+        assert!(store.latest(Uuid::from_u128(3)).unwrap().is_none());
+    }
+
+    #[test]
+    fn skips_duplicated_idempotency_key() {
+        let (_dir, store) = store();
+        let (object_id, schema_id) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        assert!(store
+            .insert(object_id, schema_id, 1, &json!({"v": 1}), Some("key"))
+            .unwrap());
+        assert!(!store
+            .insert(object_id, schema_id, 2, &json!({"v": 1}), Some("key"))
+            .unwrap());
+
+        assert_eq!(
+            store.latest(object_id).unwrap().unwrap().payload,
+            json!({"v": 1})
+        );
+    }
+
+    #[test]
+    fn stores_concurrent_duplicates_once() {
+        let (_dir, store) = store();
+        let store = std::sync::Arc::new(store);
+        let (object_id, schema_id) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        let stored = (0..8)
+            .map(|version| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    store
+                        .insert(object_id, schema_id, version, &json!({}), Some("key"))
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|stored| *stored)
+            .count();
+
+        assert_eq!(stored, 1);
+    }
+
+    #[test]
+    fn patches_latest_preceding_version() {
+        let (_dir, store) = store();
+        let (object_id, schema_id) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        store
+            .insert(object_id, schema_id, 1, &json!({"a": 1, "b": 1}), None)
+            .unwrap();
+        store
+            .patch(object_id, schema_id, 2, json!({"b": null, "c": 2}), None)
+            .unwrap();
+
+        assert_eq!(
+            store.latest(object_id).unwrap().unwrap().payload,
+            json!({"a": 1, "c": 2})
+        );
+    }
+
+    #[test]
+    fn deletes_versions_up_to_timestamp() {
+        let (_dir, store) = store();
+        let (object_id, schema_id) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        store
+            .insert(object_id, schema_id, 1, &json!({"v": 1}), None)
+            .unwrap();
+        store
+            .insert(object_id, schema_id, 3, &json!({"v": 3}), None)
+            .unwrap();
+
+        store.delete(object_id, 2).unwrap();
+        assert_eq!(
+            store.latest(object_id).unwrap().unwrap().payload,
+            json!({"v": 3})
+        );
+        assert_eq!(store.objects_of_schema(schema_id), vec![object_id]);
+
+        store.delete(object_id, 3).unwrap();
+        assert!(store.latest(object_id).unwrap().is_none());
+        assert!(store.objects_of_schema(schema_id).is_empty());
+    }
+}
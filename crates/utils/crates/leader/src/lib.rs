@@ -0,0 +1,159 @@
+//! Leader election of service instances, backed by Postgres session advisory lock.
+//!
+//! Every instance sharing the same `lock_name` competes for the lock, only one of them holds it at a time.
+//! Lock is released by Postgres as soon as connection of its holder is closed,
+//! which lets one of the hot standbys take over.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bb8_postgres::bb8::{Pool, PooledConnection};
+use bb8_postgres::tokio_postgres::{Config, NoTls};
+use bb8_postgres::PostgresConnectionManager;
+use metrics_utils::{self as metrics, gauge};
+use serde::{Deserialize, Serialize};
+use settings_utils::PostgresSettings;
+use std::time::Duration;
+use tokio::time::sleep;
+use utils::status_endpoints;
+
+type Connection<'a> = PooledConnection<'a, PostgresConnectionManager<NoTls>>;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LeaderElectionSettings {
+    /// Instances using the same name compete for the leadership
+    pub lock_name: String,
+    /// How often standby tries to acquire the lock and leader checks its connection
+    pub check_interval_ms: u64,
+    /// Standbys report they are not ready (`/status/readiness`) until they acquire the lock
+    #[serde(default)]
+    pub ready_only_when_leader: bool,
+    pub postgres: PostgresSettings,
+}
+
+#[async_trait]
+pub trait LeadershipHandler: Send + Sync {
+    async fn on_acquired(&self) -> anyhow::Result<()>;
+    async fn on_lost(&self) -> anyhow::Result<()>;
+}
+
+pub struct LeaderElector {
+    settings: LeaderElectionSettings,
+    pool: Pool<PostgresConnectionManager<NoTls>>,
+}
+
+impl LeaderElector {
+    pub async fn new(settings: LeaderElectionSettings) -> anyhow::Result<Self> {
+        let postgres = &settings.postgres;
+        let mut pg_config = Config::new();
+        pg_config
+            .user(&postgres.username)
+            .password(&postgres.password)
+            .host(&postgres.host)
+            .port(postgres.port)
+            .dbname(&postgres.dbname);
+        let manager = PostgresConnectionManager::new(pg_config, NoTls);
+        // Lock belongs to the session, so the single connection is kept by the leader
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(manager)
+            .await
+            .context("Failed to build leader election connection pool")?;
+
+        Ok(Self { settings, pool })
+    }
+
+    fn check_interval(&self) -> Duration {
+        Duration::from_millis(self.settings.check_interval_ms)
+    }
+
+    async fn connect(&self) -> anyhow::Result<Connection<'_>> {
+        self.pool
+            .get()
+            .await
+            .context("Could not connect to leader election database")
+    }
+
+    async fn try_lock(&self, client: &Connection<'_>) -> anyhow::Result<bool> {
+        // `hashtext` keeps the lock key the same across instances and builds
+        let row = client
+            .query_one(
+                "SELECT pg_try_advisory_lock(hashtext($1))",
+                &[&self.settings.lock_name],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    /// Waits until this instance becomes the leader.
+    /// Returned connection holds the lock, leadership is lost once it's closed.
+    async fn acquire(&self) -> Connection<'_> {
+        loop {
+            match self.connect().await {
+                Ok(client) => loop {
+                    match self.try_lock(&client).await {
+                        Ok(true) => return client,
+                        Ok(false) => {
+                            tracing::trace!(lock_name = %self.settings.lock_name, "Lock is held by other instance");
+                        }
+                        Err(err) => {
+                            tracing::error!("Could not acquire leadership: {:?}", err);
+                            break;
+                        }
+                    }
+                    sleep(self.check_interval()).await;
+                },
+                Err(err) => {
+                    tracing::error!("{:?}", err);
+                }
+            }
+            sleep(self.check_interval()).await;
+        }
+    }
+
+    /// Resolves when connection holding the lock is closed
+    async fn wait_until_lost(&self, client: &Connection<'_>) {
+        loop {
+            sleep(self.check_interval()).await;
+            if client.is_closed() {
+                return;
+            }
+            if let Err(err) = client.simple_query("SELECT 1").await {
+                tracing::error!("Leader election connection failed: {:?}", err);
+                return;
+            }
+        }
+    }
+
+    /// Runs election forever. Leadership is reported by `cdl.leader.is-leader` gauge, and by readiness
+    /// of the instance when `ready_only_when_leader` is set.
+    pub async fn run(self, handler: impl LeadershipHandler) -> anyhow::Result<()> {
+        loop {
+            self.set_leader(false);
+            tracing::info!(lock_name = %self.settings.lock_name, "Waiting for leadership");
+
+            let client = self.acquire().await;
+
+            tracing::info!(lock_name = %self.settings.lock_name, "Acquired leadership");
+            self.set_leader(true);
+            handler.on_acquired().await?;
+
+            self.wait_until_lost(&client).await;
+
+            tracing::warn!(lock_name = %self.settings.lock_name, "Lost leadership");
+            self.set_leader(false);
+            handler.on_lost().await?;
+        }
+    }
+
+    fn set_leader(&self, leader: bool) {
+        gauge!("cdl.leader.is-leader", if leader { 1.0 } else { 0.0 });
+        if self.settings.ready_only_when_leader {
+            if leader {
+                status_endpoints::mark_as_ready();
+            } else {
+                status_endpoints::mark_as_not_ready();
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::net::{Ipv4Addr, SocketAddrV4};
 
-pub use metrics::{self, counter, gauge, try_recorder, Key, SharedString};
+pub use metrics::{self, counter, gauge, histogram, try_recorder, Key, SharedString};
 use metrics_exporter_prometheus::PrometheusBuilder;
 use settings_utils::MonitoringSettings;
 use tracing::debug;
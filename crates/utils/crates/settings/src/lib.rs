@@ -36,6 +36,60 @@ pub struct PostgresSettings {
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct VictoriaMetricsSettings {
     pub url: Url,
+    /// Comma separated list of payload fields written as tags instead of fields
+    #[serde(default)]
+    pub tags: String,
+    /// Joins names of nested objects and arrays, when they are flattened into fields
+    #[serde(default = "default_field_separator")]
+    pub field_separator: String,
+    /// Precision of timestamps in payload, nanoseconds when missing
+    #[serde(default)]
+    pub precision: Option<TimestampPrecision>,
+}
+
+impl VictoriaMetricsSettings {
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum TimestampPrecision {
+    #[serde(rename = "ns")]
+    Nanoseconds,
+    #[serde(rename = "us")]
+    Microseconds,
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "s")]
+    Seconds,
+}
+
+impl TimestampPrecision {
+    /// Value of `precision` parameter of line protocol endpoint
+    pub fn as_query_param(&self) -> &'static str {
+        match self {
+            TimestampPrecision::Nanoseconds => "ns",
+            TimestampPrecision::Microseconds => "u",
+            TimestampPrecision::Milliseconds => "ms",
+            TimestampPrecision::Seconds => "s",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RocksDbSettings {
+    /// Directory of the database, written by command service
+    pub path: String,
+    /// Directory of the read-only replica kept by query service
+    #[serde(default)]
+    pub secondary_path: Option<String>,
+    /// Minimal time between catching up of the replica with writes of command service
+    #[serde(default = "default_catch_up_interval_ms")]
+    pub catch_up_interval_ms: u64,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -94,6 +148,14 @@ impl Default for LogSettings {
     }
 }
 
+fn default_catch_up_interval_ms() -> u64 {
+    100
+}
+
+fn default_field_separator() -> String {
+    "_".to_string()
+}
+
 fn default_otel_service_name() -> String {
     env::current_exe()
         .expect("Current executable name")
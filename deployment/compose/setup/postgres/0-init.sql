@@ -3,5 +3,10 @@ CREATE TABLE IF NOT EXISTS data (
     version BIGINT NOT NULL,
     schema_id UUID NOT NULL,
     payload JSON NOT NULL,
+    idempotency_key TEXT,
+    patch JSONB,
     PRIMARY KEY (object_id, version)
 );
+
+-- Messages without idempotency key are never considered duplicates, as NULLs are distinct
+CREATE UNIQUE INDEX IF NOT EXISTS data_idempotency_key ON data (object_id, idempotency_key);
@@ -0,0 +1,46 @@
+import pytest
+
+from tests.common import load_case, retry_retrieve
+from tests.common.command_service import BatchingConfig, CommandService
+from tests.common.kafka import push_to_kafka, create_kafka_topic, delete_kafka_topic, KafkaInputConfig
+from tests.common.postgres import clear_data, fetch_data, PostgresConfig
+
+TOPIC = "cdl.testing.command-service.postgres.batching"
+
+
+@pytest.fixture
+def prepare():
+    data, expected = load_case('batched_insert_delete',
+                               'command_service/postgres')
+
+    # declare environment
+    kafka_config = KafkaInputConfig(TOPIC)
+    postgres_config = PostgresConfig()
+
+    # prepare environment
+    create_kafka_topic(kafka_config, TOPIC)
+    clear_data(postgres_config)
+
+    with CommandService(kafka_config,
+                        db_config=postgres_config,
+                        batching_config=BatchingConfig()):
+        yield data, expected, kafka_config, postgres_config
+
+    # cleanup environment
+    delete_kafka_topic(kafka_config, TOPIC)
+    clear_data(postgres_config)
+
+
+def test_delete_follows_batched_insert_of_the_same_object(prepare):
+    data, expected, kafka_config, postgres_config = prepare
+
+    # Messages are keyed by object id, so the delete waits in the order group
+    # until the batch containing the insert is written
+    for entry in data:
+        push_to_kafka(kafka_config, entry)
+
+    actual, err = retry_retrieve(lambda: fetch_data(postgres_config),
+                                 len(expected))
+
+    assert err is None
+    assert actual == expected
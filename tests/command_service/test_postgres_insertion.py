@@ -8,7 +8,7 @@ from tests.common.postgres import clear_data, fetch_data, PostgresConfig
 TOPIC = "cdl.testing.command-service.postgres"
 
 
-@pytest.fixture(params=['single_insert', 'multiple_inserts'])
+@pytest.fixture(params=['single_insert', 'multiple_inserts', 'patch', 'idempotent_insert'])
 def prepare(request):
     data, expected = load_case(request.param, 'command_service/postgres')
 
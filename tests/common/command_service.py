@@ -6,14 +6,28 @@ from tests.common.postgres import PostgresConfig
 EXE = os.getenv('COMMAND_SERVICE_EXE') or 'command-service'
 
 
+class BatchingConfig:
+    def __init__(self, max_size='100', max_age_ms='500'):
+        self.max_size = max_size
+        self.max_age_ms = max_age_ms
+
+    def to_dict(self, app):
+        return {
+            f"{app}_BATCHING__MAX_SIZE": self.max_size,
+            f"{app}_BATCHING__MAX_AGE_MS": self.max_age_ms,
+        }
+
+
 class CommandService:
     def __init__(self,
                  kafka_input_config,
                  kafka_report_config=None,
-                 db_config=None):
+                 db_config=None,
+                 batching_config=None):
         self.kafka_input_config = kafka_input_config
         self.kafka_report_config = kafka_report_config
         self.db_config = db_config
+        self.batching_config = batching_config
 
     def __enter__(self):
         env = self.kafka_input_config.to_dict("COMMAND_SERVICE")
@@ -35,6 +49,9 @@ class CommandService:
 
         env.update(self.db_config.to_dict("COMMAND_SERVICE"))
 
+        if self.batching_config:
+            env.update(self.batching_config.to_dict("COMMAND_SERVICE"))
+
         env.update({"COMMAND_SERVICE_MONITORING__OTEL_SERVICE_NAME": "command-service"})
 
         self.svc = subprocess.Popen([EXE, plugin], env=env)
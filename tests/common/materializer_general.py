@@ -0,0 +1,38 @@
+import os
+import subprocess
+import time
+
+from tests.common.postgres import PostgresConfig
+
+EXE = os.getenv('MATERIALIZER_GENERAL_EXE') or 'materializer-general'
+
+
+class MaterializerGeneral:
+    def __init__(self,
+                 postgres_config: PostgresConfig,
+                 schema_registry_addr='http://schema_registry_not_used',
+                 input_port='50203'):
+        self.postgres_config = postgres_config
+        self.schema_registry_addr = schema_registry_addr
+        self.input_port = input_port
+        self.svc = None
+
+    def start(self):
+        env = self.postgres_config.to_dict("MATERIALIZER_GENERAL")
+        env.update(
+            MATERIALIZER_GENERAL_INPUT_PORT=self.input_port,
+            MATERIALIZER_GENERAL_CACHE_CAPACITY='1024',
+            MATERIALIZER_GENERAL_SERVICES__SCHEMA_REGISTRY_URL=self.
+            schema_registry_addr,
+            MATERIALIZER_GENERAL_MONITORING__OTEL_SERVICE_NAME=
+            'materializer-general',
+            MATERIALIZER_GENERAL_MONITORING__STATUS_PORT='0')
+
+        self.svc = subprocess.Popen([EXE], env=env)
+
+        time.sleep(3)
+
+        return self
+
+    def stop(self):
+        self.svc.kill()
@@ -52,3 +52,8 @@ class QueryRouter:
         return requests.post(f"http://localhost:{self.input_port}/raw",
                              body,
                              headers={'SCHEMA_ID': schema_id})
+
+    def query_get_schema_page(self, schema_id, query):
+        return requests.post(f"http://localhost:{self.input_port}/schema/page",
+                             json=query,
+                             headers={'SCHEMA_ID': schema_id})
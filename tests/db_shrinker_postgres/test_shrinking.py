@@ -1,9 +1,13 @@
 import pytest
 
-from tests.common import load_case
+from tests.common import load_case, retry_retrieve
+from tests.common.command_service import CommandService
 from tests.common.db_shrinker_postgres import DbShrinkerPostgres
+from tests.common.kafka import push_to_kafka, create_kafka_topic, delete_kafka_topic, KafkaInputConfig
 from tests.common.postgres import clear_data, insert_data, fetch_data, PostgresConfig
 
+TOPIC = "cdl.testing.db-shrinker-postgres.patch"
+
 
 @pytest.fixture(params=[
     'field_added', 'field_deleted', 'partial_update', 'simple_override'
@@ -32,3 +36,38 @@ def test_shrinking(shrinking):
     actual = fetch_data(postgres_config)
 
     assert actual == expected
+
+
+@pytest.fixture
+def patched():
+    data, expected = load_case('patch', 'command_service/postgres')
+
+    kafka_config = KafkaInputConfig(TOPIC)
+    postgres_config = PostgresConfig()
+
+    create_kafka_topic(kafka_config, TOPIC)
+    clear_data(postgres_config)
+
+    with CommandService(kafka_config, db_config=postgres_config):
+        for entry in data:
+            push_to_kafka(kafka_config, entry)
+
+        stored, err = retry_retrieve(lambda: fetch_data(postgres_config),
+                                     len(expected))
+        assert err is None
+
+    yield postgres_config, stored
+
+    delete_kafka_topic(kafka_config, TOPIC)
+    clear_data(postgres_config)
+
+
+def test_shrinking_keeps_fields_removed_by_patch(patched):
+    postgres_config, stored = patched
+
+    DbShrinkerPostgres(postgres_config).run()
+
+    actual = fetch_data(postgres_config)
+
+    # Shrunk object reads the same as its latest version before shrinking
+    assert actual == [stored[-1]]
@@ -5,7 +5,7 @@ from tests.common.edge_registry import EdgeRegistry
 from tests.common.kafka import KafkaInputConfig, create_kafka_topic, delete_kafka_topic
 from tests.common.postgres import PostgresConfig, clear_relations
 from tests.rpc.proto import edge_registry_pb2_grpc
-from tests.rpc.proto.edge_registry_pb2 import AddSchemaRelation, Empty, RelationDetails, RelationQuery, SchemaId, ObjectRelations, RelationIdQuery, Edge, ObjectIdQuery
+from tests.rpc.proto.edge_registry_pb2 import AddSchemaRelation, Empty, RelationDetails, RelationQuery, SchemaId, ObjectRelations, RelationIdQuery, Edge, ObjectIdQuery, ChildObjectsQuery
 
 TOPIC = "cdl.edge.tests_data"
 
@@ -139,3 +139,32 @@ def test_get_edges(prepare):
             prepare.GetEdges(ObjectIdQuery(object_id=parent)).relations))
 
     assert [(relation1, [child1]), (relation2, [child2])] == result
+
+
+def test_get_parents(prepare):
+    relation = prepare.AddRelation(
+        AddSchemaRelation(parent_schema_id="1d1cc7a5-9277-48bc-97d3-3d99cfb63100",
+                       child_schema_id="1d1cc7a5-9277-48bc-97d3-3d99cfb63101")
+    ).relation_id
+
+    parent1 = "1d1cc7a5-9277-48bc-97d3-3d99cfb63102"
+    parent2 = "1d1cc7a5-9277-48bc-97d3-3d99cfb63103"
+    child1 = "1d1cc7a5-9277-48bc-97d3-3d99cfb63104"
+    child2 = "1d1cc7a5-9277-48bc-97d3-3d99cfb63105"
+    child3 = "1d1cc7a5-9277-48bc-97d3-3d99cfb63106"
+
+    prepare.AddEdges(
+        ObjectRelations(relations=[
+            Edge(relation_id=relation,
+                 parent_object_id=parent1,
+                 child_object_ids=[child1, child2]),
+            Edge(relation_id=relation,
+                 parent_object_id=parent2,
+                 child_object_ids=[child3])
+        ]))
+
+    result = prepare.GetParents(
+        ChildObjectsQuery(relation_id=relation,
+                          child_object_ids=[child1, child2])).object_ids
+
+    assert [parent1] == list(result)
@@ -0,0 +1,69 @@
+import json
+
+import grpc
+import pytest
+
+from tests.common.materializer_general import MaterializerGeneral
+from tests.common.postgres import PostgresConfig, connect_to_postgres
+from tests.rpc.proto import materializer_general_pb2_grpc
+from tests.rpc.proto.materializer_general_pb2 import DeletedObjects, Options
+
+# Unquoted in SQL, so Postgres stores it as `materialized_view`
+TABLE = 'MATERIALIZED_VIEW'
+DELETED_ID = '00000000-0000-0000-0000-000000000001'
+KEPT_ID = '00000000-0000-0000-0000-000000000002'
+
+
+def execute(config: PostgresConfig, statement, params=None):
+    db = connect_to_postgres(config)
+    curr = db.cursor()
+    curr.execute(statement, params)
+    rows = curr.fetchall() if curr.description else None
+    db.commit()
+    curr.close()
+    db.close()
+    return rows
+
+
+@pytest.fixture
+def prepare():
+    # declare environment
+    postgres_config = PostgresConfig()
+
+    # prepare environment
+    execute(postgres_config, f'DROP TABLE IF EXISTS {TABLE}')
+    execute(
+        postgres_config,
+        f'CREATE TABLE {TABLE} (object_ids UUID[] NOT NULL PRIMARY KEY, name JSON NOT NULL)'
+    )
+    for object_id in [DELETED_ID, KEPT_ID]:
+        execute(postgres_config,
+                f'INSERT INTO {TABLE} VALUES (ARRAY[%s]::uuid[], %s)',
+                (object_id, json.dumps(object_id)))
+
+    mg = MaterializerGeneral(postgres_config)
+    channel = grpc.insecure_channel(f"localhost:{mg.input_port}")
+    stub = materializer_general_pb2_grpc.GeneralMaterializerStub(channel)
+
+    mg.start()
+
+    yield stub, postgres_config
+
+    mg.stop()
+
+    # cleanup environment
+    execute(postgres_config, f'DROP TABLE IF EXISTS {TABLE}')
+
+
+def test_deletes_rows_from_upper_case_table(prepare):
+    stub, postgres_config = prepare
+
+    stub.DeleteObjects(
+        DeletedObjects(view_id='00000000-0000-0000-0000-000000000000',
+                       options=Options(options=json.dumps({'table': TABLE})),
+                       object_ids=[DELETED_ID]))
+
+    rows = execute(postgres_config,
+                   f'SELECT object_ids::text[] FROM {TABLE}')
+
+    assert rows == [([KEPT_ID], )]
@@ -0,0 +1,77 @@
+import pytest
+
+from tests.common import load_case
+from tests.common.kafka import KafkaInputConfig
+from tests.common.postgres import clear_data, insert_data, PostgresConfig
+from tests.common.query_router import QueryRouter
+from tests.common.query_service import QueryService
+from tests.common.schema_registry import SchemaRegistry
+
+TOPIC = 'qr.test.schema.page'
+
+
+@pytest.fixture
+def prepare(tmp_path):
+    data, expected = load_case('schema_page/ordered_by_field', 'query_router')
+
+    # declare environment
+    kafka_input_config = KafkaInputConfig(TOPIC)
+    postgres_config = PostgresConfig()
+
+    qs = QueryService(db_config=postgres_config)
+    sr = SchemaRegistry('http://edge_registry_not_used', kafka_input_config.brokers, postgres_config)
+
+    # prepare environment
+    sr.start()
+
+    schema_id = sr.create_schema('test', kafka_input_config.topic,
+                                 f'http://localhost:{qs.input_port}', '{}', 0)
+
+    clear_data(postgres_config)
+
+    for entry in data['database_setup']:
+        entry['schema_id'] = schema_id
+
+    insert_data(postgres_config, data['database_setup'])
+
+    qs.start()
+
+    with QueryRouter(f'http://localhost:{sr.input_port}') as qr:
+        yield data['query'], expected, qr, schema_id
+
+    # cleanup environment
+    clear_data(postgres_config)
+
+    sr.stop()
+    qs.stop()
+
+
+def test_endpoint_schema_page_continues_after_cursor(prepare):
+    query, expected, qr, schema_id = prepare
+
+    pages = []
+    while True:
+        response = qr.query_get_schema_page(schema_id, query)
+        assert response.status_code == 200
+
+        page = response.json()
+        pages.append(page)
+        if 'next' not in page:
+            break
+        query['after'] = page['next']
+
+    # Objects with equal values are split between pages, so the second page
+    # is selected by comparison of (value, object id) pairs
+    assert [[o['objectId'] for o in page['objects']] for page in pages] == \
+        [page['objects'] for page in expected]
+    assert [page.get('next') for page in pages] == \
+        [page.get('next') for page in expected]
+
+
+def test_endpoint_schema_page_rejects_invalid_query(prepare):
+    _, _, qr, schema_id = prepare
+
+    response = qr.query_get_schema_page(schema_id,
+                                        {'orderBy': {'path': 'age'}})
+
+    assert response.status_code == 400
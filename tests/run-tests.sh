@@ -11,6 +11,7 @@ export QUERY_SERVICE_EXE="../target/debug/query-service"
 export QUERY_SERVICE_TS_EXE="../target/debug/query-service-ts"
 export EDGE_REGISTRY_EXE="../target/debug/edge-registry"
 export OBJECT_BUILDER_EXE="../target/debug/object-builder"
+export MATERIALIZER_GENERAL_EXE="../target/debug/materializer-general"
 
 echo "pip3 install -r '../requirements.txt'"
 pip3 install -r "../requirements.txt"
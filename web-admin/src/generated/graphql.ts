@@ -156,6 +156,13 @@ export enum LogicOperator {
   Or = 'OR'
 }
 
+export type LastObject = {
+  __typename?: 'LastObject';
+  objectId: Scalars['UUID'];
+  /** Value of the ordering field */
+  value?: Maybe<Scalars['JSON']>;
+};
+
 export type MaterializedView = {
   __typename?: 'MaterializedView';
   /** Source view's UUID */
@@ -288,6 +295,13 @@ export type NewView = {
   relations: Array<NewRelation>;
 };
 
+export type ObjectPage = {
+  __typename?: 'ObjectPage';
+  objects: Array<CdlObject>;
+  /** Last object of the page, missing when there are no more objects */
+  next?: Maybe<LastObject>;
+};
+
 export type ObjectRelations = {
   /** Object's schema relations */
   relationId: Scalars['UUID'];
@@ -297,11 +311,50 @@ export type ObjectRelations = {
   childObjectIds: Array<Scalars['UUID']>;
 };
 
+/** Last row of the previous page */
+export type OnDemandCursor = {
+  /** Object IDs of the row */
+  objectIds: Array<Scalars['UUID']>;
+  /** Value of the ordering field. Required when `orderBy` is present */
+  value?: Maybe<Scalars['JSON']>;
+};
+
+export type OnDemandOrdering = {
+  /** Name of the view's field */
+  field: Scalars['String'];
+  descending?: Scalars['Boolean'];
+};
+
 export type OnDemandViewRequest = {
   /** View's UUID */
   viewId: Scalars['UUID'];
   /** Schemas with objects. This collection is treated like a hash-map with `schemaId` as a key, therefore `schemaId` should be unique per request. */
   schemas: Array<Schema>;
+  /** Additional filter, applied together with view's filters */
+  filter?: Maybe<Scalars['JSON']>;
+  /** Fields to return. All fields are returned if not present */
+  fields?: Maybe<Array<Scalars['String']>>;
+  /** Field used to order rows. Rows with equal values are ordered by object IDs */
+  orderBy?: Maybe<OnDemandOrdering>;
+  /** Maximal number of rows to return */
+  limit?: Maybe<Scalars['Int']>;
+  /** Number of rows to skip. Cannot be used together with `after` */
+  offset?: Maybe<Scalars['Int']>;
+  /** Return rows following given row. Cannot be used together with `offset` */
+  after?: Maybe<OnDemandCursor>;
+};
+
+/** Last object of the previous page */
+export type PageCursor = {
+  objectId: Scalars['UUID'];
+  /** Value of the ordering field. Required when `orderBy` is present */
+  value?: Maybe<Scalars['JSON']>;
+};
+
+export type PageOrdering = {
+  /** JSON pointer to the field */
+  path: Scalars['String'];
+  descending?: Scalars['Boolean'];
 };
 
 export type QueryRoot = {
@@ -318,6 +371,11 @@ export type QueryRoot = {
   objects: Array<CdlObject>;
   /** Return a map of all objects (keyed by ID) in a schema from the query router */
   schemaObjects: Array<CdlObject>;
+  /**
+   * Return single page of objects in a schema, filtered and ordered by their payloads.
+   * Supported only by document storage backed by Postgres
+   */
+  schemaObjectsPage: ObjectPage;
   /** Return schema `parent` is in `relation_id` relation with */
   relation?: Maybe<Scalars['UUID']>;
   /** Return all relations `parent` is in */
@@ -360,6 +418,12 @@ export type QueryRootSchemaObjectsArgs = {
 };
 
 
+export type QueryRootSchemaObjectsPageArgs = {
+  schemaId: Scalars['UUID'];
+  query: SchemaPageQuery;
+};
+
+
 export type QueryRootRelationArgs = {
   relationId: Scalars['UUID'];
   parentSchemaId: Scalars['UUID'];
@@ -444,6 +508,17 @@ export type SchemaFieldFilter = {
   fieldPath: Scalars['String'];
 };
 
+export type SchemaPageQuery = {
+  /** Maximal number of objects in the page, all objects are returned when missing */
+  limit?: Maybe<Scalars['Int']>;
+  /** Return objects following given object */
+  after?: Maybe<PageCursor>;
+  /** Field used to order objects. Objects with equal values are ordered by their IDs */
+  orderBy?: Maybe<PageOrdering>;
+  /** Condition on payloads, eg. `{"and": [{"equals": {"path": "/a", "value": 1}}, {"exists": {"path": "/b"}}]}` */
+  filter?: Maybe<Scalars['JSON']>;
+};
+
 export type SchemaRelation = {
   __typename?: 'SchemaRelation';
   relationId: Scalars['UUID'];